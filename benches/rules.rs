@@ -0,0 +1,43 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use std::time::{Duration, Instant};
+use smartcalc::SmartCalc;
+
+/* Lines of a document using different rules, run with `cargo bench --bench rules` */
+const DOCUMENT: &str = "12 + 34 * 2
+10% of 200
+$1.250 to eur
+3 hours 20 minutes
+today + 2 weeks
+5 km to m
+10:30 am gmt+3 to gmt
+salary = $5.000
+salary - 15%
+120 is 20% of what
+1 jan 2021 - 3 days
+";
+
+fn measure(name: &str, calc: &SmartCalc, language: &str, data: &str, iterations: u32) {
+    calc.execute(language, data);
+
+    let mut total = Duration::ZERO;
+    for _ in 0..iterations {
+        let start = Instant::now();
+        calc.execute(language, data);
+        total += start.elapsed();
+    }
+
+    let lines = data.lines().count() as u32 * iterations;
+    println!("{:<12} {:>10.2?} per line, {:>10.2?} per document", name, total / lines, total / iterations);
+}
+
+fn main() {
+    let calc = SmartCalc::default();
+    measure("plain", &calc, "en", &"12 + 34 * 2\n(5 - 3) / 4\n".repeat(50), 20);
+    measure("rules", &calc, "en", &DOCUMENT.repeat(10), 20);
+    measure("text", &calc, "en", &"monthly rent for the flat = 1500\nshared with my brother / 2\n".repeat(50), 20);
+}
@@ -6,9 +6,28 @@
 
 extern crate smartcalc;
 
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal};
+use std::path::PathBuf;
+use std::process;
+
+use rustyline::Editor;
+use rustyline::error::ReadlineError;
 use smartcalc::*;
 
-fn main() {
+const USAGE: &str = "Usage: smartcalc [--config <file>] [eval <file>]";
+const HISTORY_FILE: &str = ".smartcalc_history";
+
+/* Configuration given with --config or the default one under the user config directory, if it exists */
+fn config_path(config: Option<&str>) -> Option<PathBuf> {
+    match config {
+        Some(config) => Some(PathBuf::from(config)),
+        None => dirs::config_dir().map(|directory| directory.join("smartcalc").join("config.json")).filter(|path| path.exists())
+    }
+}
+
+fn create_app(config: Option<PathBuf>) -> Result<SmartCalc, String> {
     use chrono_tz::Tz;
     use chrono_tz::OffsetName;
     use chrono::{TimeZone, Local};
@@ -25,27 +44,216 @@ fn main() {
         None => "UTC".to_string()
     };
 
-    let test_data = r"1 Stone to kg".to_string();
     let mut app = SmartCalc::default();
-    
-    //app.add_rule("en".to_string(), vec!["{TEXT:soyad:erhan}".to_string()], test1 as RuleFunction);
     app.set_decimal_seperator(locale.decimal().to_string());
     app.set_thousand_separator(locale.separator().to_string());
     app.set_timezone(timezone).unwrap();
 
-    let language = "en".to_string();
-    let results = app.execute(language, test_data);
-    
-    for result in results.lines.iter() {
-        match result {
+    /* Rules, currencies and aliases of the user are merged over the embedded ones */
+    if let Some(path) = config {
+        let json_data = fs::read_to_string(&path).map_err(|error| format!("{} could not be read. ({})", path.display(), error))?;
+        app.merge_json(&json_data).map_err(|error| format!("{} is not valid. ({})", path.display(), error))?;
+    }
+
+    Ok(app)
+}
+
+fn ansi_color(ui_type: &UiTokenType) -> Option<&'static str> {
+    match ui_type {
+        UiTokenType::Number => Some("33"),
+        UiTokenType::Operator => Some("36"),
+        UiTokenType::VariableDefination | UiTokenType::VariableUse => Some("35"),
+        UiTokenType::Comment => Some("90"),
+        UiTokenType::Date | UiTokenType::DateTime | UiTokenType::Month | UiTokenType::Duration => Some("32"),
+        UiTokenType::Unit | UiTokenType::Symbol1 | UiTokenType::Symbol2 => Some("34"),
+        UiTokenType::Keyword => Some("1"),
+        UiTokenType::Text => None
+    }
+}
+
+/* Token locations are character based, characters outside of the tokens are written as they are */
+fn colorize(line: &str, ui_tokens: &[UiToken]) -> String {
+    let chars = line.chars().collect::<Vec<_>>();
+    let mut tokens = ui_tokens.iter().filter(|token| token.end <= chars.len()).collect::<Vec<_>>();
+    tokens.sort_by_key(|token| token.start);
+
+    let mut colored = String::new();
+    let mut position = 0;
+    for token in tokens {
+        if token.start < position {
+            continue;
+        }
+
+        colored.extend(&chars[position..token.start]);
+        let text = chars[token.start..token.end].iter().collect::<String>();
+        match ansi_color(&token.ui_type) {
+            Some(color) => colored.push_str(&format!("\x1b[{}m{}\x1b[0m", color, text)),
+            None => colored.push_str(&text)
+        };
+        position = token.end;
+    }
+
+    colored.extend(&chars[position..]);
+    colored
+}
+
+/* Every input line is printed with its result in a right column, returns false if a line failed */
+fn print_document(app: &SmartCalc, language: &str, data: &str, color: bool) -> bool {
+    let results = app.execute(language, data);
+    let width = data.lines().map(|line| line.chars().count()).max().unwrap_or_default();
+    let mut success = true;
+
+    for (line, result) in data.lines().zip(results.lines.iter()) {
+        let input = match (color, result) {
+            (true, Some(result)) => colorize(line, &result.ui_tokens),
+            _ => line.to_string()
+        };
+
+        let output = match result {
             Some(result) => match &result.result {
-                Ok(output) => {
-                    println!("{:?}", result.ui_tokens);
-                    println!("{}", output.output)
-                },
-                Err(error) => println!("Error : {}", error)
+                Ok(output) => output.output.to_string(),
+                Err(error) => {
+                    success = false;
+                    match color {
+                        true => format!("\x1b[31mError : {}\x1b[0m", error),
+                        false => format!("Error : {}", error)
+                    }
+                }
             },
-            None => println!("No query")
+            None => String::new()
+        };
+
+        /* Padding is calculated from the plain text, escape codes have no width */
+        match output.is_empty() {
+            true => println!("{}", input),
+            false => println!("{}{}  {}", input, " ".repeat(width - line.chars().count()), output)
+        };
+    }
+
+    success
+}
+
+fn eval_file(app: &SmartCalc, path: &str) -> i32 {
+    let data = match fs::read_to_string(path) {
+        Ok(data) => data,
+        Err(error) => {
+            eprintln!("{} could not be read. ({})", path, error);
+            return 2;
         }
+    };
+
+    match print_document(app, "en", &data, io::stdout().is_terminal()) {
+        true => 0,
+        false => 1
     }
 }
+
+fn print_variables(app: &SmartCalc, session: &Session) {
+    for (name, value, _, _) in session.variables() {
+        let type_name = match &*value {
+            SmartCalcAstType::Item(item) => item.type_name(),
+            _ => "NONE"
+        };
+        println!("{} = {} ({})", name, app.format_result(session, value.clone(), &FormatOptions::default()), type_name.to_lowercase());
+    }
+}
+
+/* Line is parsed in a new session, so the variables of the REPL are not changed */
+fn print_syntax_trees(app: &SmartCalc, language: &str, line: &str) {
+    match app.execute(language, line).lines.first() {
+        Some(Some(result)) => {
+            for syntax_tree in result.syntax_trees.iter() {
+                println!("{:#?}", syntax_tree);
+            }
+        },
+        _ => println!("No query")
+    }
+}
+
+fn history_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|home| home.join(HISTORY_FILE))
+}
+
+/* Lines are executed one by one in the same session, commands start with ':' */
+fn repl(app: &SmartCalc) -> i32 {
+    let language = "en";
+    let color = io::stdout().is_terminal();
+    let mut session = Session::new();
+    let mut editor = Editor::<()>::new();
+    let history = history_path();
+
+    if let Some(history) = &history {
+        let _ = editor.load_history(history);
+    }
+
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) | Err(ReadlineError::Eof) => break,
+            Err(error) => {
+                eprintln!("Input could not be read. ({})", error);
+                break;
+            }
+        };
+
+        if !line.trim().is_empty() {
+            editor.add_history_entry(line.as_str());
+        }
+
+        match line.trim() {
+            ":quit" | ":q" => break,
+            ":vars" => print_variables(app, &session),
+            command if command.starts_with(":ast ") => print_syntax_trees(app, language, &command[5..]),
+            command if command.starts_with(':') => println!("Unknown command. (:vars, :ast <line>, :quit)"),
+            _ => if let Some(result) = app.execute_line(&mut session, language, line.as_str()) {
+                match &result.result {
+                    Ok(output) => println!("{}", output.output),
+                    Err(error) if color => println!("\x1b[31mError : {}\x1b[0m", error),
+                    Err(error) => println!("Error : {}", error)
+                }
+            }
+        };
+    }
+
+    if let Some(history) = &history {
+        if let Err(error) = editor.save_history(history) {
+            eprintln!("History could not be saved. ({})", error);
+        }
+    }
+
+    0
+}
+
+fn main() {
+    let mut args = env::args().skip(1).collect::<Vec<_>>();
+    let mut config = None;
+
+    if let Some(index) = args.iter().position(|arg| arg == "--config") {
+        if index + 1 >= args.len() {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+
+        config = Some(args.remove(index + 1));
+        args.remove(index);
+    }
+
+    let app = match create_app(config_path(config.as_deref())) {
+        Ok(app) => app,
+        Err(error) => {
+            eprintln!("{}", error);
+            process::exit(2);
+        }
+    };
+
+    let code = match args.iter().map(|arg| arg.as_str()).collect::<Vec<_>>()[..] {
+        [] => repl(&app),
+        ["eval", path] => eval_file(&app, path),
+        _ => {
+            eprintln!("{}", USAGE);
+            2
+        }
+    };
+
+    process::exit(code);
+}
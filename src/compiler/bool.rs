@@ -0,0 +1,114 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::any::{Any, TypeId};
+use alloc::rc::Rc;
+use alloc::string::{ToString, String};
+use crate::session::Session;
+use crate::config::SmartCalcConfig;
+use crate::types::TokenType;
+use super::{DataItem, OperationType, UnaryType};
+
+#[derive(Debug)]
+
+pub struct BoolItem(pub bool);
+
+impl DataItem for BoolItem {
+    fn as_token_type(&self) -> TokenType {
+        TokenType::Bool(self.0)
+    }
+    fn is_same(&self, other: &dyn Any) -> bool {
+        match other.downcast_ref::<bool>() {
+            Some(value) => *value == self.0,
+            None => false
+        }
+    }
+    fn as_any(&self) -> &dyn Any { self }
+    fn calculate(&self, _: &SmartCalcConfig, _: bool, _: &dyn DataItem, _: OperationType) -> Option<Rc<dyn DataItem>> {
+        None
+    }
+    fn get_number(&self, _: &dyn DataItem) -> f64 {
+       self.get_underlying_number()
+    }
+    fn get_underlying_number(&self) -> f64 {
+        match self.0 {
+            true => 1.0,
+            false => 0.0
+        }
+    }
+    fn type_name(&self) -> &'static str { "BOOL" }
+    fn type_id(&self) -> TypeId { TypeId::of::<BoolItem>() }
+    fn print(&self, config: &SmartCalcConfig, session: &Session) -> String {
+        let key = self.0.to_string();
+        let format = match config.format.get(&session.get_language()) {
+            Some(formats) => formats,
+            _ => match config.format.get("en") {
+                Some(formats) => formats,
+                _ => return key
+            }
+        };
+
+        match format.boolean.get(&key) {
+            Some(text) => text.to_string(),
+            None => key
+        }
+    }
+    fn unary(&self, _: UnaryType) -> Rc<dyn DataItem> {
+        Rc::new(Self(self.0))
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn bool_test() {
+    use crate::compiler::number::NumberItem;
+    use crate::types::NumberType;
+    let config = SmartCalcConfig::default();
+    let mut session = Session::default();
+
+    assert_eq!(BoolItem(true).print(&config, &session), "true".to_string());
+    assert_eq!(BoolItem(false).print(&config, &session), "false".to_string());
+
+    session.set_language("tr".to_string());
+    assert_eq!(BoolItem(true).print(&config, &session), "doğru".to_string());
+    assert_eq!(BoolItem(false).print(&config, &session), "yanlış".to_string());
+
+    assert!(BoolItem(true).calculate(&config, true, &NumberItem(1.0, NumberType::Decimal), OperationType::Add).is_none());
+}
+
+#[cfg(test)]
+#[test]
+fn comparison_test() {
+    use core::ops::Deref;
+    use alloc::string::ToString;
+    use crate::compiler::Interpreter;
+    use crate::compiler::number::NumberItem;
+    use crate::compiler::money::MoneyItem;
+    use crate::compiler::text::TextItem;
+    use crate::types::{ComparisonType, NumberType, SmartCalcAstType};
+    let config = SmartCalcConfig::default();
+    let session = Session::default();
+    let item = |item: Rc<dyn DataItem>| Rc::new(SmartCalcAstType::Item(item));
+    let compare = |left: Rc<SmartCalcAstType>, operator: ComparisonType, right: Rc<SmartCalcAstType>| Interpreter::execute(&config, Rc::new(SmartCalcAstType::Comparison { left, operator, right }), &session)
+        .map(|result| match result.deref() {
+            SmartCalcAstType::Item(item) => item.print(&config, &session),
+            _ => "".to_string()
+        });
+    let number = |value: f64| item(Rc::new(NumberItem(value, NumberType::Decimal)));
+
+    assert_eq!(compare(number(2.0), ComparisonType::Less, number(3.0)), Ok("true".to_string()));
+    assert_eq!(compare(number(2.0), ComparisonType::GreaterOrEqual, number(3.0)), Ok("false".to_string()));
+    assert_eq!(compare(number(0.1 + 0.2), ComparisonType::Equal, number(0.3)), Ok("true".to_string()));
+
+    /* Money is converted before the comparison */
+    let usd = item(Rc::new(MoneyItem(100.0, config.get_currency("usd".to_string()).unwrap())));
+    let try_money = item(Rc::new(MoneyItem(100.0, config.get_currency("try".to_string()).unwrap())));
+    assert_eq!(compare(usd, ComparisonType::Greater, try_money), Ok("true".to_string()));
+
+    assert_eq!(compare(item(Rc::new(BoolItem(true))), ComparisonType::NotEqual, item(Rc::new(BoolItem(false)))), Ok("true".to_string()));
+    assert_eq!(compare(item(Rc::new(TextItem("a".to_string()))), ComparisonType::Equal, item(Rc::new(TextItem("a".to_string())))), Ok("true".to_string()));
+    assert_eq!(compare(item(Rc::new(BoolItem(true))), ComparisonType::Less, number(1.0)), Err("Values can not be compared. (bool, number)".to_string()));
+}
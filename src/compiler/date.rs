@@ -5,13 +5,15 @@
  */
 
 use core::any::{Any, TypeId};
+use core::convert::TryFrom;
 use alloc::rc::Rc;
 use alloc::string::ToString;
 use alloc::string::String;
-use chrono::{Datelike, Duration, NaiveDate, Utc, TimeZone};
+use chrono::{Datelike, Duration, Months, NaiveDate, TimeZone};
 use crate::session::Session;
 use crate::compiler::duration::DurationItem;
 use crate::config::SmartCalcConfig;
+use crate::constants::{DurationFormatType, JsonFormat};
 use crate::formatter::{MONTH, YEAR, get_month_info, left_padding, uppercase_first_letter};
 use crate::types::{TokenType, TimeOffset};
 
@@ -30,12 +32,47 @@ impl DateItem {
         self.1.clone()
     }
     
+    /* Calendar parts keep the sign of the duration */
     fn get_month_from_duration(&self, duration: Duration) -> i64 {
-        duration.num_seconds().abs() / MONTH
+        duration.num_seconds() / MONTH
     }
 
     fn get_year_from_duration(&self, duration: Duration) -> i64 {
-        duration.num_seconds().abs() / YEAR
+        duration.num_seconds() / YEAR
+    }
+
+    fn add_months(date: NaiveDate, months: i64) -> Option<NaiveDate> {
+        match months.is_negative() {
+            true => date.checked_sub_months(Months::new(u32::try_from(-months).ok()?)),
+            false => date.checked_add_months(Months::new(u32::try_from(months).ok()?))
+        }
+    }
+
+    /* Days are written up to two weeks, weeks after that, 10 days ago and 3 weeks ago */
+    fn relative_format(&self, config: &SmartCalcConfig, format: &JsonFormat) -> Option<String> {
+        let threshold = i64::from(config.relative_date_days?);
+        let days = (self.0 - config.get_today()).num_days();
+        if days.abs() > threshold {
+            return None;
+        }
+
+        let name = match days {
+            0 => "today",
+            1 => "tomorrow",
+            -1 => "yesterday",
+            _ if days > 0 => "relative_future",
+            _ => "relative_past"
+        };
+
+        let template = format.date.get(name)?;
+        let (count, replace_str, duration_type) = match days.abs() {
+            days if days < 14 => (days, "{day}", DurationFormatType::Day),
+            days => (days / 7, "{week}", DurationFormatType::Week)
+        };
+
+        let mut duration = String::new();
+        DurationItem::duration_formatter(&format.duration, format.plural, &mut duration, replace_str, count as f64, &count.to_string(), &duration_type);
+        Some(template.replace("{duration}", duration.trim()))
     }
 }
 
@@ -60,52 +97,32 @@ impl DataItem for DateItem {
         let mut date = self.0;
         let mut duration = other.as_any().downcast_ref::<DurationItem>()?.get_duration();
 
+        /* Calendar parts are applied first, the remaining seconds are added after */
+        let mut months = 0;
+        match self.get_year_from_duration(duration) {
+            0 => (),
+            n => {
+                months   += n * 12;
+                duration = Duration::seconds(duration.num_seconds() - (YEAR * n))
+            }
+        };
+
+        match self.get_month_from_duration(duration) {
+            0 => (),
+            n => {
+                months   += n;
+                duration = Duration::seconds(duration.num_seconds() - (MONTH * n))
+            }
+        };
+
         match operation_type {
             OperationType::Add => {
-                match self.get_year_from_duration(duration) {
-                    0 => (),
-                    n => {
-                        let years_diff = date.year() + n as i32;
-                        date     = NaiveDate::from_ymd(years_diff as i32, date.month() as u32, date.day());
-                        duration = Duration::seconds(duration.num_seconds() - (YEAR * n))
-                    }
-                };
-
-                match self.get_month_from_duration(duration) {
-                    0 => (),
-                    n => {
-                        let years_diff = (date.month() + n as u32) / 12;
-                        let month = (date.month() + n as u32) % 12;
-                        date     = NaiveDate::from_ymd(date.year() + years_diff as i32, month as u32, date.day());
-                        duration = Duration::seconds(duration.num_seconds() - (MONTH * n))
-                    }
-                };
+                date = DateItem::add_months(date, months)?;
                 Some(Rc::new(DateItem(date + duration, self.1.clone())))
             },
 
             OperationType::Sub => {
-                match self.get_year_from_duration(duration) {
-                    0 => (),
-                    n => {
-                        let years_diff = date.year() - n as i32;
-                        date     = NaiveDate::from_ymd(years_diff as i32, date.month() as u32, date.day());
-                        duration = Duration::seconds(duration.num_seconds() - (YEAR * n))
-                    }
-                };
-
-                match self.get_month_from_duration(duration) {
-                    0 => (),
-                    n => {
-                        let years = date.year() - (n as i32 / 12);
-                        let mut months = date.month() as i32 - (n as i32 % 12);
-                        if months < 0 {
-                            months += 12;
-                        }
-
-                        date = NaiveDate::from_ymd(years as i32, months as u32, date.day());
-                        duration = Duration::seconds(duration.num_seconds() - (MONTH * n))
-                    }
-                };
+                date = DateItem::add_months(date, -months)?;
                 Some(Rc::new(DateItem(date - duration, self.1.clone())))
             },
             _ => None
@@ -129,7 +146,11 @@ impl DataItem for DateItem {
             }
         };
         
-        let date_format = match self.0.year() == Utc::now().date().year() {
+        if let Some(relative) = self.relative_format(config, format) {
+            return relative;
+        }
+
+        let date_format = match self.0.year() == config.get_today().year() {
             true => format.date.get("current_year"),
             false => format.date.get("full_date")
         };
@@ -170,18 +191,46 @@ fn date_test() {
     let config = SmartCalcConfig::default();
     let session = Session::default();
 
-    assert_eq!(DateItem(NaiveDate::from_ymd(2020, 1, 1), config.get_time_offset()).print(&config, &session), "1 Jan 2020".to_string());
+    assert_eq!(DateItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(), config.get_time_offset()).print(&config, &session), "1 Jan 2020".to_string());
 
-    let left = DateItem(NaiveDate::from_ymd(2020, 1, 1), config.get_time_offset());
-    let right = DateItem(NaiveDate::from_ymd(2020, 1, 1), config.get_time_offset());
+    let left = DateItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(), config.get_time_offset());
+    let right = DateItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(), config.get_time_offset());
     let result = left.calculate(&config, true, &right, OperationType::Sub);
     
     assert!(result.is_none());
 
-    let left = DateItem(NaiveDate::from_ymd(2020, 1, 1), config.get_time_offset());
+    let left = DateItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap(), config.get_time_offset());
     let right = DurationItem(Duration::hours(24 * 20));
     let result = left.calculate(&config, true, &right, OperationType::Add);
     
     assert!(result.is_some());
     assert_eq!(result.unwrap().print(&config, &session), "21 Jan 2020".to_string());
-}
\ No newline at end of file
+}
+#[cfg(test)]
+#[test]
+fn date_month_clamp_test() {
+    use crate::compiler::date::DateItem;
+    use crate::compiler::duration::DurationItem;
+    use crate::config::SmartCalcConfig;
+    let config = SmartCalcConfig::default();
+    let session = Session::default();
+
+    let left = DateItem(NaiveDate::from_ymd_opt(2024, 1, 31).unwrap(), config.get_time_offset());
+    let right = DurationItem(Duration::seconds(MONTH));
+    let result = left.calculate(&config, true, &right, OperationType::Add);
+    assert_eq!(result.unwrap().print(&config, &session), "29 Feb 2024".to_string());
+
+    let left = DateItem(NaiveDate::from_ymd_opt(2023, 3, 31).unwrap(), config.get_time_offset());
+    let result = left.calculate(&config, true, &right, OperationType::Sub);
+    assert_eq!(result.unwrap().print(&config, &session), "28 Feb 2023".to_string());
+
+    let left = DateItem(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(), config.get_time_offset());
+    let right = DurationItem(Duration::seconds(YEAR));
+    let result = left.calculate(&config, true, &right, OperationType::Add);
+    assert_eq!(result.unwrap().print(&config, &session), "28 Feb 2025".to_string());
+
+    let left = DateItem(NaiveDate::from_ymd_opt(2023, 12, 15).unwrap(), config.get_time_offset());
+    let right = DurationItem(Duration::seconds(MONTH));
+    let result = left.calculate(&config, true, &right, OperationType::Add);
+    assert_eq!(result.unwrap().print(&config, &session), "15 Jan 2024".to_string());
+}
@@ -9,7 +9,7 @@ use alloc::rc::Rc;
 use alloc::string::ToString;
 use alloc::string::String;
 use alloc::format;
-use chrono::{Datelike, NaiveDateTime, Timelike, Utc};
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike};
 use chrono::TimeZone;
 use crate::session::Session;
 use crate::compiler::duration::DurationItem;
@@ -33,6 +33,41 @@ impl DateTimeItem {
     }
 }
 
+#[cfg(feature = "tz")]
+fn shift_in_region(config: &SmartCalcConfig, date_time: NaiveDateTime, offset: &TimeOffset, duration: Duration) -> Option<(NaiveDateTime, TimeOffset)> {
+    use chrono::{LocalResult, Offset};
+    use chrono_tz::{OffsetName, Tz};
+
+    let region = config.timezone_regions.get(&offset.name)?.parse::<Tz>().ok()?;
+
+    /* Whole days keep the wall clock time, other durations move the instant */
+    let shifted = match duration.num_seconds() % 86_400 == 0 {
+        true => match region.from_local_datetime(&(region.from_utc_datetime(&date_time).naive_local() + duration)) {
+            LocalResult::Single(local) | LocalResult::Ambiguous(local, _) => local.naive_utc(),
+            LocalResult::None => date_time + duration
+        },
+        false => date_time + duration
+    };
+
+    let region_offset = region.offset_from_utc_datetime(&shifted);
+    Some((shifted, TimeOffset {
+        name: region_offset.abbreviation().to_string(),
+        offset: region_offset.fix().local_minus_utc() / 60
+    }))
+}
+
+#[cfg(not(feature = "tz"))]
+fn shift_in_region(_: &SmartCalcConfig, _: NaiveDateTime, _: &TimeOffset, _: Duration) -> Option<(NaiveDateTime, TimeOffset)> {
+    None
+}
+
+pub(crate) fn shift_date_time(config: &SmartCalcConfig, date_time: NaiveDateTime, offset: &TimeOffset, duration: Duration) -> (NaiveDateTime, TimeOffset) {
+    match shift_in_region(config, date_time, offset, duration) {
+        Some(shifted) => shifted,
+        None => (date_time + duration, offset.clone())
+    }
+}
+
 impl DataItem for DateTimeItem {
     fn as_token_type(&self) -> TokenType {
         TokenType::DateTime(self.0, self.1.clone())
@@ -45,19 +80,19 @@ impl DataItem for DateTimeItem {
     }
     fn as_any(&self) -> &dyn Any { self }
     
-    fn calculate(&self, _: &SmartCalcConfig, _: bool, other: &dyn DataItem, operation_type: OperationType) -> Option<Rc<dyn DataItem>> {
+    fn calculate(&self, config: &SmartCalcConfig, _: bool, other: &dyn DataItem, operation_type: OperationType) -> Option<Rc<dyn DataItem>> {
         /* If both item is money and current money is on left side, skip calculation */
         if other.type_name() != "DURATION" {
             return None;
         }
 
-        let date = self.0;
         let duration = other.as_any().downcast_ref::<DurationItem>()?.get_duration();
-        match operation_type {
-            OperationType::Add => Some(Rc::new(DateTimeItem(date + duration, self.1.clone()))),
-            OperationType::Sub => Some(Rc::new(DateTimeItem(date - duration, self.1.clone()))),
-            _ => None
-        }
+        let (date_time, offset) = match operation_type {
+            OperationType::Add => shift_date_time(config, self.0, &self.1, duration),
+            OperationType::Sub => shift_date_time(config, self.0, &self.1, -duration),
+            _ => return None
+        };
+        Some(Rc::new(DateTimeItem(date_time, offset)))
     }
     
     fn get_number(&self, _: &dyn DataItem) -> f64 {
@@ -80,7 +115,7 @@ impl DataItem for DateTimeItem {
         let tz_offset = chrono::FixedOffset::east(self.1.offset * 60);
         let datetime = tz_offset.from_utc_datetime(&self.0);
         
-        let date_format = match datetime.year() == Utc::now().date().year() {
+        let date_format = match datetime.year() == config.get_today().year() {
             true => format.date.get("current_year_with_time"),
             false => format.date.get("full_date_time")
         };
@@ -126,15 +161,15 @@ fn date_time_test() {
     let config = SmartCalcConfig::default();
     let session = Session::default();
 
-    assert_eq!(DateTimeItem(NaiveDate::from_ymd(2020, 1, 1).and_hms(1, 12, 13), config.get_time_offset()).print(&config, &session), "1 Jan 2020 01:12:13 UTC".to_string());
+    assert_eq!(DateTimeItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(1, 12, 13).unwrap(), config.get_time_offset()).print(&config, &session), "1 Jan 2020 01:12:13 UTC".to_string());
 
-    let left = DateTimeItem(NaiveDate::from_ymd(2020, 1, 1).and_hms(1, 1, 1), config.get_time_offset());
-    let right = DateTimeItem(NaiveDate::from_ymd(2020, 1, 1).and_hms(0, 0, 0), config.get_time_offset());
+    let left = DateTimeItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(1, 1, 1).unwrap(), config.get_time_offset());
+    let right = DateTimeItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(0, 0, 0).unwrap(), config.get_time_offset());
     let result = left.calculate(&config, true, &right, OperationType::Sub);
     
     assert!(result.is_none());
 
-    let left = DateTimeItem(NaiveDate::from_ymd(2020, 1, 1).and_hms(1, 0, 0), config.get_time_offset());
+    let left = DateTimeItem(NaiveDate::from_ymd_opt(2020, 1, 1).unwrap().and_hms_opt(1, 0, 0).unwrap(), config.get_time_offset());
     let right = DurationItem(Duration::hours(1));
     let result = left.calculate(&config, true, &right, OperationType::Sub);
     
@@ -142,3 +177,32 @@ fn date_time_test() {
     assert!(result.is_some());
     assert_eq!(result.unwrap().print(&config, &session), "1 Jan 2020 00:00:00 UTC".to_string());
 }
+
+#[cfg(all(test, feature = "tz"))]
+#[test]
+fn date_time_dst_test() {
+    use chrono::NaiveDate;
+    use crate::compiler::duration::DurationItem;
+    use crate::config::SmartCalcConfig;
+
+    let config = SmartCalcConfig::default();
+    let session = Session::default();
+    let est = TimeOffset { name: "EST".to_string(), offset: -300 };
+
+    /* 9 Mar 2024 23:00 EST, clocks jump forward at 02:00 on 10 Mar */
+    let left = DateTimeItem(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap().and_hms_opt(4, 0, 0).unwrap(), est.clone());
+    let result = left.calculate(&config, true, &DurationItem(Duration::hours(5)), OperationType::Add);
+    assert_eq!(result.unwrap().print(&config, &session), "10 Mar 2024 05:00:00 EDT".to_string());
+
+    let result = left.calculate(&config, true, &DurationItem(Duration::days(1)), OperationType::Add);
+    assert_eq!(result.unwrap().print(&config, &session), "10 Mar 2024 23:00:00 EDT".to_string());
+
+    let left = DateTimeItem(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap().and_hms_opt(9, 0, 0).unwrap(), TimeOffset { name: "EDT".to_string(), offset: -240 });
+    let result = left.calculate(&config, true, &DurationItem(Duration::hours(5)), OperationType::Sub);
+    assert_eq!(result.unwrap().print(&config, &session), "9 Mar 2024 23:00:00 EST".to_string());
+
+    /* Offsets without a known region keep the old fixed offset behaviour */
+    let left = DateTimeItem(NaiveDate::from_ymd_opt(2024, 3, 10).unwrap().and_hms_opt(4, 0, 0).unwrap(), TimeOffset { name: "GMT-5".to_string(), offset: -300 });
+    let result = left.calculate(&config, true, &DurationItem(Duration::hours(5)), OperationType::Add);
+    assert_eq!(result.unwrap().print(&config, &session), "10 Mar 2024 04:00:00 GMT-5".to_string());
+}
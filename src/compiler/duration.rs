@@ -6,25 +6,41 @@
 
 use core::any::{Any, TypeId};
 use alloc::rc::Rc;
+use alloc::format;
 use alloc::string::ToString;
 use alloc::string::String;
-use chrono::{Duration, NaiveDateTime, Utc};
+use chrono::{Duration, NaiveDateTime};
 use crate::session::Session;
 use crate::config::SmartCalcConfig;
-use crate::constants::DurationFormatType;
+use crate::config::{DurationGranularity, DurationStyle, NegativeDurationStyle};
 use crate::constants::JsonFormat;
+use crate::constants::DurationFormat;
+use crate::constants::DurationFormatType;
+use crate::constants::{PluralCategory, PluralRule};
 use crate::formatter::DAY;
 use crate::formatter::HOUR;
 use crate::formatter::MINUTE;
 use crate::formatter::MONTH;
 use crate::formatter::WEEK;
 use crate::formatter::YEAR;
+use crate::formatter::{format_number, remove_trailing_zeros};
 use crate::types::TokenType;
 use core::write;
 use alloc::fmt::Write;
 
 use super::{DataItem, OperationType, UnaryType};
 
+/* Units in printing order, from the highest to the lowest */
+const DURATION_UNITS: [(i64, &str, DurationFormatType); 7] = [
+    (YEAR, "{year}", DurationFormatType::Year),
+    (MONTH, "{month}", DurationFormatType::Month),
+    (WEEK, "{week}", DurationFormatType::Week),
+    (DAY, "{day}", DurationFormatType::Day),
+    (HOUR, "{hour}", DurationFormatType::Hour),
+    (MINUTE, "{minute}", DurationFormatType::Minute),
+    (1, "{second}", DurationFormatType::Second)
+];
+
 #[derive(Debug)]
 
 pub struct DurationItem(pub Duration);
@@ -34,50 +50,84 @@ impl DurationItem {
         self.0
     }
 
-    fn duration_formatter(format: &JsonFormat, buffer: &mut String, replace_str: &str, duration: i64, duration_type: DurationFormatType) {
-        for format_item in format.duration.iter() {
-            if format_item.duration_type == duration_type && format_item.count.trim().parse::<i64>().is_ok() && format_item.count.trim().parse::<i64>().unwrap_or_default() == duration{
-                write!(buffer, "{} ", format_item.format.to_string().replace(replace_str, &duration.to_string())).unwrap_or_default();
-                return;
-            }
-        }
-    
-        for format_item in format.duration.iter() {
-            if format_item.duration_type == duration_type && format_item.count.trim().parse::<i64>().is_err() {
-                write!(buffer, "{} ", format_item.format.to_string().replace(replace_str, &duration.to_string())).unwrap_or_default();
-                return;
-            }
-        }
-    
-        write!(buffer, "{} ", duration.to_string()).unwrap_or_default();
+    /* Exact counts are used first, then the plural category of the count, then the generic format */
+    pub(crate) fn duration_formatter(formats: &[DurationFormat], plural: PluralRule, buffer: &mut String, replace_str: &str, duration: f64, duration_text: &str, duration_type: &DurationFormatType) {
+        let category = plural.category(duration);
+        let type_formats = || formats.iter().filter(|format_item| &format_item.duration_type == duration_type);
+        let format_item = type_formats().find(|format_item| format_item.count.trim().parse::<i64>().is_ok_and(|count| count as f64 == duration))
+            .or_else(|| type_formats().find(|format_item| PluralCategory::from_name(&format_item.count) == Some(category)))
+            .or_else(|| type_formats().find(|format_item| format_item.count.trim().parse::<i64>().is_err() && PluralCategory::from_name(&format_item.count).is_none()));
+
+        match format_item {
+            Some(format_item) => write!(buffer, "{} ", format_item.format.to_string().replace(replace_str, duration_text)).unwrap_or_default(),
+            None => write!(buffer, "{} ", duration_text).unwrap_or_default()
+        };
     }
 
+    /* Highest unit of the duration, negative durations give a negative number */
     fn get_high_duration_number(&self) -> i64 {
         let duration_info = self.0.num_seconds().abs();
-        if duration_info >= YEAR {
-            return duration_info / YEAR;
-        }
+        let number = if duration_info >= YEAR {
+            duration_info / YEAR
+        } else if duration_info >= MONTH {
+            (duration_info / MONTH) % 30
+        } else if duration_info >= DAY {
+            duration_info / DAY
+        } else if duration_info >= HOUR {
+            (duration_info / HOUR) % 24
+        } else if duration_info >= MINUTE {
+            (duration_info / MINUTE) % 60
+        } else {
+            duration_info
+        };
 
-        if duration_info >= MONTH {
-            return (duration_info / MONTH) % 30;
-        }
+        number * self.0.num_seconds().signum()
+    }
 
-        if duration_info >= DAY {
-            return duration_info / DAY;
-        }
+    /* Length of the duration without its sign */
+    fn format_length(&self, config: &SmartCalcConfig, session: &Session, format: &JsonFormat) -> String {
+        let options = session.format_options();
+        let mut duration = self.0.num_seconds().abs();
+        let formats = match options.duration_style(config) {
+            DurationStyle::Clock => return format!("{}:{:02}:{:02}", duration / HOUR, (duration % HOUR) / MINUTE, duration % MINUTE),
+            DurationStyle::Short if !format.duration_short.is_empty() => &format.duration_short,
+            _ => &format.duration
+        };
 
-        if duration_info >= HOUR {
-            return (duration_info / HOUR) % 24;
+        let mut buffer = String::new();
+
+        if let DurationGranularity::Decimal(duration_type) = &config.duration_config.granularity {
+            if let Some((unit, replace_str, _)) = DURATION_UNITS.iter().find(|(_, _, unit_type)| unit_type == duration_type) {
+                let value = duration as f64 / *unit as f64;
+                let decimal_seperator = config.get_decimal_seperator(&session.get_language());
+                let value_text = remove_trailing_zeros(format_number(value, options.thousand_separator(config, session), decimal_seperator.to_string(), options.decimal_places(2), true, true), decimal_seperator);
+
+                DurationItem::duration_formatter(formats, format.plural, &mut buffer, replace_str, value, &value_text, duration_type);
+                return buffer.trim().to_string();
+            }
         }
 
-        if duration_info >= MINUTE {
-            return (duration_info / MINUTE) % 60;
+        let mut remaining_units = match config.duration_config.granularity {
+            DurationGranularity::Largest(count) => count,
+            _ => usize::MAX
+        };
+
+        for (unit, replace_str, duration_type) in DURATION_UNITS.iter() {
+            if remaining_units == 0 {
+                break;
+            }
+
+            if duration >= *unit {
+                DurationItem::duration_formatter(formats, format.plural, &mut buffer, replace_str, (duration / unit) as f64, &(duration / unit).to_string(), duration_type);
+                duration %= unit;
+                remaining_units -= 1;
+            }
         }
 
-        duration_info
+        buffer.trim().to_string()
     }
 
-    pub fn as_time(&self) -> NaiveDateTime {
+    pub fn as_time(&self, config: &SmartCalcConfig) -> NaiveDateTime {
         let mut duration_info = self.0.num_seconds().abs();
         let mut hours         = 0;
         let mut minutes       = 0;
@@ -95,7 +145,7 @@ impl DurationItem {
 
         seconds = duration_info;
         
-        let date = Utc::now().naive_local().date();
+        let date = config.get_today();
         let time = chrono::NaiveTime::from_hms(hours as u32, minutes as u32, seconds as u32);
         NaiveDateTime::new(date, time)
     }
@@ -143,47 +193,23 @@ impl DataItem for DurationItem {
             }
         };
         
-        let mut buffer = String::new();
-
-        let mut duration = self.0.num_seconds().abs();
-        if duration >= YEAR {
-            DurationItem::duration_formatter(format, &mut buffer, "{year}", duration / YEAR, DurationFormatType::Year);
-            duration %= YEAR;
-        }
-
-        if duration >= MONTH {
-            DurationItem::duration_formatter(format, &mut buffer, "{month}", duration / MONTH, DurationFormatType::Month);
-            duration %= MONTH;
+        let length = self.format_length(config, session, format);
+        if !self.0.num_seconds().is_negative() {
+            return length;
         }
 
-        if duration >= WEEK {
-            DurationItem::duration_formatter(format, &mut buffer, "{week}", duration / WEEK, DurationFormatType::Week);
-            duration %= WEEK;
+        /* Clock style is always written with a sign, -2:30:00 */
+        let relative = config.duration_config.negative == NegativeDurationStyle::Relative && session.format_options().duration_style(config) != DurationStyle::Clock;
+        match format.date.get("relative_past") {
+            Some(template) if relative => template.replace("{duration}", &length),
+            _ => format!("-{}", length)
         }
-
-        if duration >= DAY {
-            DurationItem::duration_formatter(format, &mut buffer, "{day}", duration / DAY, DurationFormatType::Day);
-            duration %= DAY;
-        }
-
-        if duration >= HOUR {
-            DurationItem::duration_formatter(format, &mut buffer, "{hour}", duration / HOUR, DurationFormatType::Hour);
-            duration %= HOUR;
-        }
-
-        if duration >= MINUTE {
-            DurationItem::duration_formatter(format, &mut buffer, "{minute}", duration / MINUTE, DurationFormatType::Minute);
-            duration %= MINUTE;
-        }
-
-        if duration > 0 {
-            DurationItem::duration_formatter(format, &mut buffer, "{second}", duration, DurationFormatType::Second);
-        }
-
-        buffer.trim().to_string()
     }
-    fn unary(&self, _: UnaryType) -> Rc<dyn DataItem> {
-        Rc::new(Self(self.0))
+    fn unary(&self, unary: UnaryType) -> Rc<dyn DataItem> {
+        match unary {
+            UnaryType::Minus => Rc::new(Self(-self.0)),
+            UnaryType::Plus => Rc::new(Self(self.0))
+        }
     }
 }
 
@@ -222,4 +248,51 @@ fn duration_test() {
     
     assert!(result.is_some());
     assert_eq!(result.unwrap().print(&config, &session), "14 hours 59 minutes".to_string());
-}
\ No newline at end of file
+
+    let result = right.calculate(&config, true, &left, OperationType::Sub).unwrap();
+    assert_eq!(result.print(&config, &session), "-14 hours 59 minutes".to_string());
+    assert_eq!(result.get_number(&left), -14.0);
+}
+#[cfg(test)]
+#[test]
+fn duration_granularity_test() {
+    use crate::compiler::duration::DurationItem;
+    use crate::config::SmartCalcConfig;
+    let mut config = SmartCalcConfig::default();
+    let session = Session::default();
+    let duration = DurationItem(Duration::seconds(5490));
+
+    assert_eq!(duration.print(&config, &session), "1 hour 31 minutes 30 seconds".to_string());
+
+    config.duration_config.style = DurationStyle::Short;
+    assert_eq!(duration.print(&config, &session), "1h 31m 30s".to_string());
+
+    config.duration_config.granularity = DurationGranularity::Largest(2);
+    assert_eq!(duration.print(&config, &session), "1h 31m".to_string());
+
+    config.duration_config.style = DurationStyle::Long;
+    config.duration_config.granularity = DurationGranularity::Largest(1);
+    assert_eq!(duration.print(&config, &session), "1 hour".to_string());
+
+    config.duration_config.granularity = DurationGranularity::Decimal(DurationFormatType::Minute);
+    assert_eq!(duration.print(&config, &session), "91,5 minutes".to_string());
+    assert_eq!(DurationItem(Duration::minutes(1)).print(&config, &session), "1 minute".to_string());
+}
+
+#[cfg(test)]
+#[test]
+fn duration_clock_test() {
+    use crate::compiler::duration::DurationItem;
+    use crate::config::SmartCalcConfig;
+    let mut config = SmartCalcConfig::default();
+    let session = Session::default();
+    config.duration_config.style = DurationStyle::Clock;
+
+    assert_eq!(DurationItem(Duration::seconds(9000)).print(&config, &session), "2:30:00".to_string());
+    assert_eq!(DurationItem(Duration::seconds(45)).print(&config, &session), "0:00:45".to_string());
+    assert_eq!(DurationItem(Duration::hours(26)).print(&config, &session), "26:00:00".to_string());
+
+    /* Granularity is not used for the clock style */
+    config.duration_config.granularity = DurationGranularity::Largest(1);
+    assert_eq!(DurationItem(Duration::seconds(5490)).print(&config, &session), "1:31:30".to_string());
+}
@@ -7,13 +7,15 @@
 use core::any::{Any, TypeId};
 use alloc::collections::BTreeMap;
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::string::ToString;
 use alloc::string::String;
 use alloc::vec::Vec;
 use core::ops::Deref;
 use crate::SmartCalc;
 use crate::session::Session;
-use crate::config::DynamicType;
+use alloc::format;
+use crate::config::{Dimension, DynamicType};
 use crate::config::SmartCalcConfig;
 use crate::types::TokenType;
 use crate::compiler::number::NumberItem;
@@ -22,12 +24,52 @@ use super::{DataItem, OperationType, UnaryType};
 use crate::formatter::format_number;
 use crate::tools::do_divition;
 
+fn dimension_text(dimension: &Dimension) -> String {
+    dimension.iter().map(|(name, exponent)| match exponent {
+        1 => name.to_string(),
+        _ => format!("{}^{}", name, exponent)
+    }).collect::<Vec<_>>().join("*")
+}
+
+/* Exponents are added on multiplication and subtracted on division, addition needs the same dimension */
+pub fn check_dimension(left: &dyn DataItem, right: &dyn DataItem, operation_type: OperationType) -> Result<(), String> {
+    let (left, right) = match (left.as_any().downcast_ref::<DynamicTypeItem>(), right.as_any().downcast_ref::<DynamicTypeItem>()) {
+        (Some(left), Some(right)) => (&left.1.dimension, &right.1.dimension),
+        _ => return Ok(())
+    };
+
+    let sign = match operation_type {
+        OperationType::Add | OperationType::Sub => return match left == right {
+            true => Ok(()),
+            false => Err(format!("Units are not compatible. ({} and {})", dimension_text(left), dimension_text(right)))
+        },
+        OperationType::Mul => 1,
+        OperationType::Div => -1,
+        OperationType::Pow => return Ok(())
+    };
+
+    let mut result = left.clone();
+    for (name, exponent) in right.iter() {
+        let total = result.get(name).copied().unwrap_or(0) + sign * exponent;
+        match total {
+            0 => result.remove(name),
+            _ => result.insert(name.to_string(), total)
+        };
+    }
+
+    /* Only dimensionless results can be shown, there is no type for compound units */
+    match result.is_empty() {
+        true => Ok(()),
+        false => Err(format!("Unit is not supported. ({})", dimension_text(&result)))
+    }
+}
+
 #[derive(Debug)]
 
-pub struct DynamicTypeItem(pub f64, pub Rc<DynamicType>);
+pub struct DynamicTypeItem(pub f64, pub Arc<DynamicType>);
 
 impl DynamicTypeItem {
-    pub fn get_type(&self) -> Rc<DynamicType> {
+    pub fn get_type(&self) -> Arc<DynamicType> {
         self.1.clone()
     }
     
@@ -35,7 +77,7 @@ impl DynamicTypeItem {
         self.0
     }
     
-    fn  calculate_unit(config: &SmartCalcConfig, number: f64, source_type: Rc<DynamicType>, target_type: Rc<DynamicType>, group: &BTreeMap<usize, Rc<DynamicType>>) -> Option<f64> {
+    fn  calculate_unit(config: &SmartCalcConfig, number: f64, source_type: Arc<DynamicType>, target_type: Arc<DynamicType>, group: &BTreeMap<usize, Arc<DynamicType>>) -> Option<f64> {
         
         if source_type.index == target_type.index {
             return Some(number);
@@ -82,9 +124,9 @@ impl DynamicTypeItem {
         Some(number)
     }
     
-    pub fn convert(config: &SmartCalcConfig, number: f64, source_type: Rc<DynamicType>, target_type: String) -> Option<(f64, Rc<DynamicType>)> {
+    pub fn convert(config: &SmartCalcConfig, number: f64, source_type: Arc<DynamicType>, target_type: String) -> Option<(f64, Arc<DynamicType>)> {
         let group = config.types.get(&source_type.group_name)?;
-        let values: Vec<Rc<DynamicType>> = group.values().cloned().collect();
+        let values: Vec<Arc<DynamicType>> = group.values().cloned().collect();
         
         /* In type calculation */
         if let Some(target) = values.iter().find(|&s| s.names.contains(&target_type)) {
@@ -126,7 +168,7 @@ impl DynamicTypeItem {
         for (_, group) in config.types.iter() {
             for (_, target_dynamic_type) in group.iter() {
                 
-                if target_dynamic_type.names.contains(&target_type) {
+                if target_dynamic_type.names.contains(&target_type) && target_dynamic_type.dimension == source_type.dimension {
                     let source_type = match group.get(&target_index) {
                         Some(source_type) => source_type,
                         None => return None
@@ -145,7 +187,7 @@ impl DataItem for DynamicTypeItem {
         TokenType::DynamicType(self.0, self.1.clone())
     }
     fn is_same(&self, other: &dyn Any) -> bool {
-        match other.downcast_ref::<(f64, Rc<DynamicType>)>() {
+        match other.downcast_ref::<(f64, Arc<DynamicType>)>() {
             Some((l_value, l_type)) => (l_value - self.0).abs() < f64::EPSILON && l_type.deref() == self.1.deref(),
             None => false
         }
@@ -157,6 +199,10 @@ impl DataItem for DynamicTypeItem {
             "NUMBER" => (other.get_underlying_number(), false),
             "DYNAMIC_TYPE" => {
                 let other_dynamic_type: &DynamicTypeItem = other.as_any().downcast_ref::<DynamicTypeItem>()?;
+                if other_dynamic_type.1.dimension != self.1.dimension {
+                    return None;
+                }
+
                 let (new_number, _) = DynamicTypeItem::convert(config, other_dynamic_type.get_number(), other_dynamic_type.get_type(), self.1.names[0].clone())?;
                 (new_number, true)
             },
@@ -179,7 +225,8 @@ impl DataItem for DynamicTypeItem {
                 }
             },
             OperationType::Mul => left * right,
-            OperationType::Sub => left - right
+            OperationType::Sub => left - right,
+            OperationType::Pow => return None
         };
         
         Some(Rc::new(DynamicTypeItem(result, self.1.clone())))
@@ -196,12 +243,13 @@ impl DataItem for DynamicTypeItem {
     fn get_underlying_number(&self) -> f64 { self.0 }
     fn type_name(&self) -> &'static str { "DYNAMIC_TYPE" }
     fn type_id(&self) -> TypeId { TypeId::of::<DynamicTypeItem>() }
-    fn print(&self, config: &SmartCalcConfig, _: &Session) -> String {
-        let decimal_digit = self.1.decimal_digits.map_or(2, |x| x);
+    fn print(&self, config: &SmartCalcConfig, session: &Session) -> String {
+        let options = session.format_options();
+        let decimal_digit = options.decimal_places(self.1.decimal_digits.map_or(2, |x| x));
         let remove_fract_if_zero = self.1.remove_fract_if_zero.map_or(true, |x| x);
         let use_fract_rounding = self.1.use_fract_rounding.map_or(true, |x| x);
 
-        let formated_number = format_number(self.0, config.thousand_separator.to_string(), config.decimal_seperator.to_string(), decimal_digit, remove_fract_if_zero, use_fract_rounding);
+        let formated_number = format_number(self.0, options.thousand_separator(config, session), config.get_decimal_seperator(&session.get_language()).to_string(), decimal_digit, remove_fract_if_zero, use_fract_rounding);
         self.1.format.replace("{value}", &formated_number)
     }
     fn unary(&self, unary: UnaryType) -> Rc<dyn DataItem> {
@@ -220,13 +268,13 @@ fn format_result_test() {
     let config = SmartCalcConfig::default();
     let session = Session::default();
 
-    let dynamic_type_1 = Rc::new(DynamicType::new("test".to_string(), 0, "{value} Test1".to_string(), Vec::new(), "{value} / 10".to_string(), "{value} * 10".to_string(), Vec::new(), Some(5), Some(true), Some(true)));
+    let dynamic_type_1 = Arc::new(DynamicType::new("test".to_string(), 0, "{value} Test1".to_string(), Vec::new(), "{value} / 10".to_string(), "{value} * 10".to_string(), Vec::new(), Some(5), Some(true), Some(true)));
 
     assert_eq!(DynamicTypeItem(1000.0, dynamic_type_1.clone()).print(&config, &session), "1.000 Test1".to_string());
     assert_eq!(DynamicTypeItem(10.0, dynamic_type_1.clone()).print(&config, &session), "10 Test1".to_string());
     assert_eq!(DynamicTypeItem(10.1, dynamic_type_1.clone()).print(&config, &session), "10,10000 Test1".to_string());
 
-    let dynamic_type_2 = Rc::new(DynamicType::new("test".to_string(), 0, "Test2 {value}".to_string(), Vec::new(), "{value} / 10".to_string(), "{value} * 10".to_string(), Vec::new(), Some(3), Some(false), Some(false)));
+    let dynamic_type_2 = Arc::new(DynamicType::new("test".to_string(), 0, "Test2 {value}".to_string(), Vec::new(), "{value} / 10".to_string(), "{value} * 10".to_string(), Vec::new(), Some(3), Some(false), Some(false)));
     assert_eq!(DynamicTypeItem(1000.0, dynamic_type_2.clone()).print(&config, &session), "Test2 1.000".to_string());
     assert_eq!(DynamicTypeItem(10.0, dynamic_type_2.clone()).print(&config, &session), "Test2 10".to_string());
     assert_eq!(DynamicTypeItem(10.1, dynamic_type_2.clone()).print(&config, &session), "Test2 10,1".to_string());
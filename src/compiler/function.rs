@@ -0,0 +1,147 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::collections::btree_map::BTreeMap;
+use lazy_static::*;
+
+use crate::config::SmartCalcConfig;
+use crate::compiler::{DataItem, OperationType};
+use crate::syntax::primative::PrimativeParser;
+use crate::compiler::number::NumberItem;
+use crate::types::{NumberType, TokenType};
+
+pub type FunctionType = fn(config: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String>;
+
+lazy_static! {
+    pub static ref FUNCTIONS: BTreeMap<&'static str, FunctionType> = {
+        let mut m = BTreeMap::new();
+        m.insert("sqrt",  sqrt  as FunctionType);
+        m.insert("abs",   abs   as FunctionType);
+        m.insert("round", round as FunctionType);
+        m.insert("floor", floor as FunctionType);
+        m.insert("ceil",  ceil  as FunctionType);
+        m.insert("min",   min   as FunctionType);
+        m.insert("max",   max   as FunctionType);
+        m.insert("sum",   sum   as FunctionType);
+        m.insert("avg",   avg   as FunctionType);
+        m.insert("average", avg as FunctionType);
+        m
+    };
+}
+
+pub fn is_function(name: &str) -> bool {
+    FUNCTIONS.contains_key(&name.to_lowercase()[..])
+}
+
+fn argument_count(name: &str, arguments: &[Rc<dyn DataItem>], min: usize, max: usize) -> Result<(), String> {
+    match arguments.len() >= min && arguments.len() <= max {
+        true => Ok(()),
+        false => Err(format!("Wrong number of arguments. ({})", name))
+    }
+}
+
+/* Keeps the type of the item, only the number part is changed */
+fn with_number(item: &Rc<dyn DataItem>, number: f64) -> Result<Rc<dyn DataItem>, String> {
+    let token = match item.as_token_type() {
+        TokenType::Number(_, number_type) => TokenType::Number(number, number_type),
+        TokenType::Money(_, currency) => TokenType::Money(number, currency),
+        TokenType::Percent(_) => TokenType::Percent(number),
+        TokenType::DynamicType(_, dynamic_type) => TokenType::DynamicType(number, dynamic_type),
+        _ => return Err(format!("Function does not support {}", item.type_name().to_lowercase()))
+    };
+
+    PrimativeParser::token_to_item(&token).ok_or_else(|| "Function result not valid".to_string())
+}
+
+fn number_function(name: &str, arguments: &[Rc<dyn DataItem>], function: fn(f64) -> f64) -> Result<Rc<dyn DataItem>, String> {
+    argument_count(name, arguments, 1, 1)?;
+    with_number(&arguments[0], function(arguments[0].get_underlying_number()))
+}
+
+fn sqrt(_: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    argument_count("sqrt", arguments, 1, 1)?;
+    match arguments[0].get_underlying_number() {
+        number if number < 0.0 => Err("Square root of negative number".to_string()),
+        number => with_number(&arguments[0], number.sqrt())
+    }
+}
+
+fn abs(_: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    number_function("abs", arguments, f64::abs)
+}
+
+fn floor(_: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    number_function("floor", arguments, f64::floor)
+}
+
+fn ceil(_: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    number_function("ceil", arguments, f64::ceil)
+}
+
+fn round(_: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    argument_count("round", arguments, 1, 2)?;
+    let digits = match arguments.get(1) {
+        Some(digits) if digits.type_name() == "NUMBER" => digits.get_underlying_number(),
+        Some(_) => return Err("Digits should be number".to_string()),
+        None => 0.0
+    };
+
+    let multiplier = 10_f64.powf(digits.trunc());
+    with_number(&arguments[0], (arguments[0].get_underlying_number() * multiplier).round() / multiplier)
+}
+
+/* Items are compared with subtraction, so money in different currencies is converted first */
+fn select(config: &SmartCalcConfig, name: &str, arguments: &[Rc<dyn DataItem>], greater: bool) -> Result<Rc<dyn DataItem>, String> {
+    argument_count(name, arguments, 1, usize::MAX)?;
+    let mut selected = arguments[0].clone();
+
+    for argument in arguments.iter().skip(1) {
+        let difference = match argument.calculate(config, true, selected.as_ref(), OperationType::Sub) {
+            Some(difference) => difference.get_underlying_number(),
+            None => return Err(format!("Arguments can not be compared. ({})", name))
+        };
+
+        if (greater && difference > 0.0) || (!greater && difference < 0.0) {
+            selected = argument.clone();
+        }
+    }
+
+    Ok(selected)
+}
+
+fn min(config: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    select(config, "min", arguments, false)
+}
+
+fn max(config: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    select(config, "max", arguments, true)
+}
+
+fn sum(config: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    argument_count("sum", arguments, 1, usize::MAX)?;
+    let mut total = arguments[0].clone();
+
+    for argument in arguments.iter().skip(1) {
+        total = match total.calculate(config, true, argument.as_ref(), OperationType::Add) {
+            Some(total) => total,
+            None => return Err("Arguments can not be added. (sum)".to_string())
+        };
+    }
+
+    Ok(total)
+}
+
+fn avg(config: &SmartCalcConfig, arguments: &[Rc<dyn DataItem>]) -> Result<Rc<dyn DataItem>, String> {
+    let total = sum(config, arguments)?;
+    match total.calculate(config, true, &NumberItem(arguments.len() as f64, NumberType::Decimal), OperationType::Div) {
+        Some(average) => Ok(average),
+        None => Err("Arguments can not be divided. (avg)".to_string())
+    }
+}
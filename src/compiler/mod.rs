@@ -6,10 +6,12 @@
 
 use core::any::Any;
 use core::any::TypeId;
+use core::cmp::Ordering;
 use core::ops::Deref;
 
 use alloc::rc::Rc;
 use alloc::string::String;
+use alloc::vec::Vec;
 use alloc::string::ToString;
 use alloc::format;
 
@@ -17,8 +19,16 @@ use crate::session::Session;
 use crate::config::SmartCalcConfig;
 use crate::types::*;
 use crate::variable::VariableInfo;
+use crate::compiler::number::NumberItem;
+use crate::compiler::bool::BoolItem;
+use crate::compiler::money::MoneyItem;
+use crate::smartcalc::TraceStep;
+use crate::compiler::dynamic_type::check_dimension;
+use crate::compiler::rational::to_rational;
+use crate::compiler::optimizer::Optimizer;
 
 pub mod number;
+pub mod rational;
 pub mod percent;
 pub mod money;
 pub mod time;
@@ -26,6 +36,10 @@ pub mod duration;
 pub mod date;
 pub mod date_time;
 pub mod dynamic_type;
+pub mod bool;
+pub mod text;
+pub mod function;
+pub mod optimizer;
 
 #[derive(Clone)]
 #[derive(Copy)]
@@ -33,7 +47,8 @@ pub enum OperationType {
     Add,
     Div,
     Mul,
-    Sub
+    Sub,
+    Pow
 }
 
 
@@ -57,8 +72,24 @@ pub trait DataItem: alloc::fmt::Debug {
     fn print(&self, config: &SmartCalcConfig, session: &Session) -> String;
 }
 
+/* Runs the parsed line, embedders can wrap the interpreter to trace or limit the evaluation */
+pub trait Evaluator: Send + Sync {
+    fn evaluate(&self, config: &SmartCalcConfig, ast: Rc<SmartCalcAstType>, session: &Session) -> Result<Rc<SmartCalcAstType>, String>;
+}
+
 pub struct Interpreter;
 
+/* Default evaluator, literal sub trees are folded before the interpretation */
+impl Evaluator for Interpreter {
+    fn evaluate(&self, config: &SmartCalcConfig, ast: Rc<SmartCalcAstType>, session: &Session) -> Result<Rc<SmartCalcAstType>, String> {
+        /* Folding is skipped while tracing, so every intermediate value is reported */
+        match session.is_tracing() {
+            true => Interpreter::execute(config, ast, session),
+            false => Interpreter::execute(config, Optimizer::optimize(config, ast), session)
+        }
+    }
+}
+
 impl Interpreter {
     pub fn execute(config: &SmartCalcConfig, ast: Rc<SmartCalcAstType>, session: &Session) -> Result<Rc<SmartCalcAstType>, String> {
         Interpreter::execute_ast(config, session, ast)
@@ -68,10 +99,14 @@ impl Interpreter {
         match ast.deref() {
             SmartCalcAstType::Binary { left, operator, right } => Interpreter::executer_binary(config, session, left.clone(), *operator, right.clone()),
             SmartCalcAstType::Assignment { variable, expression } => Interpreter::executer_assignment(config, session, variable.clone(), expression.clone()),
-            SmartCalcAstType::Variable(variable)               => Ok(Interpreter::executer_variable(variable.clone())),
+            SmartCalcAstType::Variable(variable)               => Interpreter::executer_variable(session, variable.clone()),
             SmartCalcAstType::Item(_)                          => Ok(ast),
             SmartCalcAstType::Month(_)                         => Ok(ast),
             SmartCalcAstType::PrefixUnary(ch, ast)             => Interpreter::executer_unary(config, session, *ch, ast.clone()),
+            SmartCalcAstType::Call { name, arguments }         => Interpreter::executer_call(config, session, name, arguments),
+            SmartCalcAstType::Range { start, end, aggregate }  => Ok(Interpreter::executer_range(*start, *end, *aggregate)),
+            SmartCalcAstType::List(items)                      => Interpreter::executer_list(config, session, items),
+            SmartCalcAstType::Comparison { left, operator, right } => Interpreter::executer_comparison(config, session, left.clone(), *operator, right.clone()),
             SmartCalcAstType::None                             => Ok(Rc::new(SmartCalcAstType::None)),
             _ => {
                 Ok(Rc::new(SmartCalcAstType::None))
@@ -79,51 +114,217 @@ impl Interpreter {
         }
     }
 
-    fn executer_variable(variable: Rc<VariableInfo>) -> Rc<SmartCalcAstType> {
-        variable.data.borrow().clone()
+    fn executer_call(config: &SmartCalcConfig, session: &Session, name: &str, arguments: &[Rc<SmartCalcAstType>]) -> Result<Rc<SmartCalcAstType>, String> {
+        let function = match function::FUNCTIONS.get(&name.to_lowercase()[..]) {
+            Some(function) => function,
+            None => return Err(format!("Function not found. ({})", name))
+        };
+
+        /* List arguments are expanded, so sum([1, 2]) and sum(1, 2) are the same */
+        let mut items = Vec::with_capacity(arguments.len());
+        for argument in arguments.iter() {
+            match Interpreter::execute_ast(config, session, argument.clone())?.deref() {
+                SmartCalcAstType::Item(item) => items.push(item.clone()),
+                SmartCalcAstType::List(list) => for list_item in list.iter() {
+                    if let SmartCalcAstType::Item(item) = list_item.deref() {
+                        items.push(item.clone());
+                    }
+                },
+                _ => return Err(format!("Argument is not valid. ({})", name))
+            };
+        }
+
+        let result = function(config, &items)?;
+        Interpreter::check_finite(result.deref(), || name.to_lowercase())?;
+        session.add_trace(|| TraceStep::Calculation {
+            expression: format!("{}({})", name.to_lowercase(), items.iter().map(|item| item.print(config, session)).collect::<Vec<_>>().join(", ")),
+            result: result.print(config, session)
+        });
+        Ok(Rc::new(SmartCalcAstType::Item(result)))
+    }
+
+    /* Items of a list should have the same type, money in different currencies is allowed */
+    fn executer_list(config: &SmartCalcConfig, session: &Session, items: &[Rc<SmartCalcAstType>]) -> Result<Rc<SmartCalcAstType>, String> {
+        let mut computed_items = Vec::with_capacity(items.len());
+        for item in items.iter() {
+            let computed = Interpreter::execute_ast(config, session, item.clone())?;
+            match (computed.deref(), computed_items.first().map(|first: &Rc<SmartCalcAstType>| first.type_name())) {
+                (SmartCalcAstType::Item(item), Some(type_name)) if item.type_name() != type_name => return Err("List items should have the same type".to_string()),
+                (SmartCalcAstType::Item(_), _) => computed_items.push(computed.clone()),
+                _ => return Err("List item is not valid".to_string())
+            };
+        }
+
+        Ok(Rc::new(SmartCalcAstType::List(computed_items)))
+    }
+
+    /* Ranges are inclusive and the limits can be in any order, values are aggregated without iterating */
+    fn executer_range(start: i64, end: i64, aggregate: RangeAggregate) -> Rc<SmartCalcAstType> {
+        let (start, end) = (start.min(end) as f64, start.max(end) as f64);
+        let average = (start + end) / 2.0;
+
+        let number = match aggregate {
+            RangeAggregate::Sum => average * (end - start + 1.0),
+            RangeAggregate::Average => average
+        };
+
+        Rc::new(SmartCalcAstType::Item(Rc::new(NumberItem(number, NumberType::Decimal))))
+    }
+
+    /* Bools and texts can only be checked for equality, other items are compared with subtraction, so money in different currencies is converted first */
+    fn executer_comparison(config: &SmartCalcConfig, session: &Session, left: Rc<SmartCalcAstType>, operator: ComparisonType, right: Rc<SmartCalcAstType>) -> Result<Rc<SmartCalcAstType>, String> {
+        let computed_left  = Interpreter::execute_ast(config, session, left)?;
+        let computed_right = Interpreter::execute_ast(config, session, right)?;
+
+        let (left, right) = match (computed_left.deref(), computed_right.deref()) {
+            (SmartCalcAstType::Item(left), SmartCalcAstType::Item(right)) => (left, right),
+            _ => return Err("Comparison is not valid".to_string())
+        };
+
+        let ordering = match (left.type_name(), right.type_name()) {
+            ("BOOL", _) | (_, "BOOL") | ("TEXT", _) | (_, "TEXT") => match (operator, left.as_token_type() == right.as_token_type()) {
+                (ComparisonType::Equal, equal) => return Ok(Rc::new(SmartCalcAstType::Item(Rc::new(BoolItem(equal))))),
+                (ComparisonType::NotEqual, equal) => return Ok(Rc::new(SmartCalcAstType::Item(Rc::new(BoolItem(!equal))))),
+                _ => None
+            },
+            _ => {
+                check_dimension(left.deref(), right.deref(), OperationType::Sub)?;
+                left.calculate(config, true, right.deref(), OperationType::Sub).and_then(|difference| match difference.get_underlying_number() {
+                    number if number.abs() < f64::EPSILON => Some(Ordering::Equal),
+                    number => number.partial_cmp(&0.0)
+                })
+            }
+        };
+
+        let ordering = match ordering {
+            Some(ordering) => ordering,
+            None => return Err(format!("Values can not be compared. ({}, {})", left.type_name().to_lowercase(), right.type_name().to_lowercase()))
+        };
+
+        let result = match operator {
+            ComparisonType::Equal => ordering == Ordering::Equal,
+            ComparisonType::NotEqual => ordering != Ordering::Equal,
+            ComparisonType::Less => ordering == Ordering::Less,
+            ComparisonType::LessOrEqual => ordering != Ordering::Greater,
+            ComparisonType::Greater => ordering == Ordering::Greater,
+            ComparisonType::GreaterOrEqual => ordering != Ordering::Less
+        };
+
+        Ok(Rc::new(SmartCalcAstType::Item(Rc::new(BoolItem(result)))))
+    }
+
+    fn executer_variable(session: &Session, variable: Rc<VariableInfo>) -> Result<Rc<SmartCalcAstType>, String> {
+        let data = variable.data.borrow().clone();
+
+        /* Line references are not session variables, they are empty if the line is not calculated yet */
+        if matches!(data.deref(), SmartCalcAstType::None) && !session.variables.borrow().contains_key(&variable.name) {
+            return Err("Line result not found".to_string());
+        }
+
+        Ok(data)
     }
 
     fn executer_assignment(config: &SmartCalcConfig, session: &Session, variable: Rc<VariableInfo>, expression: Rc<SmartCalcAstType>) -> Result<Rc<SmartCalcAstType>, String> {
         let computed  = Interpreter::execute_ast(config, session, expression)?;
+
+        if let Some(type_name) = variable.type_lock.borrow().deref() {
+            match computed.deref() {
+                SmartCalcAstType::Item(item) if item.type_name() == type_name => (),
+                _ => return Err(format!("Variable type does not match. ({})", type_name.to_lowercase()))
+            };
+        }
+
         *variable.data.borrow_mut() = computed.clone();
+        session.track_assignment(&variable, &computed);
         Ok(computed)
     }
     
-    fn calculate_item(config: &SmartCalcConfig, operator: char, left: Rc<SmartCalcAstType>, right: Rc<SmartCalcAstType>) -> Result<Rc<SmartCalcAstType>, String> {
+    pub(crate) fn calculate_item(config: &SmartCalcConfig, operator: char, left: Rc<SmartCalcAstType>, right: Rc<SmartCalcAstType>) -> Result<Rc<SmartCalcAstType>, String> {
         let left = match left.deref() {
-            SmartCalcAstType::Item(left) => left.clone(),
+            SmartCalcAstType::Item(left) => to_rational(config, left.clone()),
             _ => return Err("Unknown calculation".to_string())
         };
         
         let right = match right.deref() {
-            SmartCalcAstType::Item(right) => right.clone(),
+            SmartCalcAstType::Item(right) => to_rational(config, right.clone()),
             _ => return Err("Unknown calculation".to_string())
         };
         
-        let result = match operator {
-            '+' => left.calculate(config, true, right.deref(), OperationType::Add),
-            '-' => left.calculate(config, true, right.deref(), OperationType::Sub),
-            '*' => left.calculate(config, true, right.deref(), OperationType::Mul),
-            '/' => left.calculate(config, true, right.deref(), OperationType::Div),
+        let operation_type = match operator {
+            '+' => OperationType::Add,
+            '-' => OperationType::Sub,
+            '*' => OperationType::Mul,
+            '/' => OperationType::Div,
+            '^' => OperationType::Pow,
             _ => return Err(format!("Unknown operator. ({})", operator))
         };
-        
-        match result {
-            Some(item) => Ok(Rc::new(SmartCalcAstType::Item(item.clone()))),
+
+        check_dimension(left.deref(), right.deref(), operation_type)?;
+
+        match left.calculate(config, true, right.deref(), operation_type) {
+            Some(item) => {
+                Interpreter::check_finite(item.deref(), || format!("{} {} {}", left.as_token_type().to_string(), operator, right.as_token_type().to_string()))?;
+                Ok(Rc::new(SmartCalcAstType::Item(item.clone())))
+            },
             None => Err("Unknown calculation".to_string())
         }
     }
 
+    /* Infinite and NaN results are reported with the expression that produced them, instead of being formatted */
+    fn check_finite<F: Fn() -> String>(item: &dyn DataItem, expression: F) -> Result<(), String> {
+        let number = item.get_underlying_number();
+        if number.is_nan() {
+            Err(format!("Result is not a number. ({})", expression()))
+        } else if number.is_infinite() {
+            Err(format!("Number overflow. ({})", expression()))
+        } else {
+            Ok(())
+        }
+    }
+
     fn executer_binary(config: &SmartCalcConfig, session: &Session, left: Rc<SmartCalcAstType>, operator: char, right: Rc<SmartCalcAstType>) -> Result<Rc<SmartCalcAstType>, String> {
         let computed_left  = Interpreter::execute_ast(config, session, left)?;
         let computed_right = Interpreter::execute_ast(config, session, right)?;
 
         match (computed_left.deref(), computed_right.deref()) {
-            (SmartCalcAstType::Item(_), _)           | (_, SmartCalcAstType::Item(_))           => Interpreter::calculate_item(config, operator, computed_left.clone(), computed_right.clone()),
+            (SmartCalcAstType::Item(_), _)           | (_, SmartCalcAstType::Item(_))           => {
+                let result = Interpreter::calculate_item(config, operator, computed_left.clone(), computed_right.clone())?;
+                Interpreter::trace_binary(config, session, computed_left.deref(), operator, computed_right.deref(), result.deref());
+                Ok(result)
+            },
             _ => Err("Uknown calculation result".to_string())
         }
     }
 
+    /* Money on the right side is converted to the currency of the left side */
+    fn trace_binary(config: &SmartCalcConfig, session: &Session, left: &SmartCalcAstType, operator: char, right: &SmartCalcAstType, result: &SmartCalcAstType) {
+        if !session.is_tracing() {
+            return;
+        }
+
+        let (left, right, result) = match (left, right, result) {
+            (SmartCalcAstType::Item(left), SmartCalcAstType::Item(right), SmartCalcAstType::Item(result)) => (left, right, result),
+            _ => return
+        };
+
+        if let (Some(left_money), Some(right_money)) = (left.as_any().downcast_ref::<MoneyItem>(), right.as_any().downcast_ref::<MoneyItem>()) {
+            if left_money.get_currency().code != right_money.get_currency().code {
+                if let (Some(l_rate), Some(r_rate)) = (config.currency_rate.get(&left_money.get_currency()), config.currency_rate.get(&right_money.get_currency())) {
+                    session.add_trace(|| TraceStep::Conversion {
+                        from: right_money.get_currency().code.to_string(),
+                        to: left_money.get_currency().code.to_string(),
+                        rate: l_rate / r_rate
+                    });
+                }
+            }
+        }
+
+        session.add_trace(|| TraceStep::Calculation {
+            expression: format!("{} {} {}", left.print(config, session), operator, right.print(config, session)),
+            result: result.print(config, session)
+        });
+    }
+
     fn executer_unary(config: &SmartCalcConfig, session: &Session, operator: char, ast: Rc<SmartCalcAstType>) -> Result<Rc<SmartCalcAstType>, String> {
         let computed = Interpreter::execute_ast(config, session, ast)?;
 
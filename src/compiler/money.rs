@@ -7,24 +7,25 @@
 use core::any::{Any, TypeId};
 use alloc::format;
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::string::ToString;
 use alloc::string::String;
 use core::ops::Deref;
 use crate::session::Session;
-use crate::config::SmartCalcConfig;
+use crate::config::{SmartCalcConfig, CurrencyStyle};
 use crate::types::{CurrencyInfo, TokenType, NumberType};
 
 use super::number::NumberItem;
 use super::{DataItem, OperationType, UnaryType};
-use crate::formatter::format_number;
+use crate::formatter::{format_number, remove_trailing_zeros};
 use crate::tools::do_divition;
 
 #[derive(Debug)]
 
-pub struct MoneyItem(pub f64, pub Rc<CurrencyInfo>);
+pub struct MoneyItem(pub f64, pub Arc<CurrencyInfo>);
 
 impl MoneyItem {
-    pub fn get_currency(&self) -> Rc<CurrencyInfo> {
+    pub fn get_currency(&self) -> Arc<CurrencyInfo> {
         self.1.clone()
     }
     
@@ -50,7 +51,7 @@ impl DataItem for MoneyItem {
         TokenType::Money(self.0, self.1.clone())
     }
     fn is_same(&self, other: &dyn Any) -> bool {
-        match other.downcast_ref::<(f64, Rc<CurrencyInfo>)>() {
+        match other.downcast_ref::<(f64, Arc<CurrencyInfo>)>() {
             Some((l_value, l_symbol)) => (l_value - self.0).abs() < f64::EPSILON && l_symbol.deref() == self.1.deref(),
             None => false
         }
@@ -83,7 +84,8 @@ impl DataItem for MoneyItem {
                 }
             },
             OperationType::Mul => left * right,
-            OperationType::Sub => left - right
+            OperationType::Sub => left - right,
+            OperationType::Pow => return None
         };
         Some(Rc::new(MoneyItem(result, target_curreny)))
     }
@@ -99,9 +101,30 @@ impl DataItem for MoneyItem {
     fn get_underlying_number(&self) -> f64 { self.0 }
     fn type_name(&self) -> &'static str { "MONEY" }
     fn type_id(&self) -> TypeId { TypeId::of::<MoneyItem>() }
-    fn print(&self, config: &SmartCalcConfig, _: &Session) -> String {
+    fn print(&self, config: &SmartCalcConfig, session: &Session) -> String {
+        let options = session.format_options();
         let currency = self.get_currency();
-        let formated_price = format_number(self.get_price(), config.thousand_separator.to_string(), config.decimal_seperator.to_string(), currency.decimal_digits, config.money_config.remove_fract_if_zero, config.money_config.use_fract_rounding);
+        let style = options.currency_style(config);
+
+        /* Fraction is not written for whole amounts when the currency name is used, 1.500 US dollars */
+        let remove_fract_if_zero = options.remove_fract_if_zero(config.money_config.remove_fract_if_zero || style == CurrencyStyle::Name);
+        let decimal_seperator = config.get_decimal_seperator(&session.get_language());
+        let mut formated_price = format_number(self.get_price(), options.thousand_separator(config, session), decimal_seperator.to_string(), options.decimal_places(currency.decimal_digits), remove_fract_if_zero, config.money_config.use_fract_rounding);
+        if options.remove_trailing_zeros(config.money_config.remove_trailing_zeros) {
+            formated_price = remove_trailing_zeros(formated_price, decimal_seperator);
+        }
+
+        match style {
+            CurrencyStyle::Code => return format!("{} {}", formated_price, currency.code),
+            CurrencyStyle::Name => {
+                let format = config.format.get(&session.get_language()).or_else(|| config.format.get("en"));
+                let name = format.and_then(|format| format.currency.get(&currency.code).map(|name| format.plural.select(name, self.get_price())));
+
+                return format!("{} {}", formated_price, name.unwrap_or(&currency.code))
+            },
+            CurrencyStyle::Symbol => ()
+        };
+
         match (currency.symbol_on_left, currency.space_between_amount_and_symbol) {
             (true, true) => format!("{} {}", currency.symbol, formated_price),
             (true, false) => format!("{}{}", currency.symbol, formated_price),
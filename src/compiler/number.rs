@@ -13,7 +13,7 @@ use crate::config::SmartCalcConfig;
 use crate::types::{TokenType, NumberType};
 use super::percent::PercentItem;
 use super::{DataItem, OperationType, UnaryType};
-use crate::formatter::format_number;
+use crate::formatter::{format_number, remove_trailing_zeros, round_number};
 use crate::tools::do_divition;
 
 #[derive(Debug)]
@@ -31,7 +31,7 @@ impl DataItem for NumberItem {
     }
     fn as_any(&self) -> &dyn Any { self }
     fn calculate(&self, _: &SmartCalcConfig, on_left: bool, other: &dyn DataItem, operation_type: OperationType) -> Option<Rc<dyn DataItem>> {
-        let other_number  = if TypeId::of::<NumberItem>() == other.type_id() { 
+        let other_number  = if other.type_name() == "NUMBER" { 
             other.get_underlying_number()
             
         } else if TypeId::of::<PercentItem>() == other.type_id() { 
@@ -51,7 +51,9 @@ impl DataItem for NumberItem {
             OperationType::Add => left + right,
             OperationType::Div => do_divition(left, right),
             OperationType::Mul => left * right,
-            OperationType::Sub => left - right
+            OperationType::Sub => left - right,
+            OperationType::Pow if other.type_name() == "NUMBER" => left.powf(right),
+            OperationType::Pow => return None
         };
         Some(Rc::new(NumberItem(result, self.1)))
     }
@@ -59,13 +61,22 @@ impl DataItem for NumberItem {
     fn get_underlying_number(&self) -> f64 { self.0 }
     fn type_name(&self) -> &'static str { "NUMBER" }
     fn type_id(&self) -> TypeId { TypeId::of::<NumberItem>() }
-    fn print(&self, config: &SmartCalcConfig, _: &Session) -> String {
+    fn print(&self, config: &SmartCalcConfig, session: &Session) -> String {
+        let options = session.format_options();
+        let decimal_digits = options.decimal_places(config.number_config.decimal_digits);
+        let decimal_seperator = config.get_decimal_seperator(&session.get_language());
         match self.1 {
-            NumberType::Decimal     => format_number(self.0, config.thousand_separator.to_string(), config.decimal_seperator.to_string(), config.number_config.decimal_digits, config.number_config.remove_fract_if_zero, config.number_config.use_fract_rounding),
+            NumberType::Decimal     => {
+                let formated_number = format_number(round_number(self.0, decimal_digits, options.rounding(config.number_config.rounding)), options.thousand_separator(config, session), decimal_seperator.to_string(), decimal_digits, options.remove_fract_if_zero(config.number_config.remove_fract_if_zero), config.number_config.use_fract_rounding);
+                match options.remove_trailing_zeros(config.number_config.remove_trailing_zeros) {
+                    true => remove_trailing_zeros(formated_number, decimal_seperator),
+                    false => formated_number
+                }
+            },
             NumberType::Binary      => format!("{:#b}", self.0 as i32),
             NumberType::Octal       => format!("{:#o}", self.0 as i32),
             NumberType::Hexadecimal => format!("{:#X}", self.0 as i32),
-            NumberType::Raw         => format!("{}", self.0 as i32)
+            NumberType::Raw         => format!("{}", self.0 as i64)
         }
     }
     fn unary(&self, unary: UnaryType) -> Rc<dyn DataItem> {
@@ -122,3 +133,28 @@ fn format_result_test_3() {
     assert_eq!(NumberItem(10.0, NumberType::Decimal).print(&config, &session), "10,000".to_string());
     assert_eq!(NumberItem(10.1, NumberType::Decimal).print(&config, &session), "10,100".to_string());
 }
+
+#[cfg(test)]
+#[test]
+fn format_result_rounding_test() {
+    use crate::config::{SmartCalcConfig, RoundingMode};
+    use crate::formatter::{apply_template, DefaultFormatter, FormatOptions, ResultFormatter};
+    use crate::types::SmartCalcAstType;
+    let mut config = SmartCalcConfig::default();
+    let session = Session::default();
+    let number = |number: f64| Rc::new(SmartCalcAstType::Item(Rc::new(NumberItem(number, NumberType::Decimal))));
+
+    config.number_config.rounding = RoundingMode::Up;
+    assert_eq!(NumberItem(2.341, NumberType::Decimal).print(&config, &session), "2,35".to_string());
+    assert_eq!(NumberItem(-2.341, NumberType::Decimal).print(&config, &session), "-2,35".to_string());
+    assert_eq!(NumberItem(0.1 + 0.2, NumberType::Decimal).print(&config, &session), "0,30".to_string());
+
+    config.number_config.rounding = RoundingMode::Down;
+    assert_eq!(NumberItem(2.349, NumberType::Decimal).print(&config, &session), "2,34".to_string());
+    assert_eq!(NumberItem(-2.349, NumberType::Decimal).print(&config, &session), "-2,34".to_string());
+
+    let options = FormatOptions { decimal_places: Some(1), rounding: Some(RoundingMode::Nearest), ..FormatOptions::default() };
+    let format_result = |result: Rc<SmartCalcAstType>| apply_template(DefaultFormatter.format(&config, &session, result.clone(), &options), "", &result, &options);
+    assert_eq!(format_result(number(0.1 + 0.2)), "0,3".to_string());
+    assert_eq!(format_result(number(2.349)), "2,3".to_string());
+}
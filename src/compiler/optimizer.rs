@@ -0,0 +1,104 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::ops::Deref;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use crate::config::SmartCalcConfig;
+use crate::types::SmartCalcAstType;
+use super::{Interpreter, UnaryType};
+
+/* Runs between the parser and the interpreter. Literal sub trees are calculated once and unary chains are simplified,
+   failed calculations are kept as they are, so the interpreter reports the same error */
+pub struct Optimizer;
+
+impl Optimizer {
+    pub fn optimize(config: &SmartCalcConfig, ast: Rc<SmartCalcAstType>) -> Rc<SmartCalcAstType> {
+        match ast.deref() {
+            SmartCalcAstType::Binary { left, operator, right } => Optimizer::optimize_binary(config, left.clone(), *operator, right.clone()),
+            SmartCalcAstType::PrefixUnary(operator, expression) => Optimizer::optimize_unary(config, *operator, expression.clone()),
+            SmartCalcAstType::Assignment { variable, expression } => Rc::new(SmartCalcAstType::Assignment {
+                variable: variable.clone(),
+                expression: Optimizer::optimize(config, expression.clone())
+            }),
+            SmartCalcAstType::Call { name, arguments } => Rc::new(SmartCalcAstType::Call {
+                name: name.clone(),
+                arguments: Optimizer::optimize_list(config, arguments)
+            }),
+            SmartCalcAstType::List(items) => Rc::new(SmartCalcAstType::List(Optimizer::optimize_list(config, items))),
+            SmartCalcAstType::Comparison { left, operator, right } => Rc::new(SmartCalcAstType::Comparison {
+                left: Optimizer::optimize(config, left.clone()),
+                operator: *operator,
+                right: Optimizer::optimize(config, right.clone())
+            }),
+            _ => ast
+        }
+    }
+
+    fn optimize_list(config: &SmartCalcConfig, items: &[Rc<SmartCalcAstType>]) -> Vec<Rc<SmartCalcAstType>> {
+        items.iter().map(|item| Optimizer::optimize(config, item.clone())).collect()
+    }
+
+    fn optimize_binary(config: &SmartCalcConfig, left: Rc<SmartCalcAstType>, operator: char, right: Rc<SmartCalcAstType>) -> Rc<SmartCalcAstType> {
+        let left = Optimizer::optimize(config, left);
+        let right = Optimizer::optimize(config, right);
+
+        if let (SmartCalcAstType::Item(_), SmartCalcAstType::Item(_)) = (left.deref(), right.deref()) {
+            if let Ok(result) = Interpreter::calculate_item(config, operator, left.clone(), right.clone()) {
+                return result;
+            }
+        }
+
+        Rc::new(SmartCalcAstType::Binary { left, operator, right })
+    }
+
+    /* '+' is removed, two '-' cancel each other and '-' of a literal is calculated */
+    fn optimize_unary(config: &SmartCalcConfig, operator: char, expression: Rc<SmartCalcAstType>) -> Rc<SmartCalcAstType> {
+        let expression = Optimizer::optimize(config, expression);
+
+        match (operator, expression.deref()) {
+            ('+', _) => expression,
+            ('-', SmartCalcAstType::PrefixUnary('-', inner)) => inner.clone(),
+            ('-', SmartCalcAstType::Item(item)) => Rc::new(SmartCalcAstType::Item(item.unary(UnaryType::Minus))),
+            _ => Rc::new(SmartCalcAstType::PrefixUnary(operator, expression))
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn optimizer_test() {
+    use alloc::vec;
+    use alloc::string::ToString;
+    use crate::compiler::number::NumberItem;
+    use crate::types::NumberType;
+    let config = SmartCalcConfig::default();
+    let number = |value: f64| Rc::new(SmartCalcAstType::Item(Rc::new(NumberItem(value, NumberType::Decimal))));
+
+    /* -(2 * 3) + 4 */
+    let ast = Rc::new(SmartCalcAstType::Binary {
+        left: Rc::new(SmartCalcAstType::PrefixUnary('-', Rc::new(SmartCalcAstType::Binary { left: number(2.0), operator: '*', right: number(3.0) }))),
+        operator: '+',
+        right: number(4.0)
+    });
+
+    match Optimizer::optimize(&config, ast).deref() {
+        SmartCalcAstType::Item(item) => assert_eq!(item.get_underlying_number(), -2.0),
+        _ => panic!("Literal expression should be folded")
+    };
+
+    /* Calls are not folded, only the arguments */
+    let ast = Rc::new(SmartCalcAstType::Call { name: "sqrt".to_string(), arguments: vec![Rc::new(SmartCalcAstType::PrefixUnary('+', number(16.0)))] });
+    match Optimizer::optimize(&config, ast).deref() {
+        SmartCalcAstType::Call { arguments, .. } => assert!(matches!(arguments[0].deref(), SmartCalcAstType::Item(_))),
+        _ => panic!("Call should be kept")
+    };
+
+    /* Failed calculations are left to the interpreter */
+    let ast = Rc::new(SmartCalcAstType::Binary { left: number(10.0), operator: '^', right: number(400.0) });
+    assert!(matches!(Optimizer::optimize(&config, ast).deref(), SmartCalcAstType::Binary { .. }));
+}
@@ -11,7 +11,7 @@ use crate::session::Session;
 use crate::config::SmartCalcConfig;
 use crate::types::TokenType;
 use super::{DataItem, OperationType, UnaryType};
-use crate::formatter::format_number;
+use crate::formatter::{format_number, remove_trailing_zeros};
 use alloc::format;
 use crate::tools::do_divition;
 
@@ -46,7 +46,8 @@ impl DataItem for PercentItem {
             OperationType::Add => left + right,
             OperationType::Div => left / right,
             OperationType::Mul => left * right,
-            OperationType::Sub => left - right
+            OperationType::Sub => left - right,
+            OperationType::Pow => return None
         };
         Some(Rc::new(PercentItem(result)))
     }
@@ -62,7 +63,15 @@ impl DataItem for PercentItem {
     fn get_underlying_number(&self) -> f64 { self.0 }
     fn type_name(&self) -> &'static str { "PERCENT" }
     fn type_id(&self) -> TypeId { TypeId::of::<PercentItem>() }
-    fn print(&self, config: &SmartCalcConfig, _: &Session) -> String { format!("%{:}", format_number(self.0, config.thousand_separator.to_string(), config.decimal_seperator.to_string(), config.percentage_config.decimal_digits, config.percentage_config.remove_fract_if_zero, config.percentage_config.use_fract_rounding)) }
+    fn print(&self, config: &SmartCalcConfig, session: &Session) -> String {
+        let options = session.format_options();
+        let decimal_seperator = config.get_decimal_seperator(&session.get_language());
+        let formated_number = format_number(self.0, options.thousand_separator(config, session), decimal_seperator.to_string(), options.decimal_places(config.percentage_config.decimal_digits), options.remove_fract_if_zero(config.percentage_config.remove_fract_if_zero), config.percentage_config.use_fract_rounding);
+        match options.remove_trailing_zeros(config.percentage_config.remove_trailing_zeros) {
+            true => format!("%{:}", remove_trailing_zeros(formated_number, decimal_seperator)),
+            false => format!("%{:}", formated_number)
+        }
+    }
     fn unary(&self, unary: UnaryType) -> Rc<dyn DataItem> {
         match unary {
             UnaryType::Minus => Rc::new(Self(-1.0 * self.0)),
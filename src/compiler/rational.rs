@@ -0,0 +1,158 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::any::{Any, TypeId};
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use crate::session::Session;
+use crate::config::SmartCalcConfig;
+use crate::types::{TokenType, NumberType};
+use super::number::NumberItem;
+use super::{DataItem, OperationType, UnaryType};
+
+/* Number kept as numerator and denominator, used by the rational mode. Denominator is always positive */
+#[derive(Debug)]
+pub struct RationalItem(pub i128, pub i128);
+
+fn gcd(mut left: i128, mut right: i128) -> i128 {
+    while right != 0 {
+        let remainder = left % right;
+        left = right;
+        right = remainder;
+    }
+    left.abs()
+}
+
+impl RationalItem {
+    pub fn new(numerator: i128, denominator: i128) -> Option<RationalItem> {
+        if denominator == 0 {
+            return None;
+        }
+
+        let divisor = gcd(numerator, denominator);
+        let sign = if denominator < 0 { -1 } else { 1 };
+        Some(RationalItem(sign * (numerator / divisor), sign * (denominator / divisor)))
+    }
+
+    /* Decimal text of the number is used, so 0.1 is 1/10 instead of the nearest binary fraction */
+    pub fn from_number(number: f64) -> Option<RationalItem> {
+        if !number.is_finite() {
+            return None;
+        }
+
+        let text = format!("{}", number);
+        let (integer, fraction) = match text.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (&text[..], "")
+        };
+
+        let denominator = 10_i128.checked_pow(fraction.len() as u32)?;
+        let numerator = format!("{}{}", integer, fraction).parse::<i128>().ok()?;
+        RationalItem::new(numerator, denominator)
+    }
+
+    pub fn get_value(&self) -> f64 {
+        self.0 as f64 / self.1 as f64
+    }
+
+    fn operate(&self, other: &RationalItem, operation_type: OperationType) -> Option<RationalItem> {
+        match operation_type {
+            OperationType::Add => RationalItem::new(self.0.checked_mul(other.1)?.checked_add(other.0.checked_mul(self.1)?)?, self.1.checked_mul(other.1)?),
+            OperationType::Sub => RationalItem::new(self.0.checked_mul(other.1)?.checked_sub(other.0.checked_mul(self.1)?)?, self.1.checked_mul(other.1)?),
+            OperationType::Mul => RationalItem::new(self.0.checked_mul(other.0)?, self.1.checked_mul(other.1)?),
+            OperationType::Div => RationalItem::new(self.0.checked_mul(other.1)?, self.1.checked_mul(other.0)?),
+            OperationType::Pow => None
+        }
+    }
+
+    /* Decimal numbers and rationals are calculated exactly, other numbers are not converted */
+    fn from_item(item: &dyn DataItem) -> Option<RationalItem> {
+        match item.as_any().downcast_ref::<RationalItem>() {
+            Some(rational) => Some(RationalItem(rational.0, rational.1)),
+            None => match item.as_any().downcast_ref::<NumberItem>() {
+                Some(NumberItem(number, NumberType::Decimal)) => RationalItem::from_number(*number),
+                _ => None
+            }
+        }
+    }
+}
+
+impl DataItem for RationalItem {
+    fn as_token_type(&self) -> TokenType {
+        TokenType::Number(self.get_value(), NumberType::Decimal)
+    }
+    fn is_same(&self, other: &dyn Any) -> bool {
+        match other.downcast_ref::<f64>() {
+            Some(value) => (value - self.get_value()).abs() < f64::EPSILON,
+            None => false
+        }
+    }
+    fn as_any(&self) -> &dyn Any { self }
+    fn calculate(&self, config: &SmartCalcConfig, on_left: bool, other: &dyn DataItem, operation_type: OperationType) -> Option<Rc<dyn DataItem>> {
+        let other_rational = match other.type_name() {
+            "NUMBER" => RationalItem::from_item(other),
+            _ => None
+        };
+
+        let result = other_rational.and_then(|other_rational| match on_left {
+            true => self.operate(&other_rational, operation_type),
+            false => other_rational.operate(self, operation_type)
+        });
+
+        match result {
+            Some(result) => Some(Rc::new(result)),
+
+            /* Overflowed and not supported operations are calculated as float */
+            None => NumberItem(self.get_value(), NumberType::Decimal).calculate(config, on_left, other, operation_type)
+        }
+    }
+    fn get_number(&self, _: &dyn DataItem) -> f64 { self.get_value() }
+    fn get_underlying_number(&self) -> f64 { self.get_value() }
+    fn type_name(&self) -> &'static str { "NUMBER" }
+    fn type_id(&self) -> TypeId { TypeId::of::<RationalItem>() }
+    fn print(&self, config: &SmartCalcConfig, session: &Session) -> String {
+        NumberItem(self.get_value(), NumberType::Decimal).print(config, session)
+    }
+    fn unary(&self, unary: UnaryType) -> Rc<dyn DataItem> {
+        match unary {
+            UnaryType::Minus => Rc::new(Self(-self.0, self.1)),
+            UnaryType::Plus => Rc::new(Self(self.0, self.1))
+        }
+    }
+}
+
+/* Decimal numbers are converted to rationals before the calculation, when the rational mode is enabled */
+pub fn to_rational(config: &SmartCalcConfig, item: Rc<dyn DataItem>) -> Rc<dyn DataItem> {
+    if !config.rational_mode || item.as_any().downcast_ref::<RationalItem>().is_some() {
+        return item;
+    }
+
+    match RationalItem::from_item(item.as_ref()) {
+        Some(rational) => Rc::new(rational),
+        None => item
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn rational_test() {
+    use alloc::string::ToString;
+    let config = SmartCalcConfig::default();
+    let session = Session::default();
+
+    assert_eq!(RationalItem::from_number(0.25).map(|item| (item.0, item.1)), Some((1, 4)));
+    assert_eq!(RationalItem::from_number(-1.5).map(|item| (item.0, item.1)), Some((-3, 2)));
+
+    let third = RationalItem::new(1, 3).unwrap();
+    let result = third.calculate(&config, true, &NumberItem(3.0, NumberType::Decimal), OperationType::Mul).unwrap();
+    assert_eq!(result.get_underlying_number(), 1.0);
+
+    let result = RationalItem::from_number(0.1).unwrap().calculate(&config, true, &NumberItem(0.2, NumberType::Decimal), OperationType::Add).unwrap();
+    let result = result.calculate(&config, true, &NumberItem(0.3, NumberType::Decimal), OperationType::Sub).unwrap();
+    assert_eq!(result.get_underlying_number(), 0.0);
+    assert_eq!(RationalItem::new(2, 3).unwrap().print(&config, &session), "0,67".to_string());
+}
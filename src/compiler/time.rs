@@ -43,27 +43,23 @@ impl DataItem for TimeItem {
     }
     fn as_any(&self) -> &dyn Any { self }
     
-    fn calculate(&self, _: &SmartCalcConfig, on_left: bool, other: &dyn DataItem, operation_type: OperationType) -> Option<Rc<dyn DataItem>> {
+    fn calculate(&self, config: &SmartCalcConfig, on_left: bool, other: &dyn DataItem, operation_type: OperationType) -> Option<Rc<dyn DataItem>> {
         /* If both item is money and current money is on left side, skip calculation */
         if TypeId::of::<Self>() == other.type_id() && !on_left {
             return None;
         }
         
-        let (right, is_negative) = match other.type_name() {
+        /* Days of the duration are not used, negative durations move the time to the other direction */
+        let calculated_right = match other.type_name() {
             "DURATION" => {
                 let duration = other.as_any().downcast_ref::<DurationItem>()?;
-                (duration.as_time(), duration.get_duration().num_seconds().is_negative())
+                let seconds = duration.as_time(config).num_seconds_from_midnight() as i64;
+                Duration::seconds(seconds * duration.get_duration().num_seconds().signum())
             },
-            "TIME" => (other.as_any().downcast_ref::<TimeItem>()?.get_time(), false),
+            "TIME" => Duration::seconds(other.as_any().downcast_ref::<TimeItem>()?.get_time().num_seconds_from_midnight() as i64),
             _ => return None
         };
 
-        let calculated_right = Duration::seconds(right.num_seconds_from_midnight() as i64);
-
-        if is_negative {
-            return Some(Rc::new(TimeItem(self.0 - calculated_right, self.1.clone())));
-        }
-        
         match operation_type {
             OperationType::Add => Some(Rc::new(TimeItem(self.0 + calculated_right, self.1.clone()))),
             OperationType::Sub => Some(Rc::new(TimeItem(self.0 - calculated_right, self.1.clone()))),
@@ -6,25 +6,50 @@
 
 use core::borrow::Borrow;
 use alloc::format;
-use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec;
 use alloc::vec::Vec;
 use alloc::collections::btree_map::BTreeMap;
+use chrono::{DateTime, NaiveDate, NaiveDateTime, Utc};
 use regex::Regex;
-use serde_json::from_str;
+use serde_json::{from_str, from_value, to_value, Value};
 use crate::session::Session;
 use crate::tokinizer::RuleItemList;
+use crate::tokinizer::RuleMatcher;
 use crate::tokinizer::RuleType;
-use crate::types::CurrencyInfo;
+use crate::types::{CurrencyInfo, FieldType};
 use crate::types::TimeOffset;
 use crate::tokinizer::Tokinizer;
-use crate::tokinizer::TokenInfo;
+use crate::tokinizer::TokenParser;
+use crate::tokinizer::{PatternToken, PatternType};
+use crate::tokinizer::KeywordFilter;
+use crate::interner::Interner;
 use crate::tokinizer::RULE_FUNCTIONS;
 use crate::constants::*;
+use crate::tokinizer::small_date;
+use crate::types::ExpressionFunc;
+use lazy_static::*;
 
 pub type LanguageData<T> = BTreeMap<String, T>;
-pub type CurrencyData<T> = BTreeMap<Rc<CurrencyInfo>, T>;
+pub type CurrencyData<T> = BTreeMap<Arc<CurrencyInfo>, T>;
+pub type TimeProvider = Arc<dyn Fn() -> DateTime<Utc> + Send + Sync>;
+
+/// How money is written, `$1.500,00`, `1.500,00 USD` or `1.500 US dollars`.
+#[derive(Default)]
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum CurrencyStyle {
+    /* $1.500,00 */
+    #[default]
+    Symbol,
+    /* 1.500,00 USD */
+    Code,
+    /* 1.500 US dollars, name of the currency in the session language */
+    Name
+}
 
 #[derive(Default)]
 #[derive(Clone)]
@@ -32,7 +57,25 @@ pub type CurrencyData<T> = BTreeMap<Rc<CurrencyInfo>, T>;
 #[derive(PartialEq)]
 pub struct MoneyConfig {
     pub remove_fract_if_zero: bool,
-    pub use_fract_rounding: bool
+    pub use_fract_rounding: bool,
+    pub remove_trailing_zeros: bool,
+    pub style: CurrencyStyle
+}
+
+/// How a number is rounded to the configured decimal digits.
+#[derive(Default)]
+#[derive(Clone)]
+#[derive(Copy)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum RoundingMode {
+    /* Closest value, 2.346 is 2.35 */
+    #[default]
+    Nearest,
+    /* Away from zero, 2.341 is 2.35 */
+    Up,
+    /* Towards zero, 2.349 is 2.34 */
+    Down
 }
 
 #[derive(Default)]
@@ -42,7 +85,52 @@ pub struct MoneyConfig {
 pub struct NumberConfig {
     pub decimal_digits: u8,
     pub remove_fract_if_zero: bool,
-    pub use_fract_rounding: bool
+    pub use_fract_rounding: bool,
+    pub remove_trailing_zeros: bool,
+    pub rounding: RoundingMode
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum DurationStyle {
+    /* 1 hour 31 minutes */
+    Long,
+    /* 1h 31m */
+    Short,
+    /* 1:31:00, days are written as hours */
+    Clock
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum DurationGranularity {
+    /* Every non-zero unit, from years down to seconds */
+    Full,
+    /* Only the given number of highest non-zero units */
+    Largest(usize),
+    /* Single decimal value in the given unit, like 91.5 minutes */
+    Decimal(DurationFormatType)
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub enum NegativeDurationStyle {
+    /* -2 days */
+    Sign,
+    /* 2 days ago, the sign is used if the language has no relative format */
+    Relative
+}
+
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct DurationConfig {
+    pub style: DurationStyle,
+    pub granularity: DurationGranularity,
+    pub negative: NegativeDurationStyle
 }
 
 #[derive(Default)]
@@ -53,18 +141,35 @@ pub struct DynamicType {
     pub group_name: String,
     pub index: usize,
     pub format: String,
-    pub parse: Vec<Vec<Rc<TokenInfo>>>,
+    pub parse: Vec<Vec<PatternToken>>,
     pub upgrade_code: String,
     pub downgrade_code: String,
     pub names:Vec<String>,
     pub decimal_digits: Option<u8>,
     pub use_fract_rounding: Option<bool>,
-    pub remove_fract_if_zero: Option<bool>
+    pub remove_fract_if_zero: Option<bool>,
+    pub dimension: Dimension
+}
+
+/* Exponents of the base dimensions, "m/s" would be {"length": 1, "time": -1} */
+pub type Dimension = BTreeMap<String, i8>;
+
+/* Groups without a dimension are a base dimension of their own */
+pub fn group_dimension(group_name: &str, dimension: &Dimension) -> Dimension {
+    match dimension.is_empty() {
+        true => {
+            let mut dimension = Dimension::new();
+            dimension.insert(group_name.to_string(), 1);
+            dimension
+        },
+        false => dimension.clone()
+    }
 }
 
 impl DynamicType {
-    pub fn new(group_name: String, index: usize, format: String, parse: Vec<Vec<Rc<TokenInfo>>>, upgrade_code: String, downgrade_code: String, names:Vec<String>, decimal_digits: Option<u8>, use_fract_rounding: Option<bool>, remove_fract_if_zero: Option<bool>) -> Self {
+    pub fn new(group_name: String, index: usize, format: String, parse: Vec<Vec<PatternToken>>, upgrade_code: String, downgrade_code: String, names:Vec<String>, decimal_digits: Option<u8>, use_fract_rounding: Option<bool>, remove_fract_if_zero: Option<bool>) -> Self {
         DynamicType {
+            dimension: group_dimension(&group_name, &Dimension::new()),
             group_name,
             index,
             format,
@@ -79,12 +184,14 @@ impl DynamicType {
     }
 }
 
+#[derive(Clone)]
 pub struct SmartCalcConfig {
     pub(crate) json_data: JsonConstant,
     pub(crate) format: LanguageData<JsonFormat>,
-    pub(crate) currency: LanguageData<Rc<CurrencyInfo>>,
-    pub(crate) currency_alias: LanguageData<Rc<CurrencyInfo>>,
+    pub(crate) currency: LanguageData<Arc<CurrencyInfo>>,
+    pub(crate) currency_alias: LanguageData<Arc<CurrencyInfo>>,
     pub(crate) timezones: BTreeMap<String, i32>,
+    pub(crate) timezone_regions: BTreeMap<String, String>,
     pub(crate) currency_rate: CurrencyData<f64>,
     pub(crate) token_parse_regex: LanguageData<Vec<Regex>>,
     pub(crate) word_group: LanguageData<BTreeMap<String, Vec<String>>>,
@@ -92,16 +199,40 @@ pub struct SmartCalcConfig {
     pub(crate) language_alias_regex: LanguageData<Vec<(Regex, String)>>,
     pub(crate) alias_regex: Vec<(Regex, String)>,
     pub(crate) rule: LanguageData<RuleItemList>,
-    pub(crate) types: BTreeMap<String, BTreeMap<usize, Rc<DynamicType>>>,
+    pub(crate) rule_matcher: LanguageData<RuleMatcher>,
+    pub(crate) types: BTreeMap<String, BTreeMap<usize, Arc<DynamicType>>>,
     pub(crate) type_conversion: Vec<JsonTypeConversion>,
     pub(crate) month_regex: LanguageData<MonthItemList>,
     pub(crate) money_config: MoneyConfig,
     pub(crate) number_config: NumberConfig,
     pub(crate) percentage_config: NumberConfig,
+    pub(crate) duration_config: DurationConfig,
     pub(crate) decimal_seperator: String,
     pub(crate) thousand_separator: String,
+    pub(crate) language_separators: bool,
     pub(crate) timezone: String,
-    pub(crate) timezone_offset: i32
+    pub(crate) timezone_offset: i32,
+    pub(crate) fiscal_year_start_month: u32,
+    pub(crate) rational_mode: bool,
+    pub(crate) relative_date_days: Option<u32>,
+    pub(crate) time_provider: TimeProvider,
+    pub(crate) token_parsers: Vec<(String, TokenParser)>,
+    pub(crate) keyword_filter: KeywordFilter,
+    pub(crate) interner: Interner
+}
+
+fn merge_value(target: &mut Value, patch: Value) {
+    match (target, patch) {
+        (Value::Object(target), Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(&key) {
+                    Some(current) => merge_value(current, value),
+                    None => { target.insert(key, value); }
+                };
+            }
+        },
+        (target, patch) => *target = patch
+    }
 }
 
 impl Default for SmartCalcConfig {
@@ -110,7 +241,32 @@ impl Default for SmartCalcConfig {
     }
 }
 
+lazy_static! {
+    static ref SHARED_CONFIG: Arc<SmartCalcConfig> = {
+        let mut config = SmartCalcConfig::default();
+        config.set_date_rule("en", vec![
+            "{MONTH:month} {NUMBER:day}, {NUMBER:year}".to_string(),
+            "{MONTH:month} {NUMBER:day} {NUMBER:year}".to_string(),
+            "{NUMBER:day}/{NUMBER:month}/{NUMBER:year}".to_string(),
+            "{NUMBER:day} {MONTH:month} {NUMBER:year}".to_string(),
+            "{NUMBER:day} {MONTH:month}".to_string()
+        ]);
+        config.set_date_rule("tr", vec![
+            "{NUMBER:day}/{NUMBER:month}/{NUMBER:year}".to_string(),
+            "{NUMBER:day} {MONTH:month} {NUMBER:year}".to_string(),
+            "{NUMBER:day} {MONTH:month}".to_string()
+        ]);
+        Arc::new(config)
+    };
+}
+
 impl SmartCalcConfig {
+    /// Configuration of `SmartCalc::default()` with the builtin date rules, it is built once and shared.
+    /// Building a configuration parses the embedded JSON and compiles every pattern, sharing it makes new engines cheap.
+    pub fn shared() -> Arc<SmartCalcConfig> {
+        SHARED_CONFIG.clone()
+    }
+
     pub fn get_time_offset(&self) -> TimeOffset {
         TimeOffset {
             name: self.timezone.to_string(),
@@ -118,49 +274,338 @@ impl SmartCalcConfig {
         }
     }
 
-    pub fn get_currency<T: Borrow<String>>(&self, currency: T) -> Option<Rc<CurrencyInfo>> {
+    /* Every "now" and "today" goes through the time provider, so it can be pinned */
+    pub fn get_now(&self) -> NaiveDateTime {
+        (self.time_provider)().naive_utc()
+    }
+
+    pub fn get_today(&self) -> NaiveDate {
+        self.get_now().date()
+    }
+
+    /* Separators of the language are used when language separators are enabled, the configured ones otherwise */
+    pub(crate) fn get_decimal_seperator(&self, language: &str) -> &str {
+        self.get_language_separator(language, "decimal_separator").unwrap_or(&self.decimal_seperator)
+    }
+
+    pub(crate) fn get_thousand_separator(&self, language: &str) -> &str {
+        self.get_language_separator(language, "thousand_separator").unwrap_or(&self.thousand_separator)
+    }
+
+    fn get_language_separator(&self, language: &str, name: &str) -> Option<&str> {
+        match self.language_separators {
+            true => self.format.get(language).and_then(|format| format.number.get(name)).map(|separator| separator.as_str()),
+            false => None
+        }
+    }
+
+    /* Units, currencies, months and rule keywords are used by the tokinizer */
+    pub(crate) fn is_reserved_word(&self, language: &str, name: &str) -> bool {
+        let name = name.to_lowercase();
+
+        self.constant_pair.get(language).is_some_and(|constants| constants.contains_key(&name)) ||
+            self.currency.contains_key(&name) ||
+            self.currency_alias.keys().any(|alias| alias.to_lowercase() == name) ||
+            self.word_group.get(language).is_some_and(|groups| groups.values().any(|words| words.contains(&name))) ||
+            self.month_regex.get(language).is_some_and(|months| months.iter().any(|(_, month)| month.long == name || month.short == name))
+    }
+
+    /* Rule functions read the meaning of the matched words from the word groups of the language */
+    pub(crate) fn is_group_word(&self, language: &str, group: &str, word: &str) -> bool {
+        self.word_group.get(language)
+            .and_then(|groups| groups.get(group))
+            .is_some_and(|words| words.iter().any(|item| item.to_lowercase() == word.to_lowercase()))
+    }
+
+    pub(crate) fn get_constant(&self, language: &str, word: &str) -> Option<&ConstantType> {
+        self.constant_pair.get(language).and_then(|constants| constants.get(&word.to_lowercase()))
+    }
+
+    /* Rule patterns keep a copy of the group words, patterns with the previous words are updated too */
+    pub(crate) fn add_group_word(&mut self, language: &str, group: &str, word: String) -> bool {
+        let words = match self.word_group.get_mut(language) {
+            Some(word_groups) => word_groups.entry(group.to_string()).or_default(),
+            None => return false
+        };
+
+        let previous_words = words.clone();
+        words.push(word.to_string());
+        let interner = &mut self.interner;
+        let words = words.iter().map(|word| interner.intern(word)).collect::<Vec<_>>();
+
+        if let Some(language_data) = self.json_data.languages.get_mut(language) {
+            language_data.word_group.entry(group.to_string()).or_default().push(word);
+        }
+
+        let update_pattern = |rule_token: &PatternToken| match rule_token.field() {
+            Some(FieldType::Group(name, items)) if items.len() == previous_words.len() && items.iter().zip(previous_words.iter()).all(|(item, word)| item.matches(word)) => PatternToken {
+                token_type: Some(PatternType::Field(Arc::new(FieldType::Group(name.to_string(), words.clone())))),
+                ..rule_token.clone()
+            },
+            _ => rule_token.clone()
+        };
+
+        for rule in self.rule.get_mut(language).into_iter().flatten() {
+            let tokens_list = match rule {
+                RuleType::Internal { tokens_list, .. } => tokens_list,
+                RuleType::API { tokens_list, .. } => tokens_list
+            };
+
+            for rule_tokens in tokens_list.iter_mut() {
+                *rule_tokens = rule_tokens.iter().map(update_pattern).collect();
+            }
+        }
+
+        self.update_rule_matcher();
+        true
+    }
+
+    pub fn get_currency<T: Borrow<String>>(&self, currency: T) -> Option<Arc<CurrencyInfo>> {
         self.currency
             .get(currency.borrow())
             .cloned()
     }
 
+    pub(crate) fn set_date_rule(&mut self, language: &str, rules: Vec<String>) {
+        let mut function_items = Vec::new();
+        for rule_item in rules {
+            let mut session = Session::new();
+            session.set_language(language.to_string());
+            session.set_text(rule_item.to_string());
+
+            let mut tokens = Tokinizer::pattern_tokens(self, &session);
+            self.interner.intern_tokens(&mut tokens);
+            function_items.push(tokens);
+        }
+        
+        let current_rules = match self.rule.get_mut(language) {
+            Some(current_rules) => current_rules,
+            None => return
+        };
+
+        /* Remove small_date rule */
+        current_rules.retain(|rule| {
+            let is_small_date = if let RuleType::Internal { function_name, .. } = rule {
+                function_name == "small_date"
+            } else {
+                false
+            };
+            
+            !is_small_date
+        });
+        
+        current_rules.push(RuleType::Internal {
+            function_name: "small_date".to_string(),
+            function: small_date as ExpressionFunc,
+            tokens_list: function_items
+        });
+        self.update_rule_matcher();
+    }
+
+    /* Money and timezones are found only with a known name, so their names are the keywords of these groups */
+    pub(crate) fn update_keyword_filter(&mut self) {
+        let mut keywords = self.json_data.parse_keywords.clone();
+        if let Some(money_keywords) = keywords.get_mut("money") {
+            money_keywords.extend(self.currency.keys().chain(self.currency_alias.keys()).cloned());
+        }
+
+        if let Some(timezone_keywords) = keywords.get_mut("timezone") {
+            timezone_keywords.extend(self.timezones.keys().cloned());
+        }
+
+        self.keyword_filter = KeywordFilter::new(&keywords);
+    }
+
+    /* Rules are compiled again after every change, the matcher finds a rule with its position in the list */
+    pub(crate) fn update_rule_matcher(&mut self) {
+        self.rule_matcher = self.rule.iter().map(|(language, rules)| (language.to_string(), RuleMatcher::new(rules))).collect();
+    }
+
+    /* Known words are interned before the rules, so the rules and the lines use the same texts */
+    fn intern_words(&mut self) {
+        let words = self.currency.keys()
+            .chain(self.currency_alias.keys())
+            .chain(self.alias_regex.iter().map(|(_, word)| word))
+            .chain(self.language_alias_regex.values().flatten().map(|(_, word)| word))
+            .chain(self.word_group.values().flat_map(|groups| groups.values()).flatten())
+            .chain(self.constant_pair.values().flat_map(|constants| constants.keys()))
+            .chain(self.json_data.types.iter().flat_map(|dynamic_type| dynamic_type.items.iter()).flat_map(|type_item| type_item.names.iter()));
+
+        let mut interner = Interner::default();
+        for word in words {
+            interner.intern(word);
+        }
+        self.interner = interner;
+    }
+
     pub fn load_from_json(json_data: &str) -> Self {
+        match SmartCalcConfig::from_json(json_data) {
+            Ok(config) => config,
+            Err(error) => panic!("{}", error)
+        }
+    }
+
+    pub fn from_json(json_data: &str) -> Result<Self, String> {
+        match from_str(json_data) {
+            Ok(data) => Ok(SmartCalcConfig::build(data)),
+            Err(error) => Err(format!("JSON parse error: {}", error))
+        }
+    }
+
+    /* Objects are merged key by key, other values (arrays included) replace the current one.
+       Settings changed from code and rules or types added from code are kept. */
+    pub fn merge_json(&mut self, json_data: &str) -> Result<(), String> {
+        let mut merged = match to_value(&self.json_data) {
+            Ok(merged) => merged,
+            Err(error) => return Err(format!("JSON parse error: {}", error))
+        };
+
+        match from_str(json_data) {
+            Ok(patch) => merge_value(&mut merged, patch),
+            Err(error) => return Err(format!("JSON parse error: {}", error))
+        };
+
+        self.rebuild(merged)
+    }
+
+    /* Language pack is a language object of config.json, a language with the same name is replaced */
+    pub fn load_language(&mut self, language: &str, json_data: &str) -> Result<(), String> {
+        let language_data = match from_str::<JsonLanguageConstant>(json_data) {
+            Ok(language_data) => language_data,
+            Err(error) => return Err(format!("JSON parse error: {}", error))
+        };
+
+        let mut json_data = match to_value(&self.json_data) {
+            Ok(json_data) => json_data,
+            Err(error) => return Err(format!("JSON parse error: {}", error))
+        };
+
+        match (json_data.get_mut("languages").and_then(Value::as_object_mut), to_value(language_data)) {
+            (Some(languages), Ok(language_data)) => { languages.insert(language.to_string(), language_data); },
+            (_, Err(error)) => return Err(format!("JSON parse error: {}", error)),
+            (None, _) => return Err("Languages not found".to_string())
+        };
+
+        self.rebuild(json_data)
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load_language_file<P: AsRef<std::path::Path>>(&mut self, language: &str, path: P) -> Result<(), String> {
+        match std::fs::read_to_string(path) {
+            Ok(json_data) => self.load_language(language, &json_data),
+            Err(error) => Err(format!("Language file could not be read, {}", error))
+        }
+    }
+
+    fn rebuild(&mut self, json_data: Value) -> Result<(), String> {
+        let mut config = match from_value(json_data) {
+            Ok(data) => SmartCalcConfig::build(data),
+            Err(error) => return Err(format!("JSON parse error: {}", error))
+        };
+
+        for (language, rules) in self.rule.iter() {
+            if let Some(new_rules) = config.rule.get_mut(language) {
+                for rule in rules.iter() {
+                    let is_defined = match rule {
+                        RuleType::Internal { function_name, .. } => new_rules.iter().any(|new_rule| matches!(new_rule, RuleType::Internal { function_name: name, .. } if name == function_name)),
+                        RuleType::API { .. } => false
+                    };
+
+                    if !is_defined {
+                        new_rules.push(rule.clone());
+                    }
+                }
+            }
+        }
+
+        config.update_rule_matcher();
+
+        for (language, months) in self.month_regex.iter() {
+            if let Some(new_months) = config.month_regex.get_mut(language) {
+                new_months.extend(months.iter().skip(12).cloned());
+            }
+        }
+
+        for (name, group) in self.types.iter() {
+            if !config.types.contains_key(name) {
+                config.types.insert(name.to_string(), group.clone());
+            }
+        }
+
+        config.decimal_seperator = self.decimal_seperator.to_string();
+        config.thousand_separator = self.thousand_separator.to_string();
+        config.language_separators = self.language_separators;
+        config.timezone = self.timezone.to_string();
+        config.timezone_offset = self.timezone_offset;
+        config.fiscal_year_start_month = self.fiscal_year_start_month;
+        config.rational_mode = self.rational_mode;
+        config.relative_date_days = self.relative_date_days;
+        config.time_provider = self.time_provider.clone();
+        config.token_parsers = self.token_parsers.clone();
+        config.money_config = self.money_config.clone();
+        config.number_config = self.number_config.clone();
+        config.percentage_config = self.percentage_config.clone();
+        config.duration_config = self.duration_config.clone();
+
+        *self = config;
+        Ok(())
+    }
+
+    fn build(json_data: JsonConstant) -> Self {
         let mut config = SmartCalcConfig {
-            json_data: match from_str(json_data) {
-                Ok(data) => data,
-                Err(error) => panic!("JSON parse error: {}", error)
-            },
+            json_data,
             format: LanguageData::new(),
             currency: LanguageData::new(),
             currency_alias: LanguageData::new(),
             timezones: BTreeMap::new(),
+            timezone_regions: BTreeMap::new(),
             currency_rate: CurrencyData::new(),
             token_parse_regex: LanguageData::new(),
             word_group: LanguageData::new(),
             constant_pair: LanguageData::new(),
             language_alias_regex: LanguageData::new(),
             rule: LanguageData::new(),
+            rule_matcher: LanguageData::new(),
             types: BTreeMap::new(),
             type_conversion: Vec::new(),
             month_regex: LanguageData::new(),
             alias_regex: Vec::new(),
             decimal_seperator: ",".to_string(),
             thousand_separator: ".".to_string(),
+            language_separators: false,
             timezone: "UTC".to_string(),
             timezone_offset: 0,
+            fiscal_year_start_month: 1,
+            rational_mode: false,
+            relative_date_days: None,
+            time_provider: Arc::new(Utc::now),
+            token_parsers: Vec::new(),
+            keyword_filter: KeywordFilter::default(),
+            interner: Interner::default(),
             money_config: MoneyConfig {
                 remove_fract_if_zero: false,
-                use_fract_rounding: true
+                use_fract_rounding: true,
+                remove_trailing_zeros: false,
+                style: CurrencyStyle::Symbol
             },
             number_config: NumberConfig {
                 decimal_digits: 2,
                 remove_fract_if_zero: true,
-                use_fract_rounding: true
+                use_fract_rounding: true,
+                remove_trailing_zeros: false,
+                rounding: RoundingMode::Nearest
             },
             percentage_config: NumberConfig {
                 decimal_digits: 2,
                 remove_fract_if_zero: true,
-                use_fract_rounding: true
+                use_fract_rounding: true,
+                remove_trailing_zeros: false,
+                rounding: RoundingMode::Nearest
+            },
+            duration_config: DurationConfig {
+                style: DurationStyle::Long,
+                granularity: DurationGranularity::Full,
+                negative: NegativeDurationStyle::Sign
             },
         };
         
@@ -172,6 +617,10 @@ impl SmartCalcConfig {
             config.timezones.insert(timezone.clone(), *offset);
         }
 
+        for (timezone, region) in config.json_data.timezone_regions.iter() {
+            config.timezone_regions.insert(timezone.to_uppercase(), region.clone());
+        }
+
         for (from, to) in config.json_data.alias.iter() {
             match Regex::new(&format!(r"\b{}\b", from)) {
                 Ok(re) => config.alias_regex.push((re, to.to_string())),
@@ -187,7 +636,7 @@ impl SmartCalcConfig {
 
         for (key, value) in config.json_data.currency_alias.iter() {
             match config.get_currency(value) {
-                Some(currency) => { config.currency_alias.insert(key.to_string(), currency.clone()); },
+                Some(currency) => { config.currency_alias.insert(key.to_lowercase(), currency.clone()); },
                 None => log::warn!("'{}' currency not found at alias", value)
             };
         }
@@ -225,6 +674,8 @@ impl SmartCalcConfig {
             config.token_parse_regex.insert(parse_type.to_string(), patterns);
         }
 
+        config.update_keyword_filter();
+
         for (language, language_constant) in config.json_data.languages.iter() {
             let mut language_group = Vec::new();
             let mut month_list = Vec::with_capacity(12);
@@ -291,6 +742,8 @@ impl SmartCalcConfig {
             config.constant_pair.insert(language.to_string(), constants);
         }
         
+        config.intern_words();
+
         for (language, language_constant) in config.json_data.languages.iter() {
             let mut language_rules = Vec::new();
             for (rule_name, rule) in language_constant.rules.iter() {
@@ -301,7 +754,10 @@ impl SmartCalcConfig {
                         let mut session = Session::new();
                         session.set_language(language.to_string());
                         session.set_text(rule_item.to_string());
-                        function_items.push(Tokinizer::token_infos(&config, &session));
+
+                        let mut tokens = Tokinizer::pattern_tokens(&config, &session);
+                        config.interner.intern_tokens(&mut tokens);
+                        function_items.push(tokens);
                     }
 
                     language_rules.push(RuleType::Internal {
@@ -317,6 +773,8 @@ impl SmartCalcConfig {
 
             config.rule.insert(language.to_string(), language_rules);
         }
+
+        config.update_rule_matcher();
         
         for dynamic_type in config.json_data.types.iter() {
             let mut dynamic_type_holder = BTreeMap::new();
@@ -341,7 +799,8 @@ impl SmartCalcConfig {
                     names: type_item.names.clone(),
                     decimal_digits: type_item.decimal_digits,
                     use_fract_rounding: type_item.use_fract_rounding,
-                    remove_fract_if_zero: type_item.remove_fract_if_zero
+                    remove_fract_if_zero: type_item.remove_fract_if_zero,
+                    dimension: group_dimension(&dynamic_type.name, &dynamic_type.dimension)
                 };
 
                 for type_parse_item in type_item.parse.iter() {
@@ -349,11 +808,12 @@ impl SmartCalcConfig {
                     session.set_language("en".to_string());
                     session.set_text(type_parse_item.to_string());
                     
-                    let tokens = Tokinizer::token_infos(&config, &session);
+                    let mut tokens = Tokinizer::pattern_tokens(&config, &session);
+                    config.interner.intern_tokens(&mut tokens);
                     token_info.parse.push(tokens);
                 }
                 
-                dynamic_type_holder.insert(token_info.index, Rc::new(token_info));
+                dynamic_type_holder.insert(token_info.index, Arc::new(token_info));
             }
             
             config.types.insert(dynamic_type.name.to_string(), dynamic_type_holder);
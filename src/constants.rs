@@ -5,7 +5,7 @@
  */
 
 use crate::types::CurrencyInfo;
-use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::{collections::btree_map::BTreeMap};
 use alloc::string::String;
 use alloc::vec::Vec;
@@ -14,7 +14,7 @@ use serde_derive::{Deserialize, Serialize};
 use serde_repr::*;
 
 
-#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub enum DurationFormatType {
     Second,
     Minute,
@@ -39,11 +39,100 @@ pub struct MonthInfo {
     pub month: u8,
 }
 
+/* Plural forms of a language, the forms are written in the order of the categories */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PluralRule {
+    /* 1 minute, 2 minutes */
+    #[default]
+    OneOther,
+
+    /* 0 jour, 1 jour, 2 jours */
+    ZeroOneOther,
+
+    /* 1 минута, 2 минуты, 5 минут */
+    OneFewMany,
+
+    /* 1 gün, 2 gün */
+    Invariant,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PluralCategory {
+    One,
+    Few,
+    Many,
+    Other,
+}
+
+impl PluralCategory {
+    pub fn from_name(name: &str) -> Option<PluralCategory> {
+        match name.trim() {
+            "one" => Some(PluralCategory::One),
+            "few" => Some(PluralCategory::Few),
+            "many" => Some(PluralCategory::Many),
+            "other" => Some(PluralCategory::Other),
+            _ => None
+        }
+    }
+}
+
+impl PluralRule {
+    pub fn categories(&self) -> &'static [PluralCategory] {
+        match self {
+            PluralRule::OneOther | PluralRule::ZeroOneOther => &[PluralCategory::One, PluralCategory::Other],
+            PluralRule::OneFewMany => &[PluralCategory::One, PluralCategory::Few, PluralCategory::Many],
+            PluralRule::Invariant => &[PluralCategory::Other]
+        }
+    }
+
+    pub fn category(&self, count: f64) -> PluralCategory {
+        let count = count.abs();
+        let is_integer = count.fract() == 0.0;
+
+        match self {
+            PluralRule::OneOther if count == 1.0 => PluralCategory::One,
+            PluralRule::ZeroOneOther if count < 2.0 => PluralCategory::One,
+            PluralRule::OneFewMany if !is_integer => PluralCategory::Few,
+            PluralRule::OneFewMany => match (count % 10.0, count % 100.0) {
+                (last, tens) if last == 1.0 && tens != 11.0 => PluralCategory::One,
+                (last, tens) if (2.0..=4.0).contains(&last) && !(12.0..=14.0).contains(&tens) => PluralCategory::Few,
+                _ => PluralCategory::Many
+            },
+            _ => PluralCategory::Other
+        }
+    }
+
+    /* Forms are separated with '|', "US dollar|US dollars". Missing forms fall back to the last one */
+    pub fn select<'a>(&self, forms: &'a str, count: f64) -> &'a str {
+        let category = self.category(count);
+        let index = self.categories().iter().position(|item| *item == category).unwrap_or_default();
+        let forms = forms.split('|').collect::<Vec<_>>();
+        forms.get(index).or_else(|| forms.last()).copied().unwrap_or_default()
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct JsonFormat {
+    #[serde(default)]
+    pub plural: PluralRule,
+
     pub duration: Vec<DurationFormat>,
+
+    #[serde(default)]
+    pub duration_short: Vec<DurationFormat>,
+
     pub date: BTreeMap<String, String>,
 
+    #[serde(default)]
+    pub boolean: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub number: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub currency: BTreeMap<String, String>,
+
     #[serde(skip)]
     pub language: String,
 }
@@ -61,6 +150,11 @@ pub enum ConstantType {
     Tomorrow = 9,
     Yesterday = 10,
     Now = 11,
+    Pi = 12,
+    E = 13,
+    Tau = 14,
+    PreviousResult = 15,
+    Unset = 16,
 }
 
 #[derive(Clone, Debug, Serialize_repr, Deserialize_repr)]
@@ -90,6 +184,11 @@ impl ConstantType {
             9 => Some(ConstantType::Tomorrow),
             10 => Some(ConstantType::Yesterday),
             11 => Some(ConstantType::Now),
+            12 => Some(ConstantType::Pi),
+            13 => Some(ConstantType::E),
+            14 => Some(ConstantType::Tau),
+            15 => Some(ConstantType::PreviousResult),
+            16 => Some(ConstantType::Unset),
             _ => None,
         }
     }
@@ -125,7 +224,10 @@ pub struct JsonLanguageConstant {
 #[derive(Serialize, Deserialize)]
 pub struct JsonDynamicType {
     pub name: String,
-    pub items: Vec<JsonDynamicTypeItem>
+    pub items: Vec<JsonDynamicTypeItem>,
+
+    #[serde(default)]
+    pub dimension: BTreeMap<String, i8>
 }
 
 #[derive(Default)]
@@ -178,13 +280,17 @@ pub struct JsonDynamicTypeItem {
 pub struct JsonConstant {
     pub default_language: String,
     pub parse: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub parse_keywords: BTreeMap<String, Vec<String>>,
     pub alias: BTreeMap<String, String>,
     pub currency_alias: BTreeMap<String, String>,
     pub currency_rates: BTreeMap<String, f64>,
-    pub currencies: BTreeMap<String, Rc<CurrencyInfo>>,
+    pub currencies: BTreeMap<String, Arc<CurrencyInfo>>,
     pub languages: BTreeMap<String, JsonLanguageConstant>,
     pub type_group: BTreeMap<String, Vec<String>>,
     pub timezones: BTreeMap<String, i32>,
+    #[serde(default)]
+    pub timezone_regions: BTreeMap<String, String>,
     pub type_conversion: Vec<JsonTypeConversion>,
     pub types: Vec<JsonDynamicType>
 }
@@ -6,15 +6,68 @@
 
 use alloc::{string::String};
 use alloc::format;
+use alloc::vec::Vec;
 use alloc::string::ToString;
 use crate::session::Session;
 use crate::tools::do_divition;
 use core::ops::Deref;
 
-use crate::config::SmartCalcConfig;
-use crate::types::{SmartCalcAstType};
+use crate::config::{SmartCalcConfig, CurrencyStyle, DurationStyle, RoundingMode};
+use crate::types::{SmartCalcAstType, TokenType};
 use crate::constants::MonthInfo;
 
+/// Formatting of a single `SmartCalc::format_result` call, unset fields use the configuration.
+#[derive(Default)]
+#[derive(Clone)]
+#[derive(Debug)]
+#[derive(PartialEq)]
+pub struct FormatOptions {
+    pub decimal_places: Option<u8>,
+    pub thousand_separator: Option<String>,
+    pub currency_style: Option<CurrencyStyle>,
+    pub duration_style: Option<DurationStyle>,
+    pub rounding: Option<RoundingMode>,
+    /// Write whole numbers without the fraction, `$5` instead of `$5,00`.
+    pub remove_fract_if_zero: Option<bool>,
+    /// Write fractions without the zeros at the end, `2,5` instead of `2,50`.
+    pub remove_trailing_zeros: Option<bool>,
+    /// Text around the result, like `{input} = {result}` or `{result} ({currency_code})`.
+    pub template: Option<String>
+}
+
+impl FormatOptions {
+    pub(crate) fn decimal_places(&self, decimal_places: u8) -> u8 {
+        self.decimal_places.unwrap_or(decimal_places)
+    }
+
+    pub(crate) fn thousand_separator(&self, config: &SmartCalcConfig, session: &Session) -> String {
+        match &self.thousand_separator {
+            Some(thousand_separator) => thousand_separator.to_string(),
+            None => config.get_thousand_separator(&session.get_language()).to_string()
+        }
+    }
+
+    pub(crate) fn rounding(&self, rounding: RoundingMode) -> RoundingMode {
+        self.rounding.unwrap_or(rounding)
+    }
+
+    pub(crate) fn remove_fract_if_zero(&self, remove_fract_if_zero: bool) -> bool {
+        self.remove_fract_if_zero.unwrap_or(remove_fract_if_zero)
+    }
+
+    pub(crate) fn remove_trailing_zeros(&self, remove_trailing_zeros: bool) -> bool {
+        self.remove_trailing_zeros.unwrap_or(remove_trailing_zeros)
+    }
+
+    pub(crate) fn currency_style(&self, config: &SmartCalcConfig) -> CurrencyStyle {
+        self.currency_style.clone().unwrap_or_else(|| config.money_config.style.clone())
+    }
+
+    pub(crate) fn duration_style(&self, config: &SmartCalcConfig) -> DurationStyle {
+        self.duration_style.clone().unwrap_or_else(|| config.duration_config.style.clone())
+    }
+}
+
 pub const MINUTE: i64 = 60;
 pub const HOUR: i64 = MINUTE * 60;
 pub const DAY: i64 = HOUR * 24;
@@ -42,9 +95,28 @@ pub fn left_padding(number: i64, size: usize) -> String {
     format!("{:0width$}", &number, width = size)
 }
 
+/* Numbers that are already at the given digits are not moved, so 0.1 + 0.2 is not rounded up to 0.31 */
+pub fn round_number(number: f64, decimal_digits: u8, rounding: RoundingMode) -> f64 {
+    let divider = 10_f64.powi(decimal_digits.into());
+    let scaled_number = number * divider;
+    if !scaled_number.is_finite() || (scaled_number - scaled_number.round()).abs() < 1e-9 * scaled_number.abs().max(1.0) {
+        return number;
+    }
+
+    match rounding {
+        RoundingMode::Nearest => number,
+        RoundingMode::Up => do_divition(scaled_number.abs().ceil().copysign(number), divider),
+        RoundingMode::Down => do_divition(scaled_number.trunc(), divider)
+    }
+}
+
 pub fn format_number(number: f64, thousands_separator: String, decimal_separator: String, decimal_digits: u8, remove_fract_if_zero: bool, use_fract_rounding: bool) -> String {
     let divider      = 10_u32.pow(decimal_digits.into());
-    let fract_number = do_divition((number * divider as f64).round(), divider as f64);
+    let scaled_number = number * divider as f64;
+    let fract_number = match scaled_number.is_finite() {
+        true => do_divition(scaled_number.round(), divider as f64),
+        false => number
+    };
     let trunc_part   = fract_number.trunc().abs().to_string();
 
     let formated_number = match use_fract_rounding {
@@ -81,6 +153,14 @@ pub fn format_number(number: f64, thousands_separator: String, decimal_separator
     trunc_formated
 }
 
+/* Only the fraction is trimmed, the separator is removed too if nothing is left after it */
+pub fn remove_trailing_zeros(number: String, decimal_separator: &str) -> String {
+    match decimal_separator.is_empty() || !number.contains(decimal_separator) {
+        true => number,
+        false => number.trim_end_matches('0').trim_end_matches(decimal_separator).to_string()
+    }
+}
+
 pub fn get_month_info(config: &SmartCalcConfig, language: &'_ str, month: u8) -> Option<MonthInfo> {
     match config.month_regex.get(language) {
         Some(month_list) => month_list.get((month - 1) as usize).map(|(_, month)| month.clone()),
@@ -88,6 +168,38 @@ pub fn get_month_info(config: &SmartCalcConfig, language: &'_ str, month: u8) ->
     }
 }
 
+/* Names of the date formats in the language formats */
+pub const DATE_FORMAT_NAMES: [&str; 4] = ["full_date", "current_year", "full_date_time", "current_year_with_time"];
+
+/* %d.%m.%Y is converted to {day_pad}.{month_pad}.{year}, unknown specifiers are kept as they are */
+pub fn date_pattern_to_format(pattern: &str) -> String {
+    let mut format = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            format.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            Some('d') => format.push_str("{day_pad}"),
+            Some('e') => format.push_str("{day}"),
+            Some('m') => format.push_str("{month_pad}"),
+            Some('B') => format.push_str("{month_long}"),
+            Some('b') => format.push_str("{month_short}"),
+            Some('Y') => format.push_str("{year}"),
+            Some('H') => format.push_str("{hour_pad}"),
+            Some('M') => format.push_str("{minute_pad}"),
+            Some('S') => format.push_str("{second_pad}"),
+            Some('Z') => format.push_str("{timezone}"),
+            Some('%') => format.push('%'),
+            Some(other) => { format.push('%'); format.push(other); },
+            None => format.push('%')
+        };
+    }
+    format
+}
+
 pub fn uppercase_first_letter(s: &'_ str) -> String {
     let mut c = s.chars();
     match c.next() {
@@ -96,9 +208,58 @@ pub fn uppercase_first_letter(s: &'_ str) -> String {
     }
 }
 
-pub fn format_result(config: &SmartCalcConfig, session: &Session, result: alloc::rc::Rc<SmartCalcAstType>) -> String {
+/// Prints the calculated results, a custom one can render HTML or speech friendly text and call `DefaultFormatter` for the rest.
+pub trait ResultFormatter: Send + Sync {
+    fn format(&self, config: &SmartCalcConfig, session: &Session, result: alloc::rc::Rc<SmartCalcAstType>, options: &FormatOptions) -> String;
+}
+
+pub struct DefaultFormatter;
+
+/* Options are kept in the session while the result is printed, the items read them from there */
+impl ResultFormatter for DefaultFormatter {
+    fn format(&self, config: &SmartCalcConfig, session: &Session, result: alloc::rc::Rc<SmartCalcAstType>, options: &FormatOptions) -> String {
+        let previous_options = session.replace_format_options(options.clone());
+        let output = format_ast(config, session, result);
+        session.replace_format_options(previous_options);
+        output
+    }
+}
+
+/* Template placeholders are {input}, {result}, {currency_code} and {currency_symbol}, currency is empty if the result is not money */
+pub fn apply_template(output: String, input: &str, result: &SmartCalcAstType, options: &FormatOptions) -> String {
+    let template = match &options.template {
+        Some(template) => template,
+        None => return output
+    };
+
+    let currency = match result {
+        SmartCalcAstType::Item(item) => match item.as_token_type() {
+            TokenType::Money(_, currency) => Some(currency),
+            _ => None
+        },
+        _ => None
+    };
+
+    /* Input is replaced last, so the placeholders written in the input are not replaced */
+    template
+        .replace("{currency_code}", currency.as_ref().map(|currency| currency.code.as_str()).unwrap_or_default())
+        .replace("{currency_symbol}", currency.as_ref().map(|currency| currency.symbol.as_str()).unwrap_or_default())
+        .replace("{result}", &output)
+        .replace("{input}", input)
+}
+
+fn format_ast(config: &SmartCalcConfig, session: &Session, result: alloc::rc::Rc<SmartCalcAstType>) -> String {
     match result.deref() {
         SmartCalcAstType::Item(item) => item.print(config, session),
+        SmartCalcAstType::List(items) => {
+            /* Items are separated with ';' when the decimal separator is ',' */
+            let separator = match config.get_decimal_seperator(&session.get_language()) == "," {
+                true => "; ",
+                false => ", "
+            };
+
+            format!("[{}]", items.iter().map(|item| format_ast(config, session, item.clone())).collect::<Vec<_>>().join(separator))
+        },
         _ => "".to_string()
     }
 }
@@ -110,6 +271,14 @@ fn get_frac_test() {
     assert_eq!(fract_information(987654321.987), 987);
 }
 
+#[cfg(test)]
+#[test]
+fn date_pattern_to_format_test() {
+    assert_eq!(date_pattern_to_format("%d %B %Y"), "{day_pad} {month_long} {year}");
+    assert_eq!(date_pattern_to_format("%d.%m.%Y %H:%M"), "{day_pad}.{month_pad}.{year} {hour_pad}:{minute_pad}");
+    assert_eq!(date_pattern_to_format("{day} %b, 100%% %q %"), "{day} {month_short}, 100% %q %");
+}
+
 #[cfg(test)]
 #[test]
 fn format_number_test() {
@@ -150,6 +319,7 @@ fn format_result_test() {
     assert_eq!(NumberItem(1.123456789, NumberType::Decimal).print(&config, &session), "1,12".to_string());
     assert_eq!(NumberItem(2.0, NumberType::Hexadecimal).print(&config, &session), "0x2".to_string());
             
-    assert_eq!(format_result(&config, &session, Rc::new(SmartCalcAstType::Item(Rc::new(TimeItem(chrono::Utc::today().and_hms(11, 30, 0).naive_utc(), config.get_time_offset()))))), "11:30:00 UTC".to_string());
-    assert_eq!(format_result(&config, &session, Rc::new(SmartCalcAstType::Item(Rc::new(TimeItem(chrono::Utc::today().and_hms(0, 0, 0).naive_utc(), config.get_time_offset()))))), "00:00:00 UTC".to_string());
+    let format_result = |result: Rc<SmartCalcAstType>| apply_template(DefaultFormatter.format(&config, &session, result.clone(), &FormatOptions::default()), "", &result, &FormatOptions::default());
+    assert_eq!(format_result(Rc::new(SmartCalcAstType::Item(Rc::new(TimeItem(chrono::Utc::today().and_hms(11, 30, 0).naive_utc(), config.get_time_offset()))))), "11:30:00 UTC".to_string());
+    assert_eq!(format_result(Rc::new(SmartCalcAstType::Item(Rc::new(TimeItem(chrono::Utc::today().and_hms(0, 0, 0).naive_utc(), config.get_time_offset()))))), "00:00:00 UTC".to_string());
 }
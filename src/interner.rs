@@ -0,0 +1,175 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::fmt;
+use core::ops::Deref;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::collections::BTreeSet;
+use serde::ser::{Serialize, Serializer};
+
+use crate::tokinizer::{PatternToken, PatternType};
+use crate::types::FieldType;
+
+/// Text of a token with its lowercase form, texts are compared without case.
+///
+/// Words known by the configuration share the lowercase form with the rules, so they are compared by pointer.
+#[derive(Clone)]
+pub struct Symbol {
+    text: Arc<str>,
+    key: Arc<str>
+}
+
+impl Symbol {
+    pub fn new(text: &str) -> Self {
+        let key = text.to_lowercase();
+        match key == text {
+            true => Symbol::from_key(text, Arc::from(key)),
+            false => Symbol { text: Arc::from(text), key: Arc::from(key) }
+        }
+    }
+
+    /* Text is kept in the same buffer when it is already written in lowercase */
+    fn from_key(text: &str, key: Arc<str>) -> Self {
+        match &key[..] == text {
+            true => Symbol { text: key.clone(), key },
+            false => Symbol { text: Arc::from(text), key }
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Lowercase form of the text.
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Compare with a text without case, the text is not copied.
+    pub fn matches(&self, text: &str) -> bool {
+        self.key.chars().eq(text.chars().flat_map(char::to_lowercase))
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.key, &other.key) || self.key == other.key
+    }
+}
+
+impl Eq for Symbol {}
+
+impl Deref for Symbol {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.text
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.text[..], f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(text: &str) -> Self {
+        Symbol::new(text)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(text: String) -> Self {
+        Symbol::new(&text)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.text)
+    }
+}
+
+/* Lowercase forms of the words used by the configuration: currencies, units, constants, word groups and rule words */
+#[derive(Clone, Default)]
+pub(crate) struct Interner {
+    keys: BTreeSet<Arc<str>>
+}
+
+impl Interner {
+    pub(crate) fn intern(&mut self, text: &str) -> Symbol {
+        let symbol = self.symbol(text);
+        if !self.keys.contains(&symbol.key) {
+            self.keys.insert(symbol.key.clone());
+        }
+        symbol
+    }
+
+    /* Words of the lines are only looked up, the words of a line are not kept after the line */
+    pub(crate) fn symbol(&self, text: &str) -> Symbol {
+        let key = match text.chars().any(char::is_uppercase) {
+            true => self.keys.get(&text.to_lowercase()[..]),
+            false => self.keys.get(text)
+        };
+
+        match key {
+            Some(key) => Symbol::from_key(text, key.clone()),
+            None => Symbol::new(text)
+        }
+    }
+
+    /* Rule patterns are tokinized with the immutable configuration, their words are added after */
+    pub(crate) fn intern_tokens(&mut self, tokens: &mut [PatternToken]) {
+        for token in tokens.iter_mut() {
+            token.token_type = match token.token_type.take() {
+                Some(PatternType::Text(text)) => Some(PatternType::Text(self.intern(&text))),
+                Some(PatternType::Field(field)) => Some(PatternType::Field(match field.deref() {
+                    FieldType::Text(name, expected) => Arc::new(FieldType::Text(name.clone(), expected.as_ref().map(|expected| self.intern(expected)))),
+                    FieldType::Group(name, items) => Arc::new(FieldType::Group(name.clone(), items.iter().map(|item| self.intern(item)).collect())),
+                    _ => field
+                })),
+                token_type => token_type
+            };
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn interner_test() {
+    use alloc::string::ToString;
+    use crate::config::SmartCalcConfig;
+
+    assert_eq!(Symbol::new("Erhan"), Symbol::new("ERHAN"));
+    assert_ne!(Symbol::new("erhan"), Symbol::new("baris"));
+    assert!(Symbol::new("Şeker").matches("şEKER"));
+    assert_eq!(Symbol::new("Erhan").to_string(), "Erhan");
+
+    let mut interner = Interner::default();
+    let usd = interner.intern("USD");
+    assert_eq!(usd.text(), "USD");
+    assert_eq!(usd.key(), "usd");
+    assert_eq!(interner.keys.len(), 1);
+
+    let line_usd = interner.symbol("usd");
+    assert!(Arc::ptr_eq(&line_usd.key, &usd.key));
+    assert!(Arc::ptr_eq(&line_usd.text, &line_usd.key));
+    assert!(!Arc::ptr_eq(&interner.symbol("eur").key, &interner.symbol("eur").key));
+    assert_eq!(interner.keys.len(), 1);
+
+    /* Words of the lines share the words of the rules and the currencies */
+    let config = SmartCalcConfig::default();
+    assert!(Arc::ptr_eq(&config.interner.symbol("Try").key, &config.interner.symbol("try").key));
+    assert!(Arc::ptr_eq(&config.interner.symbol("of").key, &config.interner.symbol("OF").key));
+}
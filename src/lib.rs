@@ -6,6 +6,9 @@
 
 #![no_std]
 extern crate alloc;
+
+#[cfg(feature = "std")]
+extern crate std;
 extern crate lazy_static;
 extern crate log;
 
@@ -24,22 +27,56 @@ pub(crate) mod logger;
 pub(crate) mod formatter;
 pub(crate) mod token;
 pub(crate) mod config;
+pub(crate) mod interner;
 pub(crate) mod smartcalc;
 pub(crate) mod variable;
 pub(crate) mod session;
+pub(crate) mod storage;
+pub(crate) mod result;
+pub(crate) mod serialize;
+#[cfg(feature = "async")]
+pub(crate) mod stream;
+#[cfg(feature = "parallel")]
+pub(crate) mod parallel;
 
 #[cfg(test)]
 mod tests;
 
 pub use smartcalc::SmartCalc;
 pub use session::Session;
+pub use session::CancellationToken;
 pub use config::SmartCalcConfig;
+pub use config::DurationStyle;
+pub use config::DurationGranularity;
+pub use config::NegativeDurationStyle;
+pub use config::RoundingMode;
+pub use config::CurrencyStyle;
+pub use formatter::FormatOptions;
+pub use formatter::ResultFormatter;
+pub use formatter::DefaultFormatter;
+pub use constants::DurationFormatType;
 pub use types::SmartCalcAstType;
+pub use types::ComparisonType;
+pub use types::AstVisitor;
+pub use variable::VariableInfo;
 pub use types::FieldType;
+pub use interner::Symbol;
 pub use compiler::DataItem;
+pub use compiler::Evaluator;
+pub use compiler::Interpreter;
 pub use token::ui_token::UiToken;
 pub use token::ui_token::UiTokenType;
 pub use smartcalc::RuleTrait;
+pub use smartcalc::TraceStep;
+pub use smartcalc::RuleMatch;
+pub use result::ResultValue;
+pub use result::LineResult;
+#[cfg(feature = "async")]
+pub use stream::ExecuteStream;
 pub use types::TokenType;
 pub use types::NumberType;
-pub use types::TimeOffset;
\ No newline at end of file
+pub use types::TimeOffset;
+pub use types::CurrencyInfo;
+pub use constants::ConstantType;
+pub use tokinizer::Tokinizer;
+pub use tokinizer::TokenParser;
@@ -0,0 +1,91 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use rayon::prelude::*;
+
+use crate::result::LineResult;
+use crate::session::{LineJob, Session};
+use crate::smartcalc::{ExecuteLine, SmartCalc};
+
+/* Result of a line with what it needs from the other lines, reference counted values of the line are not moved out of the worker */
+struct LineOutput {
+    line: Option<LineResult>,
+    interrupted: bool,
+    uses_lines: bool,
+    assigned_variables: Vec<String>
+}
+
+fn execute_line(smartcalc: &SmartCalc, job: &LineJob) -> LineOutput {
+    let session = job.session();
+    session.start_line_tracking();
+
+    let line = smartcalc.execute_text(&session);
+    let (uses_lines, assigned_variables) = session.take_line_dependencies();
+
+    /* Rules are not applied completely on an interrupted line, so the result is not used */
+    match session.interruption() {
+        Some(error) => LineOutput { line: Some(LineResult::from(&ExecuteLine::new(Err(error), Vec::new(), Vec::new(), Vec::new()))), interrupted: true, uses_lines, assigned_variables },
+        None => LineOutput { line: line.as_ref().map(LineResult::from), interrupted: false, uses_lines, assigned_variables }
+    }
+}
+
+/* Lines depend on each other when a line reads the results of the others or assigns a variable written in a following line */
+fn has_dependencies(jobs: &[LineJob], outputs: &[LineOutput]) -> bool {
+    outputs.iter().enumerate().any(|(index, output)| output.uses_lines || output.assigned_variables.iter().any(|name| {
+        let name = name.to_lowercase();
+        jobs[index + 1..].iter().any(|job| job.text.to_lowercase().contains(&name[..]))
+    }))
+}
+
+/* Every line is calculated on its own session, the text is calculated again line by line on the calling thread when the lines depend
+   on each other */
+pub(crate) fn execute(smartcalc: &SmartCalc, session: &Session) -> Vec<Option<LineResult>> {
+    let jobs = session.line_jobs();
+    let outputs = jobs.par_iter()
+        .map(|job| execute_line(smartcalc, job))
+        .collect::<Vec<_>>();
+
+    if has_dependencies(&jobs, &outputs) {
+        return smartcalc.execute_session(session).lines.iter().map(|line| line.as_ref().map(LineResult::from)).collect();
+    }
+
+    let mut lines = Vec::with_capacity(outputs.len());
+    for output in outputs {
+        lines.push(output.line);
+        if output.interrupted {
+            break;
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+#[test]
+fn execute_parallel_test() {
+    use alloc::string::ToString;
+    use crate::session::CancellationToken;
+
+    let calc = SmartCalc::default();
+    let outputs = |lines: Vec<Option<LineResult>>| lines.into_iter().map(|line| line.map(|line| line.diagnostics.first().cloned().unwrap_or(line.formatted))).collect::<Vec<_>>();
+    let sequential = |text: &str| outputs(calc.execute("en", text).lines.iter().map(|line| line.as_ref().map(LineResult::from)).collect());
+
+    let text = "10 + 20\n\n$10 to try\n#lang: tr\n2 saat 30 dakika\nx = 5\n/* start\n1 + 1 */ 2 * 3";
+    assert_eq!(outputs(calc.execute_parallel("en", text)), sequential(text));
+    assert_eq!(outputs(calc.execute_parallel("en", "a = 2\n\na * 3")), [Some("2".to_string()), None, Some("6".to_string())]);
+    assert_eq!(outputs(calc.execute_parallel("en", "10\n20\ntotal")), [Some("10".to_string()), Some("20".to_string()), Some("30".to_string())]);
+
+    let mut session = Session::new();
+    let token = CancellationToken::new();
+    session.set_language("en".to_string());
+    session.set_text("1 + 2\n3 + 4".to_string());
+    session.set_cancellation_token(token.clone());
+    token.cancel();
+
+    assert_eq!(outputs(execute(&calc, &session)), [Some("Calculation is cancelled".to_string())]);
+}
@@ -0,0 +1,106 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use crate::smartcalc::ExecuteLine;
+use crate::token::ui_token::UiToken;
+use crate::types::{SmartCalcAstType, TimeOffset, TokenType};
+
+/// Calculated value of a line, without the internal item types.
+///
+/// Times and date times are in UTC, the offset is the timezone used for printing.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResultValue {
+    Number(f64),
+    Money { amount: f64, currency: String },
+    Percent(f64),
+    Date(NaiveDate, TimeOffset),
+    Time(NaiveDateTime, TimeOffset),
+    DateTime(NaiveDateTime, TimeOffset),
+    Duration(Duration),
+    Bool(bool),
+    Text(String),
+    Unit { value: f64, group: String, name: String },
+    List(Vec<ResultValue>)
+}
+
+impl ResultValue {
+    pub fn from_ast(ast: &SmartCalcAstType) -> Option<ResultValue> {
+        let item = match ast {
+            SmartCalcAstType::Item(item) => item,
+            SmartCalcAstType::List(items) => return Some(ResultValue::List(items.iter().filter_map(|item| ResultValue::from_ast(item)).collect())),
+            _ => return None
+        };
+
+        Some(match item.as_token_type() {
+            TokenType::Number(number, _) => ResultValue::Number(number),
+            TokenType::Money(amount, currency) => ResultValue::Money { amount, currency: currency.code.to_string() },
+            TokenType::Percent(percent) => ResultValue::Percent(percent),
+            TokenType::Date(date, offset) => ResultValue::Date(date, offset),
+            TokenType::Time(time, offset) => ResultValue::Time(time, offset),
+            TokenType::DateTime(date_time, offset) => ResultValue::DateTime(date_time, offset),
+            TokenType::Duration(duration) => ResultValue::Duration(duration),
+            TokenType::Bool(value) => ResultValue::Bool(value),
+            TokenType::TextValue(text) => ResultValue::Text(text),
+            TokenType::DynamicType(value, dynamic_type) => ResultValue::Unit {
+                value,
+                group: dynamic_type.group_name.to_string(),
+                name: dynamic_type.names.first().cloned().unwrap_or_default()
+            },
+            _ => return None
+        })
+    }
+}
+
+/// Result of a line with its value, printed output and tokens. Errors are reported in `diagnostics`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineResult {
+    pub value: Option<ResultValue>,
+    pub formatted: String,
+    pub ui_tokens: Vec<UiToken>,
+    pub diagnostics: Vec<String>
+}
+
+impl From<&ExecuteLine> for LineResult {
+    fn from(line: &ExecuteLine) -> Self {
+        match &line.result {
+            Ok(result) => LineResult {
+                value: ResultValue::from_ast(&result.ast),
+                formatted: result.output.to_string(),
+                ui_tokens: line.ui_tokens.clone(),
+                diagnostics: Vec::new()
+            },
+            Err(error) => LineResult {
+                value: None,
+                formatted: String::new(),
+                ui_tokens: line.ui_tokens.clone(),
+                diagnostics: alloc::vec![error.to_string()]
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn result_value_test() {
+    use crate::SmartCalc;
+    let calc = SmartCalc::default();
+    let results = calc.execute("en", "$25/hour * 14 hours\n15%\n2 hours 30 minutes\nis 2024 a leap year\n[1, 2]\n10 km\n1 / 0 km\n");
+    let lines = results.lines.iter().map(|line| line.as_ref().map(LineResult::from)).collect::<Vec<_>>();
+
+    assert_eq!(lines[0].as_ref().unwrap().value, Some(ResultValue::Money { amount: 350.0, currency: "USD".to_string() }));
+    assert_eq!(lines[0].as_ref().unwrap().formatted, "$350,00".to_string());
+    assert_eq!(lines[1].as_ref().unwrap().value, Some(ResultValue::Percent(15.0)));
+    assert_eq!(lines[2].as_ref().unwrap().value, Some(ResultValue::Duration(Duration::minutes(150))));
+    assert_eq!(lines[3].as_ref().unwrap().value, Some(ResultValue::Bool(true)));
+    assert_eq!(lines[4].as_ref().unwrap().value, Some(ResultValue::List(alloc::vec![ResultValue::Number(1.0), ResultValue::Number(2.0)])));
+    assert_eq!(lines[5].as_ref().unwrap().value, Some(ResultValue::Unit { value: 10.0, group: "metric-length".to_string(), name: "km".to_string() }));
+    assert!(lines[6].as_ref().unwrap().value.is_none());
+    assert_eq!(lines[6].as_ref().unwrap().diagnostics.len(), 1);
+}
@@ -0,0 +1,279 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use alloc::string::ToString;
+use serde::ser::{Serialize, SerializeMap, Serializer};
+
+use crate::result::{LineResult, ResultValue};
+use crate::smartcalc::ExecuteLine;
+use crate::types::{SmartCalcAstType, TokenType};
+
+/* Tokens are written with their type name and plain values, times and dates are in UTC */
+impl Serialize for TokenType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("type", &self.type_name())?;
+
+        match self {
+            TokenType::Number(number, number_type) => {
+                map.serialize_entry("value", number)?;
+                map.serialize_entry("number_type", number_type)?;
+            },
+            TokenType::Text(text) => map.serialize_entry("value", text)?,
+            TokenType::TextValue(text) => map.serialize_entry("value", text)?,
+            TokenType::Time(time, offset) => {
+                map.serialize_entry("value", &time.format("%H:%M:%S").to_string())?;
+                map.serialize_entry("timezone", offset)?;
+            },
+            TokenType::Date(date, offset) => {
+                map.serialize_entry("value", &date.format("%Y-%m-%d").to_string())?;
+                map.serialize_entry("timezone", offset)?;
+            },
+            TokenType::DateTime(date_time, offset) => {
+                map.serialize_entry("value", &date_time.format("%Y-%m-%dT%H:%M:%S").to_string())?;
+                map.serialize_entry("timezone", offset)?;
+            },
+            TokenType::Operator(operator) => map.serialize_entry("value", operator)?,
+            TokenType::Field(field) => map.serialize_entry("value", &field.type_name())?,
+            TokenType::Percent(percent) => map.serialize_entry("value", percent)?,
+            TokenType::Money(amount, currency) => {
+                map.serialize_entry("value", amount)?;
+                map.serialize_entry("currency", &currency.code)?;
+            },
+            TokenType::Variable(variable) => map.serialize_entry("name", &variable.name)?,
+            TokenType::Month(month) => map.serialize_entry("value", month)?,
+            TokenType::Duration(duration) => map.serialize_entry("milliseconds", &duration.num_milliseconds())?,
+            TokenType::Timezone(name, offset) => {
+                map.serialize_entry("value", name)?;
+                map.serialize_entry("offset", offset)?;
+            },
+            TokenType::DynamicType(value, dynamic_type) => {
+                map.serialize_entry("value", value)?;
+                map.serialize_entry("group", &dynamic_type.group_name)?;
+                map.serialize_entry("unit", &dynamic_type.names.first())?;
+            },
+            TokenType::Bool(value) => map.serialize_entry("value", value)?,
+            TokenType::Range(start, end, aggregate) => {
+                map.serialize_entry("start", start)?;
+                map.serialize_entry("end", end)?;
+                map.serialize_entry("aggregate", aggregate)?;
+            }
+        };
+
+        map.end()
+    }
+}
+
+/* Nodes are written with their kind, calculated items are written as tokens */
+impl Serialize for SmartCalcAstType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+
+        match self {
+            SmartCalcAstType::None => map.serialize_entry("type", "NONE")?,
+            SmartCalcAstType::Field(field) => {
+                map.serialize_entry("type", "FIELD")?;
+                map.serialize_entry("value", &field.type_name())?;
+            },
+            SmartCalcAstType::Item(item) => {
+                map.serialize_entry("type", "ITEM")?;
+                map.serialize_entry("value", &item.as_token_type())?;
+            },
+            SmartCalcAstType::Month(month) => {
+                map.serialize_entry("type", "MONTH")?;
+                map.serialize_entry("value", month)?;
+            },
+            SmartCalcAstType::Binary { left, operator, right } => {
+                map.serialize_entry("type", "BINARY")?;
+                map.serialize_entry("left", left)?;
+                map.serialize_entry("operator", operator)?;
+                map.serialize_entry("right", right)?;
+            },
+            SmartCalcAstType::PrefixUnary(operator, expression) => {
+                map.serialize_entry("type", "PREFIX_UNARY")?;
+                map.serialize_entry("operator", operator)?;
+                map.serialize_entry("expression", expression)?;
+            },
+            SmartCalcAstType::Assignment { variable, expression } => {
+                map.serialize_entry("type", "ASSIGNMENT")?;
+                map.serialize_entry("name", &variable.name)?;
+                map.serialize_entry("expression", expression)?;
+            },
+            SmartCalcAstType::Symbol(symbol) => {
+                map.serialize_entry("type", "SYMBOL")?;
+                map.serialize_entry("value", symbol)?;
+            },
+            SmartCalcAstType::Variable(variable) => {
+                map.serialize_entry("type", "VARIABLE")?;
+                map.serialize_entry("name", &variable.name)?;
+            },
+            SmartCalcAstType::Call { name, arguments } => {
+                map.serialize_entry("type", "CALL")?;
+                map.serialize_entry("name", name)?;
+                map.serialize_entry("arguments", arguments)?;
+            },
+            SmartCalcAstType::Range { start, end, aggregate } => {
+                map.serialize_entry("type", "RANGE")?;
+                map.serialize_entry("start", start)?;
+                map.serialize_entry("end", end)?;
+                map.serialize_entry("aggregate", aggregate)?;
+            },
+            SmartCalcAstType::List(items) => {
+                map.serialize_entry("type", "LIST")?;
+                map.serialize_entry("items", items)?;
+            },
+            SmartCalcAstType::Comparison { left, operator, right } => {
+                map.serialize_entry("type", "COMPARISON")?;
+                map.serialize_entry("left", left)?;
+                map.serialize_entry("operator", operator)?;
+                map.serialize_entry("right", right)?;
+            }
+        };
+
+        map.end()
+    }
+}
+
+/* Values are written like the tokens, lists have their items */
+impl Serialize for ResultValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+
+        match self {
+            ResultValue::Number(number) => {
+                map.serialize_entry("type", "NUMBER")?;
+                map.serialize_entry("value", number)?;
+            },
+            ResultValue::Money { amount, currency } => {
+                map.serialize_entry("type", "MONEY")?;
+                map.serialize_entry("value", amount)?;
+                map.serialize_entry("currency", currency)?;
+            },
+            ResultValue::Percent(percent) => {
+                map.serialize_entry("type", "PERCENT")?;
+                map.serialize_entry("value", percent)?;
+            },
+            ResultValue::Date(date, offset) => {
+                map.serialize_entry("type", "DATE")?;
+                map.serialize_entry("value", &date.format("%Y-%m-%d").to_string())?;
+                map.serialize_entry("timezone", offset)?;
+            },
+            ResultValue::Time(time, offset) => {
+                map.serialize_entry("type", "TIME")?;
+                map.serialize_entry("value", &time.format("%H:%M:%S").to_string())?;
+                map.serialize_entry("timezone", offset)?;
+            },
+            ResultValue::DateTime(date_time, offset) => {
+                map.serialize_entry("type", "DATE_TIME")?;
+                map.serialize_entry("value", &date_time.format("%Y-%m-%dT%H:%M:%S").to_string())?;
+                map.serialize_entry("timezone", offset)?;
+            },
+            ResultValue::Duration(duration) => {
+                map.serialize_entry("type", "DURATION")?;
+                map.serialize_entry("milliseconds", &duration.num_milliseconds())?;
+            },
+            ResultValue::Bool(value) => {
+                map.serialize_entry("type", "BOOL")?;
+                map.serialize_entry("value", value)?;
+            },
+            ResultValue::Text(text) => {
+                map.serialize_entry("type", "TEXT_VALUE")?;
+                map.serialize_entry("value", text)?;
+            },
+            ResultValue::Unit { value, group, name } => {
+                map.serialize_entry("type", "DYNAMIC_TYPE")?;
+                map.serialize_entry("value", value)?;
+                map.serialize_entry("group", group)?;
+                map.serialize_entry("unit", name)?;
+            },
+            ResultValue::List(items) => {
+                map.serialize_entry("type", "LIST")?;
+                map.serialize_entry("items", items)?;
+            }
+        };
+
+        map.end()
+    }
+}
+
+impl Serialize for LineResult {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("value", &self.value)?;
+        map.serialize_entry("formatted", &self.formatted)?;
+        map.serialize_entry("ui_tokens", &self.ui_tokens)?;
+        map.serialize_entry("diagnostics", &self.diagnostics)?;
+        map.end()
+    }
+}
+
+impl Serialize for ExecuteLine {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(None)?;
+        match &self.result {
+            Ok(result) => {
+                map.serialize_entry("status", &true)?;
+                map.serialize_entry("output", &result.output)?;
+                map.serialize_entry("result", &result.ast)?;
+            },
+            Err(error) => {
+                map.serialize_entry("status", &false)?;
+                map.serialize_entry("error", error)?;
+            }
+        };
+
+        map.serialize_entry("label", &self.label)?;
+        map.serialize_entry("syntax_trees", &self.syntax_trees)?;
+        map.serialize_entry("rule_matches", &self.rule_matches)?;
+        map.serialize_entry("tokens", &self.raw_tokens)?;
+        map.serialize_entry("ui_tokens", &self.ui_tokens)?;
+        map.end()
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn serialize_test() {
+    use serde_json::{json, Value};
+    use crate::SmartCalc;
+    let calc = SmartCalc::default();
+
+    let json = calc.execute_to_json("en", "price = $10\nprice * 2 + 1\n\n1 / 0 km").unwrap();
+    let lines = serde_json::from_str::<Value>(&json).unwrap();
+
+    assert_eq!(lines[0]["syntax_trees"][0], json!({
+        "type": "ASSIGNMENT",
+        "name": "price",
+        "expression": { "type": "ITEM", "value": { "type": "MONEY", "value": 10.0, "currency": "USD" } }
+    }));
+    assert_eq!(lines[1]["output"], "$21,00");
+    assert_eq!(lines[1]["result"]["value"]["value"], 21.0);
+    assert_eq!(lines[1]["syntax_trees"][0]["left"]["left"], json!({ "type": "VARIABLE", "name": "price" }));
+    assert_eq!(lines[1]["tokens"].as_array().unwrap().iter().map(|token| token["type"].as_str().unwrap()).collect::<alloc::vec::Vec<_>>(), ["VARIABLE", "OPERATOR", "NUMBER", "OPERATOR", "NUMBER"]);
+    assert_eq!(lines[2], Value::Null);
+    assert_eq!(lines[3]["status"], false);
+    assert!(lines[3]["error"].is_string());
+}
+
+#[cfg(test)]
+#[test]
+fn line_result_serialize_test() {
+    use serde_json::{json, Value};
+    use alloc::vec::Vec;
+    use crate::SmartCalc;
+    let calc = SmartCalc::default();
+
+    let results = calc.execute("en", "$10 + 5\n[1, 2]\n1 / 0 km");
+    let lines = results.lines.iter().map(|line| line.as_ref().map(LineResult::from)).collect::<Vec<_>>();
+    let lines = serde_json::from_str::<Value>(&serde_json::to_string(&lines).unwrap()).unwrap();
+
+    assert_eq!(lines[0]["value"], json!({ "type": "MONEY", "value": 15.0, "currency": "USD" }));
+    assert_eq!(lines[0]["formatted"], "$15,00");
+    assert!(lines[0]["ui_tokens"].as_array().is_some_and(|tokens| !tokens.is_empty()));
+    assert_eq!(lines[1]["value"], json!({ "type": "LIST", "items": [{ "type": "NUMBER", "value": 1.0 }, { "type": "NUMBER", "value": 2.0 }] }));
+    assert_eq!(lines[2]["value"], Value::Null);
+    assert_eq!(lines[2]["diagnostics"].as_array().map(|diagnostics| diagnostics.len()), Some(1));
+}
@@ -5,22 +5,209 @@
  */
 
 use core::cell::{Cell, RefCell};
-use alloc::collections::BTreeMap;
+use core::ops::Deref;
+use core::sync::atomic::{AtomicBool, Ordering};
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::string::{String, ToString};
 
-use alloc::{rc::Rc, vec::Vec};
+use alloc::{rc::Rc, sync::Arc, vec::Vec};
+use lazy_static::*;
 use regex::Regex;
 
 use crate::variable::VariableInfo;
+use crate::types::{SmartCalcAstType, TokenType};
+use crate::config::SmartCalcConfig;
+use crate::storage::StoredSession;
+use crate::smartcalc::{ExecuteResult, ExecutionLine, SmartCalc, TraceStep};
+use crate::formatter::FormatOptions;
+use crate::tokinizer::{TokenInfo, Tokinizer};
+use crate::token::ui_token::UiTokenCollection;
 
+lazy_static! {
+    static ref LINE_SEPARATOR: Regex = Regex::new(r"\r\n|\n").unwrap();
+}
+
+/* Line data is shorter than the line count while the current line is calculated */
+fn remove_first<T>(lines: &mut Vec<T>, count: usize) {
+    lines.drain(..count.min(lines.len()));
+}
+
+/* Aggregates stop at blank lines and headings, so only the kind of the previous lines is kept */
+#[derive(Clone, Copy, PartialEq)]
+enum LineKind {
+    Blank,
+    Header,
+    Value
+}
+
+impl LineKind {
+    fn from_line(line: &str) -> LineKind {
+        let line = line.trim();
+        if line.is_empty() {
+            LineKind::Blank
+        } else if Session::is_section_header(line) {
+            LineKind::Header
+        } else {
+            LineKind::Value
+        }
+    }
+}
+
+/* Returns true if a block comment is still open at the end of the line */
+fn ends_in_block_comment(line: &str, mut inside: bool) -> bool {
+    let mut rest = line;
+    loop {
+        if inside {
+            match rest.find("*/") {
+                Some(end) => rest = &rest[end + 2..],
+                None => return true
+            };
+            inside = false;
+        } else {
+            let start = match rest.find("/*") {
+                Some(start) => start,
+                None => return false
+            };
+
+            if rest[..start].contains('#') || rest[..start].contains("//") {
+                return false;
+            }
+
+            rest = &rest[start + 2..];
+            inside = true;
+        }
+    }
+}
+
+/* '#lang: tr' line changes the language of the following lines */
+fn language_directive(line: &str) -> Option<String> {
+    let directive = line.trim().strip_prefix('#')?.trim_start();
+    let language = directive.get(..5).filter(|prefix| prefix.eq_ignore_ascii_case("lang:")).map(|_| directive[5..].trim())?;
+    match language.is_empty() || language.contains(char::is_whitespace) {
+        true => None,
+        false => Some(language.to_lowercase())
+    }
+}
+
+/* Variables read and assigned while a line is executed */
+#[derive(Default, Clone)]
+struct LineTracking {
+    used_variables: Vec<(Rc<VariableInfo>, Rc<SmartCalcAstType>)>,
+    assigned_variables: Vec<(Rc<VariableInfo>, Rc<SmartCalcAstType>)>,
+    uses_lines: bool
+}
+
+/* Executed line with everything it depends on, reused while the text and the values of the used variables are the same */
+struct CachedLine {
+    text: String,
+    language: String,
+    block_comment: bool,
+    mentioned_variables: Vec<String>,
+    used_variables: Vec<(Rc<VariableInfo>, Rc<SmartCalcAstType>)>,
+    assigned_variables: Vec<(Rc<VariableInfo>, Rc<SmartCalcAstType>)>,
+    line: ExecutionLine
+}
+
+/* Executed line with the variables it read and assigned, so an updated line is calculated again only with the lines depending on it */
+struct LineRecord {
+    line: ExecutionLine,
+    used_variables: Vec<(Rc<VariableInfo>, Rc<SmartCalcAstType>)>,
+    assigned_variables: Vec<(Rc<VariableInfo>, Rc<SmartCalcAstType>)>,
+    uses_lines: bool,
+    aggregate: bool
+}
+
+/* Tokens of a statement before the variables and the rules are applied, they only depend on the text and the language */
+struct CachedTokens {
+    token_infos: Vec<TokenInfo>,
+    ui_tokens: UiTokenCollection,
+    used: Cell<bool>
+}
+
+/* Line executed on its own session by `SmartCalc::execute_parallel`, with the state it gets from the previous lines */
+#[cfg(feature = "parallel")]
+pub(crate) struct LineJob {
+    pub(crate) text: String,
+    language: String,
+    block_comment: bool,
+    cancellation_token: Option<CancellationToken>,
+    deadline: Option<std::time::Instant>
+}
+
+#[cfg(feature = "parallel")]
+impl LineJob {
+    /* Assignments are tracked like the line cache does, so the dependencies of the line are known after the execution */
+    pub(crate) fn session(&self) -> Session {
+        let mut session = Session::new();
+        session.set_text(self.text.clone());
+        session.set_language(self.language.clone());
+        session.block_comments = alloc::vec![self.block_comment];
+        session.line_cache_enabled = true;
+        session.cancellation_token = self.cancellation_token.clone();
+        session.deadline.set(self.deadline);
+        session
+    }
+}
+
+/// Flag to stop an execution from another thread, see `Session::set_cancellation_token`.
+#[derive(Clone, Default, Debug)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Text of a document with its language, variables and line results.
+///
+/// Variables are kept between executions, so a session can be executed again after its text is changed.
 #[derive(Default)]
 pub struct Session {
     text: String,
-    text_parts: Vec<String>,
+    pub(crate) text_parts: Vec<String>,
+    line_kinds: Vec<LineKind>,
+    block_comments: Vec<bool>,
+    line_languages: Vec<Option<String>>,
+    first_line: usize,
+    streamed: bool,
     language: String,
     position: Cell<usize>,
+    globals: Option<Rc<Session>>,
+
+    pub(crate) variables: RefCell<BTreeMap<String, Rc<VariableInfo>>>,
+    pub(crate) line_results: RefCell<Vec<Option<Rc<SmartCalcAstType>>>>,
+    pub(crate) line_labels: RefCell<Vec<Option<String>>>,
+    pub(crate) aggregate_lines: RefCell<BTreeSet<usize>>,
+
+    max_lines: Option<usize>,
+    max_variables: Option<usize>,
+    evicted_lines: usize,
+    variable_uses: RefCell<BTreeMap<String, usize>>,
+    use_counter: Cell<usize>,
+
+    line_cache_enabled: bool,
+    line_cache: RefCell<Vec<Option<CachedLine>>>,
+    token_cache: RefCell<BTreeMap<String, BTreeMap<String, CachedTokens>>>,
+    line_tracking: RefCell<LineTracking>,
+    line_records: RefCell<Option<Vec<Option<LineRecord>>>>,
+    trace: RefCell<Option<Vec<TraceStep>>>,
+    format_options: RefCell<FormatOptions>,
+    cancellation_token: Option<CancellationToken>,
 
-    pub(crate) variables: RefCell<BTreeMap<String, Rc<VariableInfo>>>
+    #[cfg(feature = "std")]
+    time_limit: Option<core::time::Duration>,
+
+    #[cfg(feature = "std")]
+    deadline: Cell<Option<std::time::Instant>>
 }
 
 impl Session {
@@ -31,41 +218,655 @@ impl Session {
         Session {
             text: String::new(),
             text_parts: Vec::new(),
+            line_kinds: Vec::new(),
+            block_comments: Vec::new(),
+            line_languages: Vec::new(),
+            first_line: 0,
+            streamed: false,
             language: String::new(),
             variables: RefCell::new(BTreeMap::new()),
-            position: Cell::default()
+            line_results: RefCell::new(Vec::new()),
+            line_labels: RefCell::new(Vec::new()),
+            aggregate_lines: RefCell::new(BTreeSet::new()),
+            max_lines: None,
+            max_variables: None,
+            evicted_lines: 0,
+            variable_uses: RefCell::new(BTreeMap::new()),
+            use_counter: Cell::default(),
+            position: Cell::default(),
+            globals: None,
+            line_cache_enabled: false,
+            line_cache: RefCell::new(Vec::new()),
+            token_cache: RefCell::new(BTreeMap::new()),
+            line_tracking: RefCell::new(LineTracking::default()),
+            line_records: RefCell::new(None),
+            trace: RefCell::new(None),
+            format_options: RefCell::new(FormatOptions::default()),
+            cancellation_token: None,
+
+            #[cfg(feature = "std")]
+            time_limit: None,
+
+            #[cfg(feature = "std")]
+            deadline: Cell::new(None)
         }
     }
 
     /// Set the text to be executed.
     pub fn set_text(&mut self, text: String) {
         self.text = text;
+        self.position.set(0);
+        self.line_results.borrow_mut().clear();
+        self.line_labels.borrow_mut().clear();
+        self.aggregate_lines.borrow_mut().clear();
+        if let Some(records) = self.line_records.get_mut() {
+            records.clear();
+        }
         
-        self.text_parts = match Regex::new(r"\r\n|\n") {
-            Ok(re) => re.split(&self.text).map(|item| item.to_string()).collect::<Vec<_>>(),
-            _ => self.text.lines().map(|item| item.to_string()).collect::<Vec<_>>()
+        self.text_parts = LINE_SEPARATOR.split(&self.text).map(|item| item.to_string()).collect::<Vec<_>>();
+        self.line_kinds = self.text_parts.iter().map(|line| LineKind::from_line(line)).collect();
+        self.first_line = 0;
+        self.streamed = false;
+        self.evicted_lines = 0;
+
+        let mut inside = false;
+        self.block_comments = self.text_parts.iter().map(|line| {
+            let starts_inside = inside;
+            inside = ends_in_block_comment(line, inside);
+            starts_inside
+        }).collect();
+
+        self.line_languages.clear();
+        for line in self.text_parts.iter() {
+            let language = language_directive(line).or_else(|| self.line_languages.last().cloned().flatten());
+            self.line_languages.push(language);
+        }
+    }
+
+    /// Execute the text from its first line, results of the previous execution are replaced.
+    pub fn execute(&self, smartcalc: &SmartCalc) -> ExecuteResult {
+        self.position.set(self.first_line);
+        self.line_results.borrow_mut().clear();
+        self.line_labels.borrow_mut().clear();
+        self.aggregate_lines.borrow_mut().clear();
+        smartcalc.execute_session(self)
+    }
+
+    /// Remove the text, the variables and the results. Language and global variables are kept.
+    pub fn clear(&mut self) {
+        self.set_text(String::new());
+        self.variables.borrow_mut().clear();
+        self.variable_uses.borrow_mut().clear();
+        self.clear_line_cache();
+    }
+
+    /* Streamed documents keep only the current line, the buffer of the previous line is reused */
+    pub(crate) fn set_stream_line(&mut self, line: &str) {
+        let index = self.evicted_lines + self.line_kinds.len();
+        let inside = match (self.text_parts.first(), self.block_comments.last()) {
+            (Some(previous), Some(starts_inside)) => ends_in_block_comment(previous, *starts_inside),
+            _ => false
+        };
+
+        self.first_line = index;
+        self.streamed = true;
+        self.position.set(index);
+        self.line_kinds.push(LineKind::from_line(line));
+        self.block_comments.push(inside);
+        self.push_line_language(line);
+
+        self.text_parts.truncate(1);
+        match self.text_parts.first_mut() {
+            Some(current) => {
+                current.clear();
+                current.push_str(line);
+            },
+            None => self.text_parts.push(line.to_string())
+        };
+        self.evict_lines();
+    }
+
+    /* Lines of a streamed session are not kept, so they can not be replaced. Returns the replaced line and the following lines
+       with a changed block comment or language. */
+    pub(crate) fn replace_line(&mut self, index: usize, line: &str) -> Option<BTreeSet<usize>> {
+        if self.first_line > 0 || index >= self.text_parts.len() {
+            return None;
+        }
+
+        let block_comments = core::mem::take(&mut self.block_comments);
+        let line_languages = core::mem::take(&mut self.line_languages);
+        let records = self.line_records.take();
+
+        let mut lines = core::mem::take(&mut self.text_parts);
+        let line_count = lines.len();
+        lines[index] = line.to_string();
+        self.set_text(lines.join("\n"));
+
+        /* Records of the following lines do not belong to them anymore if the line is split */
+        *self.line_records.get_mut() = records.map(|records| match self.text_parts.len() == line_count {
+            true => records,
+            false => Vec::new()
+        });
+
+        Some((index..self.text_parts.len())
+            .filter(|line| *line == index || block_comments.get(*line) != self.block_comments.get(*line) || line_languages.get(*line) != self.line_languages.get(*line))
+            .collect())
+    }
+
+    /* Line is added to the end of the text and becomes the current line, streamed sessions keep only the new line */
+    pub(crate) fn push_line(&mut self, line: &str) {
+        if self.streamed {
+            return self.set_stream_line(line);
+        }
+
+        let inside = match (self.text_parts.last(), self.block_comments.last()) {
+            (Some(previous), Some(starts_inside)) => ends_in_block_comment(previous, *starts_inside),
+            _ => false
+        };
+
+        if !self.text_parts.is_empty() {
+            self.text.push('\n');
+        }
+
+        self.text.push_str(line);
+        self.position.set(self.first_line + self.text_parts.len());
+        self.text_parts.push(line.to_string());
+        self.line_kinds.push(LineKind::from_line(line));
+        self.block_comments.push(inside);
+        self.push_line_language(line);
+        self.evict_lines();
+    }
+
+    fn push_line_language(&mut self, line: &str) {
+        let language = language_directive(line).or_else(|| self.line_languages.last().cloned().flatten());
+        self.line_languages.push(language);
+    }
+
+    /// Keep the data of the last `max_lines` lines added with `SmartCalc::execute_line` or read by `SmartCalc::execute_reader`.
+    ///
+    /// Older lines are removed with their text and results, so aggregates like `total` and line references can not read them.
+    /// The limit is applied while lines are added, `shrink` applies it at once.
+    pub fn set_max_lines(&mut self, max_lines: Option<usize>) {
+        self.max_lines = max_lines;
+    }
+
+    /// Keep at most `max_variables` variables, the least recently used or assigned one is removed when a new variable is added.
+    ///
+    /// Lines using a removed variable read its name as text. Global variables are not limited.
+    pub fn set_max_variables(&mut self, max_variables: Option<usize>) {
+        self.max_variables = max_variables;
+    }
+
+    /// Apply the storage limits, remove the cached tokens of the removed lines and release the unused memory.
+    pub fn shrink(&mut self) {
+        self.evict_lines();
+        self.evict_variables();
+        self.prune_token_cache();
+
+        self.text.shrink_to_fit();
+        self.text_parts.shrink_to_fit();
+        self.line_kinds.shrink_to_fit();
+        self.block_comments.shrink_to_fit();
+        self.line_languages.shrink_to_fit();
+        self.line_results.borrow_mut().shrink_to_fit();
+        self.line_labels.borrow_mut().shrink_to_fit();
+        self.line_cache.borrow_mut().shrink_to_fit();
+    }
+
+    /* Position of a line in the kept line data, removed lines do not have one */
+    fn kept_index(&self, line: usize) -> Option<usize> {
+        line.checked_sub(self.evicted_lines)
+    }
+
+    /* Oldest lines are removed, the current line is always kept. Line numbers do not change, so the removed lines are counted. */
+    fn evict_lines(&mut self) {
+        let count = match self.max_lines {
+            Some(max_lines) => self.line_kinds.len().saturating_sub(max_lines.max(1)),
+            None => return
+        };
+
+        if count == 0 {
+            return;
+        }
+
+        self.line_kinds.drain(..count);
+        remove_first(&mut self.block_comments, count);
+        remove_first(&mut self.line_languages, count);
+        remove_first(self.line_results.get_mut(), count);
+        remove_first(self.line_labels.get_mut(), count);
+        remove_first(self.line_cache.get_mut(), count);
+        if let Some(records) = self.line_records.get_mut() {
+            remove_first(records, count);
+        }
+
+        self.evicted_lines += count;
+        self.aggregate_lines.borrow_mut().retain(|line| *line >= self.evicted_lines);
+
+        /* Streamed sessions keep only the current line, the text of the other sessions starts from the first kept line */
+        let removed_parts = self.evicted_lines.saturating_sub(self.first_line).min(self.text_parts.len().saturating_sub(1));
+        if removed_parts > 0 {
+            self.text_parts.drain(..removed_parts);
+            self.first_line += removed_parts;
+            self.text = self.text_parts.join("\n");
+        }
+    }
+
+    /* Variables are ordered by their last use, the ones without a use are removed first */
+    fn touch_variable(&self, variable: &VariableInfo) {
+        if self.max_variables.is_some() {
+            self.use_counter.set(self.use_counter.get() + 1);
+            self.variable_uses.borrow_mut().insert(variable.to_string(), self.use_counter.get());
+        }
+    }
+
+    fn evict_variables(&self) {
+        let max_variables = match self.max_variables {
+            Some(max_variables) => max_variables,
+            None => return
+        };
+
+        let mut variables = self.variables.borrow_mut();
+        let mut variable_uses = self.variable_uses.borrow_mut();
+        variable_uses.retain(|name, _| variables.contains_key(name));
+
+        while variables.len() > max_variables {
+            let least_used = match variables.keys().min_by_key(|name| variable_uses.get(*name).copied().unwrap_or_default()) {
+                Some(name) => name.to_string(),
+                None => break
+            };
+
+            variables.remove(&least_used);
+            variable_uses.remove(&least_used);
+        }
+    }
+
+    /// Reuse the results of unchanged lines when the session is executed again.
+    ///
+    /// A line is calculated again if its text or a variable it uses is changed. Lines using other lines, like `total`, and date or time
+    /// calculations are always calculated, with the tokens found for their text before. The cache does not follow configuration
+    /// changes, use `clear_line_cache` after them.
+    pub fn set_line_cache(&mut self, enabled: bool) {
+        self.line_cache_enabled = enabled;
+        self.clear_line_cache();
+    }
+
+    /// Remove the cached line results.
+    pub fn clear_line_cache(&self) {
+        self.line_cache.borrow_mut().clear();
+        self.token_cache.borrow_mut().clear();
+    }
+
+    /* Tokens are found again only for the changed statements, the rules are applied to a copy of the cached tokens */
+    pub(crate) fn cached_tokens(&self, tokinizer: &mut Tokinizer) -> bool {
+        if !self.line_cache_enabled {
+            return false;
+        }
+
+        let cache = self.token_cache.borrow();
+        let cached = match cache.get(&tokinizer.language).and_then(|statements| statements.get(&tokinizer.data[..])) {
+            Some(cached) => cached,
+            None => return false
+        };
+
+        cached.used.set(true);
+        tokinizer.token_infos = cached.token_infos.iter().map(|token| Rc::new(token.clone())).collect();
+        tokinizer.ui_tokens = cached.ui_tokens.clone();
+        true
+    }
+
+    /* Time and date tokens are created with the current date, so they are always found again */
+    pub(crate) fn cache_tokens(&self, tokinizer: &Tokinizer) {
+        if !self.line_cache_enabled {
+            return;
+        }
+
+        let uses_time = tokinizer.token_infos.iter().any(|token| matches!(token.token_type.borrow().as_ref(), Some(TokenType::Date(_, _) | TokenType::Time(_, _) | TokenType::DateTime(_, _))));
+        if uses_time {
+            return;
+        }
+
+        self.token_cache.borrow_mut().entry(tokinizer.language.to_string()).or_default().insert(tokinizer.data.to_string(), CachedTokens {
+            token_infos: tokinizer.token_infos.iter().map(|token| token.deref().clone()).collect(),
+            ui_tokens: tokinizer.ui_tokens.clone(),
+            used: Cell::new(true)
+        });
+    }
+
+    /* Statements not in the text anymore are removed, so the cache does not grow while the text is edited.
+       Lines with a cached result are not tokinized, so their tokens are kept while the line is in the text. */
+    pub(crate) fn prune_token_cache(&self) {
+        let lines = self.text_parts.iter().map(|line| &line[..]).collect::<BTreeSet<_>>();
+        let mut cache = self.token_cache.borrow_mut();
+        for statements in cache.values_mut() {
+            statements.retain(|text, cached| cached.used.replace(false) || lines.contains(&text[..]));
+        }
+
+        cache.retain(|_, statements| !statements.is_empty());
+    }
+
+    /// Stop the execution when the token is cancelled.
+    ///
+    /// Lines calculated before are returned, the interrupted line has an error and the next lines are not calculated.
+    pub fn set_cancellation_token(&mut self, token: CancellationToken) {
+        self.cancellation_token = Some(token);
+    }
+
+    /// Stop the execution when it takes longer than the limit, like a cancelled execution.
+    #[cfg(feature = "std")]
+    pub fn set_time_limit(&mut self, limit: core::time::Duration) {
+        self.time_limit = Some(limit);
+    }
+
+    pub(crate) fn start_time_limit(&self) {
+        #[cfg(feature = "std")]
+        self.deadline.set(self.time_limit.map(|limit| std::time::Instant::now() + limit));
+    }
+
+    /* Checked between the lines and while the rules are applied */
+    pub(crate) fn interruption(&self) -> Option<String> {
+        if self.cancellation_token.as_ref().is_some_and(|token| token.is_cancelled()) {
+            return Some("Calculation is cancelled".to_string());
+        }
+
+        #[cfg(feature = "std")]
+        if self.deadline.get().is_some_and(|deadline| std::time::Instant::now() >= deadline) {
+            return Some("Calculation time limit is exceeded".to_string());
+        }
+
+        None
+    }
+
+    pub(crate) fn start_trace(&self) {
+        *self.trace.borrow_mut() = Some(Vec::new());
+    }
+
+    pub(crate) fn is_tracing(&self) -> bool {
+        self.trace.borrow().is_some()
+    }
+
+    /* Steps are built only while tracing */
+    pub(crate) fn add_trace<F: FnOnce() -> TraceStep>(&self, step: F) {
+        if let Some(trace) = self.trace.borrow_mut().as_mut() {
+            trace.push(step());
+        }
+    }
+
+    pub(crate) fn take_trace(&self) -> Vec<TraceStep> {
+        match self.trace.borrow_mut().as_mut() {
+            Some(trace) => core::mem::take(trace),
+            None => Vec::new()
+        }
+    }
+
+    pub(crate) fn format_options(&self) -> FormatOptions {
+        self.format_options.borrow().clone()
+    }
+
+    pub(crate) fn replace_format_options(&self, options: FormatOptions) -> FormatOptions {
+        self.format_options.replace(options)
+    }
+
+    /* Variables are tracked for the line cache and the line records */
+    fn is_tracking_lines(&self) -> bool {
+        self.line_cache_enabled || self.line_records.borrow().is_some()
+    }
+
+    pub(crate) fn start_line_tracking(&self) {
+        if !self.is_tracking_lines() {
+            return;
+        }
+
+        *self.line_tracking.borrow_mut() = LineTracking::default();
+    }
+
+    /* A variable defined later changes how the line is tokinized, so the visible variables written in the line are part of the cache key */
+    fn mentioned_variables(&self, assigned_variables: &[(Rc<VariableInfo>, Rc<SmartCalcAstType>)]) -> Vec<String> {
+        let line = self.current_line().to_lowercase();
+        self.visible_variables().into_keys()
+            .filter(|name| line.contains(&name.to_lowercase()[..]))
+            .filter(|name| !assigned_variables.iter().any(|(variable, _)| variable.to_string() == *name))
+            .collect()
+    }
+
+    pub(crate) fn track_variable_use(&self, variable: &Rc<VariableInfo>) {
+        self.touch_variable(variable);
+        if self.is_tracking_lines() {
+            self.line_tracking.borrow_mut().used_variables.push((variable.clone(), variable.data.borrow().clone()));
+        }
+    }
+
+    pub(crate) fn track_assignment(&self, variable: &Rc<VariableInfo>, value: &Rc<SmartCalcAstType>) {
+        self.touch_variable(variable);
+        if self.is_tracking_lines() {
+            self.line_tracking.borrow_mut().assigned_variables.push((variable.clone(), value.clone()));
+        }
+    }
+
+    fn track_line_use(&self) {
+        self.line_tracking.borrow_mut().uses_lines = true;
+    }
+
+    pub(crate) fn cache_line(&self, line: &ExecutionLine) {
+        if !self.line_cache_enabled {
+            return;
+        }
+
+        let tracking = self.line_tracking.replace(LineTracking::default());
+        let uses_time = line.as_ref().is_some_and(|line| line.raw_tokens.iter().any(|token| matches!(token.as_ref(), TokenType::Date(_, _) | TokenType::Time(_, _) | TokenType::DateTime(_, _))));
+        let index = match self.kept_index(self.position.get()) {
+            Some(index) => index,
+            None => return
+        };
+
+        let mut cache = self.line_cache.borrow_mut();
+        if cache.len() <= index {
+            cache.resize_with(index + 1, || None);
+        }
+
+        cache[index] = match tracking.uses_lines || uses_time {
+            true => None,
+            false => Some(CachedLine {
+                text: self.current_line().to_string(),
+                language: self.get_language(),
+                block_comment: self.block_comments.get(index).copied().unwrap_or_default(),
+                mentioned_variables: self.mentioned_variables(&tracking.assigned_variables),
+                used_variables: tracking.used_variables,
+                assigned_variables: tracking.assigned_variables,
+                line: line.clone()
+            })
         };
     }
 
+    /* Assignments of the cached line are applied again, so the following lines see the same values */
+    pub(crate) fn cached_line(&self) -> Option<ExecutionLine> {
+        if !self.line_cache_enabled {
+            return None;
+        }
+
+        let index = self.kept_index(self.position.get())?;
+        let cache = self.line_cache.borrow();
+        let cached = cache.get(index)?.as_ref()?;
+
+        let valid = cached.text == *self.current_line() &&
+            cached.language == self.get_language() &&
+            cached.block_comment == self.block_comments.get(index).copied().unwrap_or_default() &&
+            cached.mentioned_variables == self.mentioned_variables(&cached.assigned_variables) &&
+            cached.used_variables.iter().all(|(variable, value)| match self.visible_variables().get(&variable.to_string()) {
+                Some(current) => Rc::ptr_eq(&current.data.borrow(), value),
+                None => false
+            });
+
+        if !valid {
+            return None;
+        }
+
+        self.apply_assignments(&cached.assigned_variables);
+        self.add_line_record(&cached.line, LineTracking {
+            used_variables: cached.used_variables.clone(),
+            assigned_variables: cached.assigned_variables.clone(),
+            uses_lines: false
+        });
+        Some(cached.line.clone())
+    }
+
+    fn apply_assignments(&self, assigned_variables: &[(Rc<VariableInfo>, Rc<SmartCalcAstType>)]) {
+        for (variable, value) in assigned_variables.iter() {
+            let key = variable.to_string();
+            if !self.variables.borrow().get(&key).is_some_and(|current| Rc::ptr_eq(current, variable)) {
+                self.add_variable(variable.clone());
+            }
+            *variable.data.borrow_mut() = value.clone();
+        }
+    }
+
+    /* Lines are recorded after the first `SmartCalc::update_line`, the line cache setting is not changed */
+    pub(crate) fn start_line_records(&mut self) {
+        self.line_records.get_mut().get_or_insert_with(Vec::new);
+    }
+
+    /* Every kept line is recorded since the text is set */
+    pub(crate) fn has_line_records(&self) -> bool {
+        match self.line_records.borrow().as_ref() {
+            Some(records) => records.len() >= self.line_kinds.len() && records.iter().take(self.line_kinds.len()).all(Option::is_some),
+            None => false
+        }
+    }
+
+    pub(crate) fn record_line(&self, line: &ExecutionLine) {
+        let tracking = self.line_tracking.borrow().clone();
+        self.add_line_record(line, tracking);
+    }
+
+    fn add_line_record(&self, line: &ExecutionLine, tracking: LineTracking) {
+        let mut records = self.line_records.borrow_mut();
+        let (records, index) = match (records.as_mut(), self.kept_index(self.position.get())) {
+            (Some(records), Some(index)) => (records, index),
+            _ => return
+        };
+
+        if records.len() <= index {
+            records.resize_with(index + 1, || None);
+        }
+
+        records[index] = Some(LineRecord {
+            line: line.clone(),
+            used_variables: tracking.used_variables,
+            assigned_variables: tracking.assigned_variables,
+            uses_lines: tracking.uses_lines,
+            aggregate: self.aggregate_lines.borrow().contains(&self.position.get())
+        });
+    }
+
+    /* Interrupted line is not completely calculated, so it is calculated again by the next update */
+    pub(crate) fn remove_line_record(&self) {
+        if let (Some(records), Some(index)) = (self.line_records.borrow_mut().as_mut(), self.kept_index(self.position.get())) {
+            if let Some(record) = records.get_mut(index) {
+                *record = None;
+            }
+        }
+    }
+
+    /* Recorded line is reused if the values of the variables it read are the same, and the lines before it are the same when it reads
+       them. A variable added by an updated line is found in the lines mentioning its name, so they are calculated again. */
+    pub(crate) fn recorded_line(&self, lines_changed: bool, added_variables: &BTreeSet<String>) -> Option<ExecutionLine> {
+        let index = self.kept_index(self.position.get())?;
+        let records = self.line_records.borrow();
+        let record = records.as_ref()?.get(index)?.as_ref()?;
+
+        let valid = !(record.uses_lines && lines_changed) &&
+            record.used_variables.iter().all(|(variable, value)| match self.visible_variable(&variable.to_string()) {
+                Some(current) => Rc::ptr_eq(&current.data.borrow(), value),
+                None => false
+            }) &&
+            (added_variables.is_empty() || !added_variables.iter().any(|name| Session::variable_key(self.current_line()).contains(&name[..])));
+
+        if !valid {
+            return None;
+        }
+
+        self.apply_assignments(&record.assigned_variables);
+        if record.aggregate {
+            self.mark_aggregate_line();
+        }
+
+        let line = record.line.clone();
+        self.add_line_result(line.as_ref().and_then(|line| line.result.as_ref().ok()).map(|result| result.ast.clone()));
+        self.add_line_label(line.as_ref().and_then(|line| line.label.clone()));
+        Some(line)
+    }
+
+    /* Variables assigned by a line, read before the line is replaced. Lines without a record give the variables defined on them. */
+    pub(crate) fn line_assignments(&self, index: usize) -> BTreeSet<String> {
+        let records = self.line_records.borrow();
+        match self.kept_index(index).and_then(|index| records.as_ref()?.get(index)?.as_ref()) {
+            Some(record) => record.assigned_variables.iter().map(|(variable, _)| variable.to_string()).collect(),
+            None => self.variables.borrow().iter().filter(|(_, variable)| variable.line == index).map(|(key, _)| key.to_string()).collect()
+        }
+    }
+
+    /* Names of the local variables, compared before and after a line is calculated */
+    pub(crate) fn variable_names(&self) -> BTreeSet<String> {
+        self.variables.borrow().keys().cloned().collect()
+    }
+
+    /* Every line with the language and the block comment state it has in the text, the time limit starts for all of them */
+    #[cfg(feature = "parallel")]
+    pub(crate) fn line_jobs(&self) -> Vec<LineJob> {
+        self.start_time_limit();
+        let offset = self.first_line - self.evicted_lines;
+        self.text_parts.iter().enumerate().map(|(index, text)| LineJob {
+            text: text.to_string(),
+            language: self.line_languages.get(offset + index).cloned().flatten().unwrap_or_else(|| self.language.to_string()),
+            block_comment: self.block_comments.get(offset + index).copied().unwrap_or_default(),
+            cancellation_token: self.cancellation_token.clone(),
+            deadline: self.deadline.get()
+        }).collect()
+    }
+
+    /* Whether the executed line used the other lines, and the names of the variables it assigned */
+    #[cfg(feature = "parallel")]
+    pub(crate) fn take_line_dependencies(&self) -> (bool, Vec<String>) {
+        let tracking = self.line_tracking.replace(LineTracking::default());
+        (tracking.uses_lines, tracking.assigned_variables.iter().map(|(variable, _)| variable.to_string()).collect())
+    }
+
+    /// Returns the text to be executed.
+    pub fn get_text(&self) -> String {
+        self.text.to_string()
+    }
+
     /// Set the language used to interpret input.
     pub fn set_language(&mut self, language: String) {
         self.language = language;
     }
     
     pub(crate) fn current_line(&self) -> &'_ String { 
-        &self.text_parts[self.position.get()]
+        &self.text_parts[self.position.get() - self.first_line]
     }
     
+    /* Length of the current line part that belongs to a block comment started on a previous line */
+    pub(crate) fn block_comment_length(&self) -> usize {
+        let line = self.current_line();
+        match self.kept_index(self.position.get()).and_then(|index| self.block_comments.get(index)) {
+            Some(true) => line.find("*/").map(|end| end + 2).unwrap_or(line.len()),
+            _ => 0
+        }
+    }
+
     pub(crate) fn has_value(&self) -> bool { 
-        self.text_parts.len() > self.position.get()
+        self.first_line + self.text_parts.len() > self.position.get()
+    }
+    
+    pub(crate) fn current_line_index(&self) -> usize { 
+        self.position.get()
     }
     
     pub(crate) fn line_count(&self) -> usize { 
-        self.text_parts.len()
+        self.first_line + self.text_parts.len()
     }
     
     pub(crate) fn next_line(&self) -> Option<&'_ String> {
-        match self.text_parts.len() > self.position.get() + 1 {
+        match self.line_count() > self.position.get() + 1 {
             true => {
                 let current = Some(self.current_line());
                 self.position.set(self.position.get() + 1);
@@ -76,11 +877,165 @@ impl Session {
     }
     
     pub(crate) fn add_variable(&self, variable_info: Rc<VariableInfo>) {
+        self.touch_variable(&variable_info);
         self.variables.borrow_mut().insert(variable_info.to_string(), variable_info);
+        self.evict_variables();
+    }
+
+    /// Share the variables of an executed session with this one.
+    ///
+    /// Global variables can be used but not changed, an assignment with the same name creates a local variable that hides the global one.
+    pub fn set_globals(&mut self, globals: Rc<Session>) {
+        self.globals = Some(globals);
+    }
+
+    /* Local variable, or the global one with the same name */
+    fn visible_variable(&self, key: &str) -> Option<Rc<VariableInfo>> {
+        match self.variables.borrow().get(key) {
+            Some(variable) => Some(variable.clone()),
+            None => self.globals.as_ref().and_then(|globals| globals.visible_variable(key))
+        }
+    }
+
+    /* Local variables first, then the global ones */
+    pub(crate) fn visible_variables(&self) -> BTreeMap<String, Rc<VariableInfo>> {
+        let mut variables = match &self.globals {
+            Some(globals) => globals.visible_variables(),
+            None => BTreeMap::new()
+        };
+
+        variables.extend(self.variables.borrow().iter().map(|(name, variable)| (name.to_string(), variable.clone())));
+        variables
     }
     
-    /// Returns the language configured for this session.
+    /// Returns the variables defined in this session as name, current value, defining line index and the byte range of the name
+    /// in that line, ordered by line.
+    pub fn variables(&self) -> impl Iterator<Item = (String, Rc<SmartCalcAstType>, usize, (usize, usize))> {
+        let mut variables = self.variables.borrow().values().map(|variable| (variable.name.to_string(), variable.data.borrow().clone(), variable.line, (variable.start, variable.end))).collect::<Vec<_>>();
+        variables.sort_by_key(|(_, _, line, span)| (*line, *span));
+        variables.into_iter()
+    }
+
+    /// Serialize variables and calculated line results, so the session can be restored later without executing it again.
+    pub fn serialize(&self) -> Result<String, String> {
+        serde_json::to_string(&StoredSession::from_session(self)).map_err(|error| error.to_string())
+    }
+
+    pub(crate) fn deserialize(config: &SmartCalcConfig, data: &str) -> Result<Session, String> {
+        let stored = serde_json::from_str::<StoredSession>(data).map_err(|error| error.to_string())?;
+        stored.to_session(config)
+    }
+
+    /* Variables are stored with their lowercased tokens, without spaces */
+    pub(crate) fn variable_key(name: &str) -> String {
+        name.split_whitespace().collect::<String>().to_lowercase()
+    }
+
+    pub(crate) fn get_variable(&self, name: &str) -> Option<Rc<VariableInfo>> {
+        self.variables.borrow().get(&Session::variable_key(name)).cloned()
+    }
+
+    /// Returns the current value of a variable, global variables are included.
+    pub fn variable(&self, name: &str) -> Option<Rc<SmartCalcAstType>> {
+        self.visible_variables().get(&Session::variable_key(name)).map(|variable| variable.data.borrow().clone())
+    }
+
+    /// Remove a variable defined in this session, returns false if it is not defined.
+    pub fn remove_variable(&self, name: &str) -> bool {
+        self.variable_uses.borrow_mut().remove(&Session::variable_key(name));
+        self.variables.borrow_mut().remove(&Session::variable_key(name)).is_some()
+    }
+
+    pub(crate) fn add_line_result(&self, result: Option<Rc<SmartCalcAstType>>) {
+        self.line_results.borrow_mut().push(result);
+    }
+
+    pub(crate) fn add_line_label(&self, label: Option<String>) {
+        self.line_labels.borrow_mut().push(label);
+    }
+
+    /// Label written in front of the result of a line, like `rent` in `rent: $1,500`.
+    ///
+    /// Lines are indexed from zero, in the same order as the execution results.
+    pub fn line_label(&self, line: usize) -> Option<String> {
+        self.line_labels.borrow().get(self.kept_index(line)?).cloned().flatten()
+    }
+
+    /* Latest successfully calculated line, empty and failed lines are skipped */
+    pub(crate) fn last_result(&self) -> Option<Rc<SmartCalcAstType>> {
+        self.track_line_use();
+        self.line_results.borrow().iter().rev().find_map(|result| result.clone())
+    }
+    
+    /* Lines starting with '#' or ending with ':' start a new section */
+    fn is_section_header(line: &str) -> bool {
+        line.starts_with('#') || line.ends_with(':')
+    }
+
+    /* Aggregate lines are not calculated again by the following aggregates */
+    pub(crate) fn mark_aggregate_line(&self) {
+        self.aggregate_lines.borrow_mut().insert(self.current_line_index());
+    }
+
+    /* Results above the current line until the stop line, lines without a result or with an other label are skipped */
+    fn results_until<F: Fn(LineKind) -> bool>(&self, stop: F, label: Option<&str>) -> Vec<Rc<SmartCalcAstType>> {
+        self.track_line_use();
+        let mut results = Vec::new();
+        let aggregate_lines = self.aggregate_lines.borrow();
+        let line_labels = self.line_labels.borrow();
+
+        for (index, result) in self.line_results.borrow().iter().enumerate().rev() {
+            if stop(self.line_kinds[index]) {
+                break;
+            }
+
+            let label_matched = match (label, line_labels.get(index).cloned().flatten()) {
+                (Some(label), Some(line_label)) => label.to_lowercase() == line_label.to_lowercase(),
+                (Some(_), None) => false,
+                (None, _) => true
+            };
+
+            if let Some(result) = result {
+                if label_matched && !aggregate_lines.contains(&(self.evicted_lines + index)) {
+                    results.push(result.clone());
+                }
+            }
+        }
+
+        results.reverse();
+        results
+    }
+
+    /* Results after the last blank line or heading */
+    pub(crate) fn block_results(&self, label: Option<&str>) -> Vec<Rc<SmartCalcAstType>> {
+        self.results_until(|kind| kind != LineKind::Value, label)
+    }
+
+    /* Results after the last heading, blank lines are a part of the section */
+    pub(crate) fn section_results(&self, label: Option<&str>) -> Vec<Rc<SmartCalcAstType>> {
+        self.results_until(|kind| kind == LineKind::Header, label)
+    }
+
+    pub(crate) fn document_results(&self, label: Option<&str>) -> Vec<Rc<SmartCalcAstType>> {
+        self.results_until(|_| false, label)
+    }
+
+    /* Line numbers start from one, like in the editor */
+    pub(crate) fn line_result(&self, line: usize) -> Option<Rc<SmartCalcAstType>> {
+        self.track_line_use();
+        self.line_results.borrow().get(self.kept_index(line.checked_sub(1)?)?).cloned().flatten()
+    }
+    
+    /* Language set for the session, without the '#lang:' lines */
+    pub(crate) fn base_language(&self) -> &str {
+        &self.language
+    }
+
+    /// Returns the language of the current line, the language of the session is changed with a `#lang: tr` line.
     pub fn get_language(&self) -> String {
-        self.language.to_string()
+        match self.kept_index(self.position.get()).and_then(|index| self.line_languages.get(index)) {
+            Some(Some(language)) => language.to_string(),
+            _ => self.language.to_string()
+        }
     }
 }
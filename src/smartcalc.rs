@@ -6,30 +6,37 @@
 
 use core::borrow::Borrow;
 use core::ops::Deref;
-use alloc::collections::BTreeMap;
-use alloc::vec;
+use alloc::collections::{BTreeMap, BTreeSet};
 use alloc::vec::Vec;
 use alloc::rc::Rc;
+use alloc::sync::Arc;
+use chrono::{DateTime, Utc};
 use alloc::string::{String, ToString};
+use alloc::format;
 use anyhow::anyhow;
+use regex::Regex;
+use serde_derive::Serialize;
 use crate::{Session, TimeOffset};
-use crate::tokinizer::{read_currency, RuleType, small_date};
+use crate::tokinizer::{read_currency, RuleType};
 
-use crate::compiler::Interpreter;
+use crate::compiler::{Evaluator, Interpreter};
 use crate::logger::{LOGGER, initialize_logger};
 use crate::syntax::SyntaxParser;
-use crate::token::ui_token::UiToken;
+use crate::token::ui_token::{UiToken, UiTokenType};
 use crate::tokinizer::TokenInfo;
 use crate::tokinizer::Tokinizer;
+use crate::tokinizer::TokenParser;
 use crate::tools::parse_timezone;
-use crate::types::{TokenType, ExpressionFunc};
+use crate::types::{TokenType, CurrencyInfo};
+use crate::constants::{ConstantType, MonthInfo};
 use crate::types::SmartCalcAstType;
-use crate::formatter::format_result;
-use crate::config::{SmartCalcConfig, DynamicType};
+use crate::formatter::{apply_template, date_pattern_to_format, DefaultFormatter, FormatOptions, ResultFormatter, DATE_FORMAT_NAMES};
+use crate::result::ResultValue;
+use crate::config::{SmartCalcConfig, DynamicType, CurrencyStyle, DurationStyle, DurationGranularity, NegativeDurationStyle, RoundingMode};
 
 pub type ExecutionLine = Option<ExecuteLine>;
 
-pub trait RuleTrait {
+pub trait RuleTrait: Send + Sync {
     fn name(&self) -> String;
     fn call(&self, smartcalc: &SmartCalcConfig, fields: &BTreeMap<String, TokenType>) -> Option<TokenType>;
 }
@@ -51,11 +58,44 @@ impl ExecuteLineResult {
     pub fn new(output: String, ast: Rc<SmartCalcAstType>) -> Self {
         ExecuteLineResult { output, ast }
     }
+
+    /// Calculated value without the internal item types.
+    pub fn value(&self) -> Option<ResultValue> {
+        ResultValue::from_ast(&self.ast)
+    }
 }
 
-#[derive(Debug)]
+/// Step taken while a line is calculated, collected by `SmartCalc::execute_with_trace`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TraceStep {
+    /// Token found in the text, before the rules are applied.
+    Token { text: String, token_type: String },
+    /// Rule replaced the matched tokens with its result.
+    Rule { name: String, result: String },
+    /// Money converted with the rate between two currencies.
+    Conversion { from: String, to: String, rate: f64 },
+    /// Intermediate calculation and its result.
+    Calculation { expression: String, result: String }
+}
+
+/// Rule that replaced a part of the line, with the byte range and the text it consumed.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RuleMatch {
+    pub name: String,
+    pub start: usize,
+    pub end: usize,
+    pub text: String
+}
+
+#[derive(Debug, Clone)]
 pub struct ExecuteLine {
     pub result: Result<ExecuteLineResult, String>,
+    pub label: Option<String>,
+    pub trace: Vec<TraceStep>,
+    /// Parsed statements of the line before the evaluation, see `SmartCalcAstType::walk`.
+    pub syntax_trees: Vec<Rc<SmartCalcAstType>>,
+    /// Rules applied to the line in order, the last one produced the final token.
+    pub rule_matches: Vec<RuleMatch>,
     pub raw_tokens: Vec<Rc<TokenType>>,
     pub ui_tokens: Vec<UiToken>,
     pub calculated_tokens: Vec<Rc<TokenInfo>>
@@ -63,33 +103,21 @@ pub struct ExecuteLine {
 
 impl ExecuteLine {
     pub fn new(result: Result<ExecuteLineResult, String>, ui_tokens: Vec<UiToken>, raw_tokens: Vec<Rc<TokenType>>, calculated_tokens: Vec<Rc<TokenInfo>>) -> Self {
-        ExecuteLine { result, ui_tokens, raw_tokens, calculated_tokens }
+        ExecuteLine { result, label: None, trace: Vec::new(), syntax_trees: Vec::new(), rule_matches: Vec::new(), ui_tokens, raw_tokens, calculated_tokens }
     }
 }
 
+#[derive(Clone)]
 pub struct SmartCalc {
-    config: SmartCalcConfig
+    config: Arc<SmartCalcConfig>,
+    evaluator: Arc<dyn Evaluator>,
+    formatter: Arc<dyn ResultFormatter>
 }
 
 impl Default for SmartCalc {
     fn default() -> Self {
         initialize_logger();
-        let mut smartcalc = SmartCalc {
-            config: SmartCalcConfig::default()
-        };
-        smartcalc.set_date_rule("en", vec![
-            "{MONTH:month} {NUMBER:day}, {NUMBER:year}".to_string(),
-            "{MONTH:month} {NUMBER:day} {NUMBER:year}".to_string(),
-            "{NUMBER:day}/{NUMBER:month}/{NUMBER:year}".to_string(),
-            "{NUMBER:day} {MONTH:month} {NUMBER:year}".to_string(),
-            "{NUMBER:day} {MONTH:month}".to_string()
-        ]);
-        smartcalc.set_date_rule("tr", vec![
-            "{NUMBER:day}/{NUMBER:month}/{NUMBER:year}".to_string(),
-            "{NUMBER:day} {MONTH:month} {NUMBER:year}".to_string(),
-            "{NUMBER:day} {MONTH:month}".to_string()
-        ]);
-        smartcalc
+        SmartCalc::from_config(SmartCalcConfig::shared())
     }
 }
 
@@ -98,7 +126,7 @@ impl SmartCalc {
         match self.config.types.get(name.borrow()) {
             Some(_) => false,
             None => {
-                self.config.types.insert(name.borrow().to_string(), BTreeMap::new());
+                self.config_mut().types.insert(name.borrow().to_string(), BTreeMap::new());
                 true
             }
         }
@@ -120,71 +148,125 @@ impl SmartCalc {
             session.set_language("en".to_string());
             session.set_text(type_parse_item.borrow().to_string());
             
-            let tokens = Tokinizer::token_infos(&self.config, &session);
+            let tokens = Tokinizer::pattern_tokens(&self.config, &session);
             parse_tokens.push(tokens);
         }
+
+        let config = self.config_mut();
+        for word in names.iter() {
+            config.interner.intern(word);
+        }
+
+        for tokens in parse_tokens.iter_mut() {
+            config.interner.intern_tokens(tokens);
+        }
         
-        if let Some(dynamic_type) = self.config.types.get_mut(name.borrow()) {            
-            dynamic_type.insert(index, Rc::new(DynamicType::new(name.borrow().to_string(), index, format.borrow().to_string(), parse_tokens, upgrade_code.borrow().to_string(), downgrade_code.borrow().to_string(), names, decimal_digits, use_fract_rounding, remove_fract_if_zero)));
+        if let Some(dynamic_type) = self.config_mut().types.get_mut(name.borrow()) {            
+            dynamic_type.insert(index, Arc::new(DynamicType::new(name.borrow().to_string(), index, format.borrow().to_string(), parse_tokens, upgrade_code.borrow().to_string(), downgrade_code.borrow().to_string(), names, decimal_digits, use_fract_rounding, remove_fract_if_zero)));
         }
         true
     }
     
+    /// Replace the evaluation of the parsed lines, tokinizing and parsing are kept.
+    ///
+    /// `Interpreter` is the default evaluator, a custom one can call it after tracing or checking the syntax tree.
+    pub fn set_evaluator(&mut self, evaluator: Arc<dyn Evaluator>) {
+        self.evaluator = evaluator;
+    }
+
+    /// `DefaultFormatter` is the default formatter, a custom one prints the results of the executed lines and `format_result`.
+    pub fn set_formatter(&mut self, formatter: Arc<dyn ResultFormatter>) {
+        self.formatter = formatter;
+    }
+
     pub fn set_money_configuration(&mut self, remove_fract_if_zero: bool, use_fract_rounding: bool) {
-        self.config.money_config.remove_fract_if_zero = remove_fract_if_zero;
-        self.config.money_config.use_fract_rounding = use_fract_rounding;
+        self.config_mut().money_config.remove_fract_if_zero = remove_fract_if_zero;
+        self.config_mut().money_config.use_fract_rounding = use_fract_rounding;
+    }
+
+    /// How money is written when the format options do not set a currency style, `$1.500,00` by default.
+    pub fn set_currency_style(&mut self, style: CurrencyStyle) {
+        self.config_mut().money_config.style = style;
     }
     
     pub fn set_number_configuration(&mut self, decimal_digits: u8, remove_fract_if_zero: bool, use_fract_rounding: bool) {
-        self.config.number_config.decimal_digits = decimal_digits;
-        self.config.number_config.remove_fract_if_zero = remove_fract_if_zero;
-        self.config.number_config.use_fract_rounding = use_fract_rounding;
+        self.config_mut().number_config.decimal_digits = decimal_digits;
+        self.config_mut().number_config.remove_fract_if_zero = remove_fract_if_zero;
+        self.config_mut().number_config.use_fract_rounding = use_fract_rounding;
     }
     
+    /// Rounding of plain numbers to their decimal digits, numbers are rounded to the closest value by default.
+    pub fn set_number_rounding(&mut self, rounding: RoundingMode) {
+        self.config_mut().number_config.rounding = rounding;
+    }
+
+    /// Write fractions without the zeros at the end, `2,5` instead of `2,50`. Numbers and percentages use the first flag, money the second.
+    pub fn set_remove_trailing_zeros(&mut self, numbers: bool, money: bool) {
+        self.config_mut().number_config.remove_trailing_zeros = numbers;
+        self.config_mut().percentage_config.remove_trailing_zeros = numbers;
+        self.config_mut().money_config.remove_trailing_zeros = money;
+    }
+
     pub fn set_percentage_configuration(&mut self, decimal_digits: u8, remove_fract_if_zero: bool, use_fract_rounding: bool) {
-        self.config.percentage_config.decimal_digits = decimal_digits;
-        self.config.percentage_config.remove_fract_if_zero = remove_fract_if_zero;
-        self.config.percentage_config.use_fract_rounding = use_fract_rounding;
+        self.config_mut().percentage_config.decimal_digits = decimal_digits;
+        self.config_mut().percentage_config.remove_fract_if_zero = remove_fract_if_zero;
+        self.config_mut().percentage_config.use_fract_rounding = use_fract_rounding;
+    }
+
+    pub fn set_duration_configuration(&mut self, style: DurationStyle, granularity: DurationGranularity) {
+        self.config_mut().duration_config.style = style;
+        self.config_mut().duration_config.granularity = granularity;
+    }
+
+    /// Write negative durations with a sign, `-2 days`, or relative to now, `2 days ago`.
+    pub fn set_negative_duration_style(&mut self, style: NegativeDurationStyle) {
+        self.config_mut().duration_config.negative = style;
+    }
+
+    /// Keep decimal numbers as fractions while calculating, so `1/3 * 3` is exactly 1.
+    ///
+    /// Results are converted to float only for formatting, float calculation is used if a fraction overflows.
+    pub fn set_rational_mode(&mut self, enabled: bool) {
+        self.config_mut().rational_mode = enabled;
+    }
+
+    /// Write dates up to the given number of days away from today as `tomorrow`, `in 3 days` or `2 weeks ago`.
+    ///
+    /// Dates further away are written with the date format, `None` disables relative dates.
+    pub fn set_relative_dates(&mut self, threshold_days: Option<u32>) {
+        self.config_mut().relative_date_days = threshold_days;
+    }
+
+    pub fn set_time_provider<T: Fn() -> DateTime<Utc> + Send + Sync + 'static>(&mut self, provider: T) {
+        self.config_mut().time_provider = Arc::new(provider);
+    }
+
+    /* Parsers are called with the whole line, found tokens should be added with add_token_location */
+    pub fn add_token_parser<T: Borrow<str>>(&mut self, name: T, parser: TokenParser) -> bool {
+        match self.config.token_parsers.iter().any(|(parser_name, _)| parser_name == name.borrow()) {
+            true => false,
+            false => {
+                self.config_mut().token_parsers.push((name.borrow().to_string(), parser));
+                true
+            }
+        }
     }
 
     pub fn set_decimal_seperator(&mut self, decimal_seperator: String) {
-        self.config.decimal_seperator = decimal_seperator;
+        self.config_mut().decimal_seperator = decimal_seperator;
     }
     
     pub fn set_thousand_separator(&mut self, thousand_separator: String) {
-        self.config.thousand_separator = thousand_separator;
+        self.config_mut().thousand_separator = thousand_separator;
     }
-    
-    pub fn set_date_rule(&mut self, language: &str, rules: Vec<String>) {                
-        let mut function_items = Vec::new();
-        for rule_item in rules {
-            let mut session = Session::new();
-            session.set_language(language.to_string());
-            session.set_text(rule_item.to_string());
-            function_items.push(Tokinizer::token_infos(&self.config, &session));
-        }
-        
-        let current_rules = match self.config.rule.get_mut(language) {
-            Some(current_rules) => current_rules,
-            None => return
-        };
 
-        /* Remove small_date rule */
-        current_rules.retain(|rule| {
-            let is_small_date = if let RuleType::Internal { function_name, .. } = rule {
-                function_name == "small_date"
-            } else {
-                false
-            };
-            
-            !is_small_date
-        });
-        
-        current_rules.push(RuleType::Internal {
-            function_name: "small_date".to_string(),
-            function: small_date as ExpressionFunc,
-            tokens_list: function_items
-        });
+    /// Use the decimal and thousand separators of the session language instead of the configured ones, `1.234,56` for `tr` and `1,234.56` for `en`.
+    pub fn set_language_separators(&mut self, language_separators: bool) {
+        self.config_mut().language_separators = language_separators;
+    }
+    
+    pub fn set_date_rule(&mut self, language: &str, rules: Vec<String>) {
+        self.config_mut().set_date_rule(language, rules);
     }
     
     pub fn set_timezone(&mut self, timezone: String) -> Result<(), String> {
@@ -204,8 +286,8 @@ impl SmartCalc {
         
         match timezone {
             Some((timezone, offset)) => {
-                self.config.timezone = timezone.to_uppercase();
-                self.config.timezone_offset = offset;
+                self.config_mut().timezone = timezone.to_uppercase();
+                self.config_mut().timezone_offset = offset;
                 Ok(())
             },
             None => Err("Timezone information not found".to_string())
@@ -215,25 +297,160 @@ impl SmartCalc {
     pub fn get_time_offset(&self) -> TimeOffset {
         self.config.get_time_offset()
     }
+
+    pub fn set_fiscal_year_start_month(&mut self, month: u32) -> Result<(), String> {
+        match month {
+            1..=12 => {
+                self.config_mut().fiscal_year_start_month = month;
+                Ok(())
+            },
+            _ => Err("Month information not valid".to_string())
+        }
+    }
     
     pub fn load_from_json(json_data: &str) -> Self {
+        SmartCalc::from_config(Arc::new(SmartCalcConfig::load_from_json(json_data)))
+    }
+
+    pub fn from_json(json_data: &str) -> Result<Self, String> {
+        Ok(SmartCalc::from_config(Arc::new(SmartCalcConfig::from_json(json_data)?)))
+    }
+
+    /// Create an engine on top of a configuration, engines can share one configuration like `SmartCalcConfig::shared()`.
+    /// Settings changed on the engine are applied to its own copy, the shared configuration is not changed.
+    pub fn from_config(config: Arc<SmartCalcConfig>) -> Self {
         SmartCalc {
-            config: SmartCalcConfig::load_from_json(json_data)
+            config,
+            evaluator: Arc::new(Interpreter),
+            formatter: Arc::new(DefaultFormatter)
         }
     }
 
+    /* Configuration is copied on the first change when it is shared with other engines */
+    fn config_mut(&mut self) -> &mut SmartCalcConfig {
+        Arc::make_mut(&mut self.config)
+    }
+
+    pub fn merge_json(&mut self, json_data: &str) -> Result<(), String> {
+        self.config_mut().merge_json(json_data)
+    }
+
+    /// Add a language from a language pack, the JSON object of a language in the configuration with its months, words, rules and formats.
+    ///
+    /// A language with the same name is replaced, settings changed from code are kept.
+    pub fn load_language<T: Borrow<str>>(&mut self, language: T, json_data: T) -> Result<(), String> {
+        self.config_mut().load_language(language.borrow(), json_data.borrow())
+    }
+
+    /// Add a language from a language pack file, see `load_language`.
+    #[cfg(feature = "std")]
+    pub fn load_language_file<T: Borrow<str>, P: AsRef<std::path::Path>>(&mut self, language: T, path: P) -> Result<(), String> {
+        self.config_mut().load_language_file(language.borrow(), path)
+    }
+
     pub fn update_currency(&mut self, currency: &str, rate: f64) -> bool {
         match read_currency(&self.config, currency) {
             Some(real_currency) => {
-                self.config.currency_rate.insert(real_currency, rate);
+                self.config_mut().currency_rate.insert(real_currency, rate);
                 true
             },
              _ => false
         }
     }
     
+    /// Add a currency with its rate to USD. The symbol is also read as the currency, unless it is used by another one.
+    pub fn add_currency(&mut self, currency: CurrencyInfo, rate: f64) -> bool {
+        let code = currency.code.to_lowercase();
+        if self.config.currency.contains_key(&code) {
+            return false;
+        }
+
+        let symbol = currency.symbol.to_lowercase();
+        let currency = Arc::new(currency);
+        self.config_mut().currency.insert(code.to_string(), currency.clone());
+        self.config_mut().currency_rate.insert(currency.clone(), rate);
+        self.config_mut().interner.intern(&code);
+        self.config_mut().interner.intern(&symbol);
+
+        /* Tables are rebuilt from the JSON data while merging, so it is updated as well */
+        self.config_mut().json_data.currencies.insert(currency.code.to_string(), currency.clone());
+        self.config_mut().json_data.currency_rates.insert(code.to_string(), rate);
+
+        if symbol != code && !self.config.currency_alias.contains_key(&symbol) {
+            self.config_mut().currency_alias.insert(symbol.to_string(), currency);
+            self.config_mut().json_data.currency_alias.insert(symbol, code);
+        }
+
+        self.config_mut().update_keyword_filter();
+        true
+    }
+
+    /// Add a word for a constant, like "wk" for `ConstantType::Week` or "π" for `ConstantType::Pi`.
+    pub fn add_constant_word<T: Borrow<str>>(&mut self, language: T, word: T, constant: ConstantType) -> bool {
+        let config = self.config_mut();
+        let (constants, language_data) = match (config.constant_pair.get_mut(language.borrow()), config.json_data.languages.get_mut(language.borrow())) {
+            (Some(constants), Some(language_data)) => (constants, language_data),
+            _ => return false
+        };
+
+        let word = word.borrow().to_lowercase();
+        language_data.constant_pair.insert(word.to_string(), constant.clone() as u8);
+        config.interner.intern(&word);
+
+        let is_duration = matches!(constant, ConstantType::Second | ConstantType::Minute | ConstantType::Hour | ConstantType::Day | ConstantType::Week | ConstantType::Month | ConstantType::Year);
+        constants.insert(word.to_string(), constant);
+
+        /* Duration rules match the unit from its word group */
+        if is_duration {
+            self.config_mut().add_group_word(language.borrow(), "duration_group", word);
+        }
+        true
+    }
+
+    /// Add names for months, every name is given with its month number (1 to 12).
+    pub fn add_month_names<T: Borrow<str>>(&mut self, language: T, names: Vec<(T, u8)>) -> bool {
+        if names.iter().any(|(_, month)| !(1..=12).contains(month)) {
+            return false;
+        }
+
+        let months = match self.config_mut().month_regex.get_mut(language.borrow()) {
+            Some(months) => months,
+            None => return false
+        };
+
+        /* First twelve items are the names used while printing, new names are added after them */
+        for (name, month) in names.iter() {
+            let name = name.borrow().to_lowercase();
+            match Regex::new(&format!(r"\b{}\b", regex::escape(&name))) {
+                Ok(re) => months.push((re, MonthInfo { short: name.to_string(), long: name, month: *month })),
+                Err(error) => log::error!("Month parser error ({}) {}", name, error)
+            };
+        }
+        true
+    }
+
+    /// Set how dates are written, `name` is one of `full_date`, `current_year`, `full_date_time` or `current_year_with_time`.
+    ///
+    /// Pattern can use `%d %B %Y` style specifiers or the `{day} {month_long} {year}` placeholders of the language formats.
+    pub fn set_date_format<T: Borrow<str>>(&mut self, language: T, name: T, pattern: T) -> bool {
+        if !DATE_FORMAT_NAMES.contains(&name.borrow()) {
+            return false;
+        }
+
+        let config = self.config_mut();
+        let (format, language_data) = match (config.format.get_mut(language.borrow()), config.json_data.languages.get_mut(language.borrow())) {
+            (Some(format), Some(language_data)) => (format, language_data),
+            _ => return false
+        };
+
+        let pattern = date_pattern_to_format(pattern.borrow());
+        format.date.insert(name.borrow().to_string(), pattern.to_string());
+        language_data.format.date.insert(name.borrow().to_string(), pattern);
+        true
+    }
+
     pub fn delete_rule(&mut self, language: String, rule_name: String) -> bool {
-        match self.config.rule.get_mut(&language) {
+        match self.config_mut().rule.get_mut(&language) {
             Some(language_collection) => {
                 let position = language_collection.iter().position(|item| match item {
                     RuleType::API { tokens_list: _, rule: rule_item } => rule_name == rule_item.name(),
@@ -243,6 +460,7 @@ impl SmartCalc {
                 match position {
                     Some(location) => {
                         language_collection.remove(location);
+                        self.config_mut().update_rule_matcher();
                         true
                     }
                     _ => false
@@ -252,18 +470,22 @@ impl SmartCalc {
         }
     }
     
-    pub fn add_rule(&mut self, language: String, rules: Vec<String>, rule: Rc<dyn RuleTrait>) -> bool {
+    pub fn add_rule(&mut self, language: String, rules: Vec<String>, rule: Arc<dyn RuleTrait>) -> bool {
         let mut rule_tokens = Vec::new();
         
         for rule_item in rules.iter() {
             let mut session = Session::new();
             session.set_language(language.to_string());
             session.set_text(rule_item.to_string());
-            let tokens = Tokinizer::token_infos(&self.config, &session);
+            let tokens = Tokinizer::pattern_tokens(&self.config, &session);
             rule_tokens.push(tokens);
         }
+
+        for tokens in rule_tokens.iter_mut() {
+            self.config_mut().interner.intern_tokens(tokens);
+        }
         
-        let language_data = match self.config.rule.get_mut(&language) {
+        let language_data = match self.config_mut().rule.get_mut(&language) {
             Some(language) => language,
             None => return false
         };
@@ -272,11 +494,19 @@ impl SmartCalc {
             tokens_list: rule_tokens,
             rule
         });
+        self.config_mut().update_rule_matcher();
         true
     }
     
-    pub fn format_result(&self, session: &Session, result: Rc<SmartCalcAstType>) -> String {
-        format_result(&self.config, session, result)
+    /// Print a result, `FormatOptions::default()` prints it like the executed lines.
+    pub fn format_result(&self, session: &Session, result: Rc<SmartCalcAstType>, options: &FormatOptions) -> String {
+        self.format_line(session, "", result, options)
+    }
+
+    /// Print a result with the input of its line, used by the `{input}` placeholder of `FormatOptions::template`.
+    pub fn format_line(&self, session: &Session, input: &str, result: Rc<SmartCalcAstType>, options: &FormatOptions) -> String {
+        let output = self.formatter.format(&self.config, session, result.clone(), options);
+        apply_template(output, input, &result, options)
     }
 
     pub fn initialize() {
@@ -290,34 +520,230 @@ impl SmartCalc {
     }
 
 
-    pub(crate) fn execute_text(&self, session: &Session) -> ExecutionLine {
-        if session.current_line().is_empty() {
-            return None;
+    /* Statements are separated with ';', separators inside of an atom or a comment are skipped */
+    fn statements(line: &str) -> Vec<(usize, &str)> {
+        let mut statements = Vec::new();
+        let mut start = 0;
+        let mut in_atom = false;
+        let mut comment_end = 0;
+
+        for (index, ch) in line.char_indices() {
+            if index < comment_end {
+                continue;
+            }
+
+            match ch {
+                '#' => break,
+                '/' if line[index + 1..].starts_with('/') => break,
+                '/' if line[index + 1..].starts_with('*') => match line[index + 2..].find("*/") {
+                    Some(end) => comment_end = index + end + 4,
+                    None => break
+                },
+                '[' => in_atom = true,
+                ']' => in_atom = false,
+                ';' if !in_atom => {
+                    statements.push((start, &line[start..index]));
+                    start = index + 1;
+                },
+                _ => ()
+            };
         }
 
-        let mut tokinizer = Tokinizer::new(&self.config, session);
+        statements.push((start, &line[start..]));
+        statements
+    }
+
+    fn execute_statement(&self, session: &Session, data: &str, offset: usize) -> ExecutionLine {
+        let mut tokinizer = Tokinizer::with_text(&self.config, session, data, offset);
         if !tokinizer.tokinize() {
             return None;
         }
 
         let mut syntax = SyntaxParser::new(session, &tokinizer);
 
-        let execution_result = match syntax.parse() {
-            Ok(ast) => {
-                let ast_rc = Rc::new(ast);
+        let mut syntax_trees = Vec::new();
+        let rule_error = tokinizer.error.borrow_mut().take();
+        let execution_result = match rule_error {
+            Some(error) => Err(error),
+            None => match syntax.parse() {
+                Ok(ast) => {
+                    let ast = Rc::new(ast);
+                    syntax_trees.push(ast.clone());
 
-                match Interpreter::execute(&self.config, ast_rc, session) {
-                    Ok(ast) => Ok(ExecuteLineResult::new(self.format_result(session, ast.clone()), ast)),
-                    Err(error) => Err(error)
+                    match self.evaluator.evaluate(&self.config, ast, session) {
+                        Ok(ast) => Ok(ExecuteLineResult::new(self.format_result(session, ast.clone(), &FormatOptions::default()), ast)),
+                        Err(error) => Err(error)
+                    }
+                },
+                Err((error, _, _)) => {
+                    log::info!("Syntax parse error, {}", error);
+                    Err(error.to_string())
                 }
-            },
-            Err((error, _, _)) => {
-                log::info!("Syntax parse error, {}", error);
-                Err(error.to_string())
             }
         };
         
-        Some(ExecuteLine::new(execution_result, tokinizer.ui_tokens.get_tokens(), tokinizer.tokens, tokinizer.token_infos.clone()))
+        /* Positions of the normalized digits point to the written text, so the moved tokens share a copy of it */
+        let mut written_text: Option<Arc<str>> = None;
+        let token_infos = tokinizer.token_infos.iter().map(|token| match tokinizer.original_position(token.start) == token.start && tokinizer.original_position(token.end) == token.end {
+            true => token.clone(),
+            false => Rc::new(TokenInfo {
+                start: tokinizer.original_position(token.start),
+                end: tokinizer.original_position(token.end),
+                source: written_text.get_or_insert_with(|| Arc::from(data)).clone(),
+                ..token.deref().clone()
+            })
+        }).collect();
+
+        let mut line = ExecuteLine::new(execution_result, tokinizer.ui_tokens.get_tokens(), tokinizer.tokens, token_infos);
+        line.label = tokinizer.label;
+        line.syntax_trees = syntax_trees;
+        line.rule_matches = tokinizer.rule_matches;
+        Some(line)
+    }
+
+    pub(crate) fn execute_text(&self, session: &Session) -> ExecutionLine {
+        let line = session.current_line();
+        let comment = session.block_comment_length();
+        if line.len() == comment {
+            return None;
+        }
+
+        /* '#lang:' directive may name a language that is not loaded */
+        let language = session.get_language();
+        if !self.config.json_data.languages.contains_key(&language) {
+            return Some(ExecuteLine::new(Err(format!("Language not found. ({})", language)), Vec::new(), Vec::new(), Vec::new()));
+        }
+
+        let statements = SmartCalc::statements(&line[comment..]).into_iter().map(|(offset, statement)| (offset + comment, statement)).collect::<Vec<_>>();
+        if statements.len() == 1 && comment == 0 {
+            return self.execute_statement(session, line, 0);
+        }
+
+        /* Statements run one by one, so the variables of a statement are visible to the next ones */
+        let mut result = None;
+        let mut label = None;
+        let mut ui_tokens = Vec::new();
+        let mut raw_tokens = Vec::new();
+        let mut calculated_tokens = Vec::new();
+        let mut syntax_trees = Vec::new();
+        let mut rule_matches = Vec::new();
+
+        if comment > 0 {
+            ui_tokens.push(UiToken { start: 0, end: line[..comment].chars().count(), ui_type: UiTokenType::Comment });
+        }
+
+        let source: Arc<str> = Arc::from(line.as_str());
+
+        for (offset, statement) in statements.into_iter() {
+            if statement.trim().is_empty() {
+                continue;
+            }
+
+            let statement_line = match self.execute_statement(session, statement, offset) {
+                Some(statement_line) => statement_line,
+                None => continue
+            };
+
+            let char_offset = line[..offset].chars().count();
+            if offset > comment {
+                ui_tokens.push(UiToken { start: char_offset - 1, end: char_offset, ui_type: UiTokenType::Operator });
+            }
+
+            ui_tokens.extend(statement_line.ui_tokens.into_iter().map(|token| UiToken { start: token.start + char_offset, end: token.end + char_offset, ui_type: token.ui_type }));
+            raw_tokens.extend(statement_line.raw_tokens);
+            syntax_trees.extend(statement_line.syntax_trees);
+            rule_matches.extend(statement_line.rule_matches.into_iter().map(|rule_match| RuleMatch { start: rule_match.start + offset, end: rule_match.end + offset, ..rule_match }));
+            calculated_tokens.extend(statement_line.calculated_tokens.iter().map(|token| Rc::new(TokenInfo {
+                start: token.start + offset,
+                end: token.end + offset,
+                source: source.clone(),
+                ..token.deref().clone()
+            })));
+
+            let failed = statement_line.result.is_err();
+            label = label.or(statement_line.label);
+            result = Some(statement_line.result);
+            if failed {
+                break;
+            }
+        }
+
+        result.map(|result| ExecuteLine { label, syntax_trees, rule_matches, ..ExecuteLine::new(result, ui_tokens, raw_tokens, calculated_tokens) })
+    }
+
+    /* Byte range of the variable name on the left side of an assignment */
+    fn assignment_name_range(line: &str) -> Option<(usize, usize)> {
+        let name = &line[..line.find('=')?];
+        let name = name.split(':').next().unwrap_or(name);
+        let start = name.len() - name.trim_start().len();
+        Some((start, name.trim_end().len()))
+    }
+
+    /// Variables of the executed session as name, current value, defining line index and the byte range of the name, see
+    /// `Session::variables`.
+    pub fn variables(&self, session: &Session) -> impl Iterator<Item = (String, Rc<SmartCalcAstType>, usize, (usize, usize))> {
+        session.variables()
+    }
+
+    /// Rename a variable and every line that uses it, then execute the session again.
+    pub fn rename_variable(&self, session: &mut Session, old_name: &str, new_name: &str) -> Result<ExecuteResult, String> {
+        let variable = match session.get_variable(old_name) {
+            Some(variable) => variable,
+            None => return Err("Variable not found".to_string())
+        };
+
+        if new_name.trim().is_empty() || new_name.contains(['=', ':']) {
+            return Err("Variable name not valid".to_string());
+        }
+
+        if session.get_variable(new_name).is_some() {
+            return Err("Variable already exists".to_string());
+        }
+
+        /* References are resolved line by line, so the session is executed once with the current names */
+        let text = session.get_text();
+        session.set_text(text.to_string());
+        let results = self.execute_session(session);
+
+        let mut lines = Vec::new();
+        for (index, line) in session.text_parts.iter().enumerate() {
+            let mut ranges = Vec::new();
+
+            for (offset, statement) in SmartCalc::statements(line) {
+                if let Some((start, end)) = SmartCalc::assignment_name_range(statement) {
+                    if Session::variable_key(&statement[start..end]) == variable.to_string() {
+                        ranges.push((offset + start, offset + end));
+                    }
+                }
+            }
+
+            if let Some(Some(result)) = results.lines.get(index) {
+                for token in result.calculated_tokens.iter() {
+                    if let Some(TokenType::Variable(item)) = token.token_type.borrow().deref() {
+                        if Rc::ptr_eq(item, &variable) {
+                            ranges.push((token.start, token.end));
+                        }
+                    }
+                }
+            }
+
+            let mut line = line.to_string();
+            ranges.sort_unstable();
+            for (start, end) in ranges.into_iter().rev() {
+                line.replace_range(start..end, new_name.trim());
+            }
+
+            lines.push(line);
+        }
+
+        let separator = match text.contains("\r\n") {
+            true => "\r\n",
+            false => "\n"
+        };
+
+        session.remove_variable(old_name);
+        session.set_text(lines.join(separator));
+        Ok(self.execute_session(session))
     }
 
     pub fn execute<Tlan: Borrow<str>, Tdata: Borrow<str>>(&self, language: Tlan, data: Tdata) -> ExecuteResult {
@@ -328,6 +754,76 @@ impl SmartCalc {
         self.execute_session(&session)
     }
 
+    /// Execute the snippets one after another in the same session, results are grouped by snippet.
+    ///
+    /// Variables defined in a snippet can be used in the next ones.
+    pub fn execute_many<Tlan: Borrow<str>, Tdata: Borrow<str>>(&self, language: Tlan, snippets: &[Tdata]) -> Vec<ExecuteResult> {
+        let mut session = Session::new();
+        session.set_language(language.borrow().to_string());
+
+        snippets.iter().map(|snippet| {
+            session.set_text(snippet.borrow().to_string());
+            self.execute_session(&session)
+        }).collect()
+    }
+
+    /// Execute the text and pass each line to the callback with its index as soon as it is calculated, the results are not collected.
+    pub fn execute_with<Tlan: Borrow<str>, Tdata: Borrow<str>, F: FnMut(usize, ExecutionLine)>(&self, language: Tlan, data: Tdata, mut on_line: F) {
+        let mut session = Session::new();
+
+        session.set_text(data.borrow().to_string());
+        session.set_language(language.borrow().to_string());
+
+        if session.has_value() {
+            loop {
+                on_line(session.current_line_index(), self.execute_current_line(&session));
+                if session.next_line().is_none() {
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Calculate the lines of the text as a stream, the lines are calculated on a worker thread and read by the task one by one.
+    #[cfg(feature = "async")]
+    pub fn execute_async<Tlan: Borrow<str>, Tdata: Borrow<str>>(&self, language: Tlan, data: Tdata) -> crate::stream::ExecuteStream {
+        crate::stream::ExecuteStream::new(self.clone(), language.borrow().to_string(), data.borrow().to_string())
+    }
+
+    /// Execute the lines of the text at the same time on the threads of the rayon pool, results are the same as the lines of `execute`
+    /// converted to `LineResult`.
+    ///
+    /// Lines are calculated on their own, so the text is calculated again line by line when a line uses the results of the other
+    /// lines, like `total`, or defines a variable used in a following line. Results stop at a cancelled line, its error is in the
+    /// diagnostics.
+    #[cfg(feature = "parallel")]
+    pub fn execute_parallel<Tlan: Borrow<str>, Tdata: Borrow<str>>(&self, language: Tlan, data: Tdata) -> Vec<Option<crate::result::LineResult>> {
+        let mut session = Session::new();
+
+        session.set_text(data.borrow().to_string());
+        session.set_language(language.borrow().to_string());
+        crate::parallel::execute(self, &session)
+    }
+
+    /// Execute the text like `execute`, each line also has the steps of the calculation.
+    ///
+    /// Steps are the matched tokens, the fired rules, the applied currency rates and the intermediate values, in the order they are taken.
+    pub fn execute_with_trace<Tlan: Borrow<str>, Tdata: Borrow<str>>(&self, language: Tlan, data: Tdata) -> ExecuteResult {
+        let mut session = Session::new();
+
+        session.set_text(data.borrow().to_string());
+        session.set_language(language.borrow().to_string());
+        session.start_trace();
+        self.execute_session(&session)
+    }
+
+    /// Execute the text and write the lines as JSON, with the results, syntax trees and tokens.
+    ///
+    /// Empty lines are written as `null`.
+    pub fn execute_to_json<Tlan: Borrow<str>, Tdata: Borrow<str>>(&self, language: Tlan, data: Tdata) -> Result<String, String> {
+        serde_json::to_string(&self.execute(language, data).lines).map_err(|error| error.to_string())
+    }
+
     pub fn basic_execute<T: Borrow<str>>(data: T, config: &SmartCalcConfig) -> anyhow::Result<f64> {
         let mut session = Session::new();
 
@@ -374,20 +870,172 @@ impl SmartCalc {
         }
     }
 
+    /// Restore a session saved with `Session::serialize`, currencies and dynamic types are resolved from this configuration.
+    pub fn load_session(&self, data: &str) -> Result<Session, String> {
+        Session::deserialize(&self.config, data)
+    }
+
+    pub(crate) fn execute_current_line(&self, session: &Session) -> ExecutionLine {
+        let line_result = match session.cached_line() {
+            Some(line_result) => line_result,
+            None => {
+                session.start_line_tracking();
+                let mut line_result = self.execute_text(session);
+                let trace = session.take_trace();
+                if let Some(line) = line_result.as_mut() {
+                    line.trace = trace;
+                }
+
+                /* Rules are not applied completely on an interrupted line, so the result is not used */
+                match session.interruption() {
+                    Some(error) => {
+                        session.remove_line_record();
+                        Some(ExecuteLine::new(Err(error), Vec::new(), Vec::new(), Vec::new()))
+                    },
+                    None => {
+                        session.record_line(&line_result);
+                        session.cache_line(&line_result);
+                        line_result
+                    }
+                }
+            }
+        };
+        session.add_line_result(line_result.as_ref().and_then(|line| line.result.as_ref().ok()).map(|result| result.ast.clone()));
+        session.add_line_label(line_result.as_ref().and_then(|line| line.label.clone()));
+        line_result
+    }
+
+    /// Execute one line after the lines of the session, the line is added to the end of the session text.
+    ///
+    /// Variables and results of the previous lines can be used, so editors do not need to execute the whole document again.
+    pub fn execute_line<Tlan: Borrow<str>, Tdata: Borrow<str>>(&self, session: &mut Session, language: Tlan, line: Tdata) -> ExecutionLine {
+        session.set_language(language.borrow().to_string());
+        session.push_line(line.borrow());
+        self.execute_current_line(session)
+    }
+
+    /// Replace a line of an executed session and return the results of every line.
+    ///
+    /// The session keeps the variables each line read and assigned since the first update, which executes the whole document. After
+    /// that only the changed line and the lines depending on it are calculated again: lines reading a variable with a new value, lines
+    /// mentioning a new variable and lines using the results of the previous lines, like `total`, after a changed line. Results of the
+    /// other lines are reused. Variables the replaced line does not assign anymore are removed. Returns an empty result when the line
+    /// does not exist.
+    pub fn update_line<Tdata: Borrow<str>>(&self, session: &mut Session, index: usize, line: Tdata) -> ExecuteResult {
+        let recorded = session.has_line_records();
+        let assigned_variables = session.line_assignments(index);
+        let changed_lines = match session.replace_line(index, line.borrow()) {
+            Some(changed_lines) => changed_lines,
+            None => return ExecuteResult::default()
+        };
+
+        /* Variables of the replaced line are assigned again only if the new line still assigns them, or by the other lines assigning
+           them. The lines reading a removed variable do not find it, so they are calculated again. */
+        for name in assigned_variables.iter() {
+            session.remove_variable(name);
+        }
+
+        if !recorded {
+            session.start_line_records();
+            return session.execute(self);
+        }
+
+        let mut results = ExecuteResult { status: true, lines: Vec::new() };
+        let mut lines_changed = false;
+        let mut added_variables = BTreeSet::new();
+        session.start_time_limit();
+
+        loop {
+            let recorded_line = match changed_lines.contains(&session.current_line_index()) {
+                true => None,
+                false => session.recorded_line(lines_changed, &added_variables)
+            };
+
+            let line = match recorded_line {
+                Some(line) => line,
+                None => {
+                    let variables = session.variable_names();
+                    let line = self.execute_current_line(session);
+                    added_variables.extend(session.variable_names().difference(&variables).cloned());
+                    lines_changed = true;
+                    line
+                }
+            };
+
+            results.lines.push(line);
+            if session.interruption().is_some() {
+                results.status = false;
+                break;
+            }
+
+            if session.next_line().is_none() {
+                break;
+            }
+        }
+
+        session.prune_token_cache();
+        results
+    }
+
+    /// Replace a line of an executed session like `update_line` and return only the lines with a new result, with their indexes.
+    ///
+    /// The replaced line is always returned. Other lines are left out when their result is reused or they fail again, so the first
+    /// update returns every line unless their results are reused from the line cache.
+    pub fn update_line_changes<Tdata: Borrow<str>>(&self, session: &mut Session, index: usize, line: Tdata) -> Vec<(usize, ExecutionLine)> {
+        let previous = session.line_results.borrow().clone();
+
+        self.update_line(session, index, line).lines.into_iter().enumerate().filter(|(line_index, line)| {
+            let current = line.as_ref().and_then(|line| line.result.as_ref().ok()).map(|result| &result.ast);
+            match (previous.get(*line_index), current) {
+                _ if *line_index == index => true,
+                (Some(Some(previous)), Some(current)) => !Rc::ptr_eq(previous, current),
+                (Some(None), None) => false,
+                _ => true
+            }
+        }).collect()
+    }
+
+    /// Execute a document line by line while reading it, the whole text is not kept in memory.
+    ///
+    /// A trailing line break does not add an empty line to the results.
+    #[cfg(feature = "std")]
+    pub fn execute_reader<Tlan: Borrow<str>, R: std::io::BufRead>(&self, language: Tlan, mut reader: R) -> std::io::Result<ExecuteResult> {
+        let mut session = Session::new();
+        session.set_language(language.borrow().to_string());
+
+        let mut results = ExecuteResult::default();
+        let mut buffer = String::new();
+
+        while reader.read_line(&mut buffer)? > 0 {
+            session.set_stream_line(buffer.trim_end_matches(&['\r', '\n'][..]));
+            results.lines.push(self.execute_current_line(&session));
+            results.status = true;
+            buffer.clear();
+        }
+
+        Ok(results)
+    }
+
     pub fn execute_session(&self, session: &Session) -> ExecuteResult {
         let mut results = ExecuteResult::default();
+        session.start_time_limit();
 
         if session.has_value() {
             results.status = true;
             loop {
-                let line_result = self.execute_text(session);
-                results.lines.push(line_result);
+                results.lines.push(self.execute_current_line(session));
+                if session.interruption().is_some() {
+                    results.status = false;
+                    break;
+                }
+
                 if session.next_line().is_none() {
                     break;
                 }
             }
         }
 
+        session.prune_token_cache();
         results
     }
 }
@@ -395,9 +1043,9 @@ impl SmartCalc {
 #[cfg(test)]
 mod test {
     use core::ops::Deref;
-    use alloc::{collections::BTreeMap, string::{String, ToString}, vec, rc::Rc};
+    use alloc::{collections::BTreeMap, string::{String, ToString}, vec, vec::Vec, rc::Rc, sync::Arc};
 
-    use crate::{SmartCalc, types::{TokenType, NumberType}, RuleTrait, SmartCalcConfig};
+    use crate::{SmartCalc, types::{TokenType, NumberType, SmartCalcAstType}, RuleTrait, SmartCalcConfig};
 
     #[derive(Default)]
     pub struct Test1;
@@ -408,7 +1056,7 @@ mod test {
         fn call(&self, _: &SmartCalcConfig, fields: &BTreeMap<String, TokenType>) -> Option<TokenType> {
             match fields.get("surname") {
                 Some(TokenType::Text(surname)) => {
-                    assert_eq!(surname, &"baris".to_string());
+                    assert_eq!(surname.text(), "baris");
                     Some(TokenType::Number(2022.0, NumberType::Decimal))
                 },
                 _ => None
@@ -462,7 +1110,7 @@ mod test {
     #[test]
     fn add_rule_1() ->  Result<(), ()> {
         let mut calculater = SmartCalc::default();
-        let test1 = Rc::new(Test1::default());
+        let test1 = Arc::new(Test1::default());
         calculater.add_rule("en".to_string(), vec!["erhan {TEXT:surname}".to_string(), "{TEXT:surname} erhan".to_string()], test1.clone());
         let result = calculater.execute("en".to_string(), "erhan baris");
         check_basic_rule_output!(result, TokenType::Number(2022.0, NumberType::Decimal));
@@ -476,7 +1124,7 @@ mod test {
     #[test]
     fn add_rule_2() ->  Result<(), ()> {
         let mut calculater = SmartCalc::default();
-        let test1 = Rc::new(Test1::default());
+        let test1 = Arc::new(Test1::default());
         calculater.add_rule("en".to_string(), vec!["erhan {TEXT:surname:baris}".to_string(), "{TEXT:surname:baris} erhan".to_string()], test1.clone());
         let result = calculater.execute("en".to_string(), "erhan baris");
         check_basic_rule_output!(result, TokenType::Number(2022.0, NumberType::Decimal));
@@ -490,7 +1138,7 @@ mod test {
     #[test]
     fn delete_rule_2() ->  Result<(), ()> {
         let mut calculater = SmartCalc::default();
-        let test1 = Rc::new(Test1::default());
+        let test1 = Arc::new(Test1::default());
         assert!(calculater.add_rule("en".to_string(), vec!["erhan {TEXT:surname:baris}".to_string(), "{TEXT:surname:baris} erhan".to_string()], test1.clone()));
         assert!(calculater.delete_rule("en".to_string(), test1.name().clone()));
 
@@ -500,7 +1148,7 @@ mod test {
     #[test]
     fn delete_rule_3() ->  Result<(), ()> {
         let mut calculater = SmartCalc::default();
-        let test1 = Rc::new(Test1::default());
+        let test1 = Arc::new(Test1::default());
         assert!(!calculater.delete_rule("en".to_string(), test1.name().clone()));
 
         Ok(())
@@ -615,7 +1263,7 @@ mod test {
                 _ => return None
             };
             let coin = match fields.get("coin") {
-                Some(TokenType::Text(text)) => text.clone(),
+                Some(TokenType::Text(text)) => text.to_string(),
                 _ => return None
             };
             
@@ -632,7 +1280,7 @@ mod test {
     #[test]
     fn add_rule_3() ->  Result<(), ()> {
         let mut calculater = SmartCalc::default();
-        let test1 = Rc::new(Coin::default());
+        let test1 = Arc::new(Coin::default());
         calculater.add_rule("en".to_string(), vec!["{NUMBER:count} {TEXT:coin}".to_string()], test1.clone());
         let result = calculater.execute("en".to_string(), "10 btc to usd");
         check_basic_rule_output!(result, TokenType::Money(10000.0, calculater.config.get_currency("usd".to_string()).unwrap()));
@@ -642,7 +1290,7 @@ mod test {
     #[test]
     fn add_rule_4() ->  Result<(), ()> {
         let mut calculater = SmartCalc::default();
-        let test1 = Rc::new(Coin::default());
+        let test1 = Arc::new(Coin::default());
         calculater.add_rule("en".to_string(), vec!["{NUMBER:count} {TEXT:coin}".to_string()], test1.clone());
         let result = calculater.execute("en".to_string(), "10 eth to usd");
         check_basic_rule_output!(result, TokenType::Money(8000.0, calculater.config.get_currency("usd".to_string()).unwrap()));
@@ -652,10 +1300,89 @@ mod test {
     #[test]
     fn add_rule_5() ->  Result<(), ()> {
         let mut calculater = SmartCalc::default();
-        let test1 = Rc::new(Coin::default());
+        let test1 = Arc::new(Coin::default());
         calculater.add_rule("en".to_string(), vec!["{NUMBER:count} {TEXT:coin}".to_string()], test1.clone());
         let result = calculater.execute("en".to_string(), "10 eth to dkk");
         check_basic_rule_output!(result, TokenType::Money(49644.9970792, calculater.config.get_currency("dkk".to_string()).unwrap()));
         Ok(())
     }
+
+    /* Counts the evaluated lines and rejects the power operator */
+    #[derive(Default)]
+    pub struct LimitedEvaluator {
+        count: core::sync::atomic::AtomicUsize
+    }
+
+    impl crate::Evaluator for LimitedEvaluator {
+        fn evaluate(&self, config: &SmartCalcConfig, ast: Rc<SmartCalcAstType>, session: &crate::Session) -> Result<Rc<SmartCalcAstType>, String> {
+            self.count.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+            match ast.deref() {
+                SmartCalcAstType::Binary { operator: '^', .. } => Err("Power is not allowed".to_string()),
+                _ => crate::Interpreter.evaluate(config, ast, session)
+            }
+        }
+    }
+
+    #[test]
+    fn custom_evaluator() {
+        let mut calculater = SmartCalc::default();
+        let evaluator = Arc::new(LimitedEvaluator::default());
+        calculater.set_evaluator(evaluator.clone());
+
+        let result = calculater.execute("en".to_string(), "2 + 3
+2 ^ 10
+
+$10 * 2");
+        let outputs = result.lines.iter().map(|line| line.as_ref().map(|line| line.result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string()))).collect::<Vec<_>>();
+        assert_eq!(outputs, vec![Some(Ok("5".to_string())), Some(Err("Power is not allowed".to_string())), None, Some(Ok("$20,00".to_string()))]);
+        assert_eq!(evaluator.count.load(core::sync::atomic::Ordering::Relaxed), 3);
+    }
+
+    /* Writes money with its code in a tooltip, other results are printed by the default formatter */
+    pub struct HtmlFormatter;
+
+    impl crate::ResultFormatter for HtmlFormatter {
+        fn format(&self, config: &SmartCalcConfig, session: &crate::Session, result: Rc<SmartCalcAstType>, options: &crate::FormatOptions) -> String {
+            let output = crate::DefaultFormatter.format(config, session, result.clone(), options);
+            match result.deref() {
+                SmartCalcAstType::Item(item) => match item.as_token_type() {
+                    TokenType::Money(_, currency) => alloc::format!("<span title=\"{}\">{}</span>", currency.code, output),
+                    _ => output
+                },
+                _ => output
+            }
+        }
+    }
+
+    #[test]
+    fn custom_formatter() {
+        let mut calculater = SmartCalc::default();
+        calculater.set_formatter(Arc::new(HtmlFormatter));
+
+        let result = calculater.execute("en".to_string(), "$10 * 2
+2 + 3");
+        let outputs = result.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<Vec<_>>();
+        assert_eq!(outputs, vec!["<span title=\"USD\">$20,00</span>".to_string(), "5".to_string()]);
+
+        let mut session = crate::Session::default();
+        session.set_language("en".to_string());
+        let options = crate::FormatOptions { template: Some("= {result}".to_string()), ..crate::FormatOptions::default() };
+        assert_eq!(calculater.format_result(&session, result.lines[0].as_ref().unwrap().result.as_ref().unwrap().ast.clone(), &options), "= <span title=\"USD\">$20,00</span>");
+    }
+
+    #[test]
+    fn shared_config() {
+        assert!(Arc::ptr_eq(&SmartCalcConfig::shared(), &SmartCalcConfig::shared()));
+
+        let shared = SmartCalc::from_config(SmartCalcConfig::shared());
+        let mut custom = SmartCalc::default();
+        custom.set_decimal_seperator(".".to_string());
+        custom.set_thousand_separator(",".to_string());
+
+        let output = |calculater: &SmartCalc, data: &str| calculater.execute("en", data).lines[0].as_ref().unwrap().result.as_ref().unwrap().output.to_string();
+        assert_eq!(output(&shared, "$1234,5"), "$1.234,50");
+        assert_eq!(output(&custom, "$1234.5"), "$1,234.50");
+        assert_eq!(output(&shared, "12 march 2024"), "12 Mar 2024");
+        assert_eq!(SmartCalcConfig::shared().decimal_seperator, ",");
+    }
 }
@@ -0,0 +1,215 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::cell::RefCell;
+use core::ops::Deref;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use chrono::{DateTime, Duration, NaiveDate};
+use serde_derive::{Deserialize, Serialize};
+
+use crate::config::SmartCalcConfig;
+use crate::session::Session;
+use crate::syntax::primative::PrimativeParser;
+use crate::tokinizer::Tokinizer;
+use crate::types::{NumberType, SmartCalcAstType, TimeOffset, TokenType};
+use crate::variable::VariableInfo;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/* Calculated values in a plain form, currencies and dynamic types are kept by their names */
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub(crate) enum StoredValue {
+    Number { value: f64, number_type: NumberType },
+    Money { value: f64, currency: String },
+    Percent { value: f64 },
+    Time { timestamp: i64, offset: TimeOffset },
+    Date { date: String, offset: TimeOffset },
+    DateTime { timestamp: i64, offset: TimeOffset },
+    Duration { milliseconds: i64 },
+    DynamicType { value: f64, group: String, index: usize },
+    Bool { value: bool },
+    Text { value: String },
+    List { values: Vec<StoredValue> }
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredVariable {
+    name: String,
+    line: usize,
+    start: usize,
+    end: usize,
+    #[serde(default)]
+    type_lock: Option<String>,
+    value: Option<StoredValue>
+}
+
+#[derive(Serialize, Deserialize)]
+pub(crate) struct StoredSession {
+    language: String,
+    text: String,
+    variables: Vec<StoredVariable>,
+    results: Vec<Option<StoredValue>>,
+    #[serde(default)]
+    labels: Vec<Option<String>>
+}
+
+impl StoredValue {
+    fn from_ast(ast: &SmartCalcAstType) -> Option<StoredValue> {
+        let item = match ast {
+            SmartCalcAstType::Item(item) => item,
+            SmartCalcAstType::List(items) => return Some(StoredValue::List { values: items.iter().filter_map(|item| StoredValue::from_ast(item)).collect() }),
+            _ => return None
+        };
+
+        Some(match item.as_token_type() {
+            TokenType::Number(value, number_type) => StoredValue::Number { value, number_type },
+            TokenType::Money(value, currency) => StoredValue::Money { value, currency: currency.code.to_string() },
+            TokenType::Percent(value) => StoredValue::Percent { value },
+            TokenType::Time(time, offset) => StoredValue::Time { timestamp: time.and_utc().timestamp_millis(), offset },
+            TokenType::Date(date, offset) => StoredValue::Date { date: date.format(DATE_FORMAT).to_string(), offset },
+            TokenType::DateTime(date_time, offset) => StoredValue::DateTime { timestamp: date_time.and_utc().timestamp_millis(), offset },
+            TokenType::Duration(duration) => StoredValue::Duration { milliseconds: duration.num_milliseconds() },
+            TokenType::DynamicType(value, dynamic_type) => StoredValue::DynamicType { value, group: dynamic_type.group_name.to_string(), index: dynamic_type.index },
+            TokenType::Bool(value) => StoredValue::Bool { value },
+            TokenType::TextValue(value) => StoredValue::Text { value },
+            _ => return None
+        })
+    }
+
+    fn to_ast(&self, config: &SmartCalcConfig) -> Result<Rc<SmartCalcAstType>, String> {
+        let token = match self {
+            StoredValue::Number { value, number_type } => TokenType::Number(*value, *number_type),
+            StoredValue::Money { value, currency } => match config.get_currency(currency.to_lowercase()) {
+                Some(currency) => TokenType::Money(*value, currency),
+                None => return Err(format!("Currency not found. ({})", currency))
+            },
+            StoredValue::Percent { value } => TokenType::Percent(*value),
+            StoredValue::Time { timestamp, offset } => match DateTime::from_timestamp_millis(*timestamp) {
+                Some(time) => TokenType::Time(time.naive_utc(), offset.clone()),
+                None => return Err("Timestamp is not valid".to_string())
+            },
+            StoredValue::Date { date, offset } => match NaiveDate::parse_from_str(date, DATE_FORMAT) {
+                Ok(date) => TokenType::Date(date, offset.clone()),
+                Err(_) => return Err("Date information not valid".to_string())
+            },
+            StoredValue::DateTime { timestamp, offset } => match DateTime::from_timestamp_millis(*timestamp) {
+                Some(date_time) => TokenType::DateTime(date_time.naive_utc(), offset.clone()),
+                None => return Err("Timestamp is not valid".to_string())
+            },
+            StoredValue::Duration { milliseconds } => TokenType::Duration(Duration::milliseconds(*milliseconds)),
+            StoredValue::DynamicType { value, group, index } => match config.types.get(group).and_then(|items| items.get(index)) {
+                Some(dynamic_type) => TokenType::DynamicType(*value, dynamic_type.clone()),
+                None => return Err(format!("Dynamic type not found. ({})", group))
+            },
+            StoredValue::Bool { value } => TokenType::Bool(*value),
+            StoredValue::Text { value } => TokenType::TextValue(value.to_string()),
+            StoredValue::List { values } => return Ok(Rc::new(SmartCalcAstType::List(values.iter().map(|value| value.to_ast(config)).collect::<Result<Vec<_>, _>>()?)))
+        };
+
+        match PrimativeParser::token_to_item(&token) {
+            Some(item) => Ok(Rc::new(SmartCalcAstType::Item(item))),
+            None => Err("Value is not valid".to_string())
+        }
+    }
+}
+
+/* Variable tokens are rebuilt from the name, the same way an assignment line tokinizes it */
+fn variable_tokens(config: &SmartCalcConfig, language: &str, name: &str) -> Vec<Rc<TokenType>> {
+    let mut session = Session::new();
+    session.set_language(language.to_string());
+    session.set_text(format!("{} = 0", name));
+
+    let mut tokinizer = Tokinizer::new(config, &session);
+    tokinizer.tokinize();
+    tokinizer.tokens.iter().take_while(|token| !matches!(***token, TokenType::Operator('='))).cloned().collect()
+}
+
+impl StoredSession {
+    pub(crate) fn from_session(session: &Session) -> StoredSession {
+        StoredSession {
+            language: session.base_language().to_string(),
+            text: session.get_text(),
+            variables: session.variables.borrow().values().map(|variable| StoredVariable {
+                name: variable.name.to_string(),
+                line: variable.line,
+                start: variable.start,
+                end: variable.end,
+                type_lock: variable.type_lock.borrow().clone(),
+                value: StoredValue::from_ast(variable.data.borrow().deref())
+            }).collect(),
+            results: session.line_results.borrow().iter().map(|result| result.as_ref().and_then(|result| StoredValue::from_ast(result))).collect(),
+            labels: session.line_labels.borrow().clone()
+        }
+    }
+
+    pub(crate) fn to_session(&self, config: &SmartCalcConfig) -> Result<Session, String> {
+        let mut session = Session::new();
+        session.set_language(self.language.to_string());
+        session.set_text(self.text.to_string());
+
+        for variable in self.variables.iter() {
+            let data = match &variable.value {
+                Some(value) => value.to_ast(config)?,
+                None => Rc::new(SmartCalcAstType::None)
+            };
+
+            session.add_variable(Rc::new(VariableInfo {
+                tokens: variable_tokens(config, &self.language, &variable.name),
+                data: RefCell::new(data),
+                name: variable.name.to_string(),
+                line: variable.line,
+                start: variable.start,
+                end: variable.end,
+                type_lock: RefCell::new(variable.type_lock.clone())
+            }));
+        }
+
+        for result in self.results.iter() {
+            session.add_line_result(match result {
+                Some(value) => Some(value.to_ast(config)?),
+                None => None
+            });
+        }
+
+        for label in self.labels.iter() {
+            session.add_line_label(label.clone());
+        }
+
+        Ok(session)
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn storage_test() {
+    use crate::smartcalc::SmartCalc;
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("rent = 1000 usd\ndiscount = 15%\nstart = 1 jan 2024\nwork = 2 hours 30 minutes\nmeeting = 2024-03-10T09:30:00Z\nscores = [10, 20, 30]\n\nrent - discount".to_string());
+    let results = calc.execute_session(&session);
+    let outputs = results.lines.iter().map(|line| line.as_ref().map(|line| line.result.as_ref().unwrap().output.to_string())).collect::<Vec<_>>();
+
+    let data = session.serialize().unwrap();
+    let mut restored = calc.load_session(&data).unwrap();
+
+    assert_eq!(restored.get_text(), session.get_text());
+    assert_eq!(restored.variables().map(|(name, _, line, span)| (name, line, span)).collect::<Vec<_>>(), session.variables().map(|(name, _, line, span)| (name, line, span)).collect::<Vec<_>>());
+
+    let restored_outputs = restored.line_results.borrow().iter().map(|result| result.as_ref().map(|result| calc.format_result(&restored, result.clone(), &crate::FormatOptions::default()))).collect::<Vec<_>>();
+    assert_eq!(restored_outputs, outputs);
+
+    restored.set_text("rent * 2\nstart + 1 month\nwork + 30 minutes\ndiscount of rent\nmeeting\nscores avg".to_string());
+    let results = calc.execute_session(&restored);
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<Vec<_>>();
+    assert_eq!(outputs, ["$2.000,00", "1 Feb 2024", "3 hours", "$150,00", "10 Mar 2024 09:30:00 UTC", "20"]);
+
+    assert!(calc.load_session("{}").is_err());
+}
@@ -0,0 +1,120 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::pin::Pin;
+use core::task::{Context, Poll, Waker};
+use alloc::string::String;
+use alloc::sync::Arc;
+use std::sync::Mutex;
+use std::sync::mpsc::{sync_channel, Receiver, TryRecvError};
+use futures_core::Stream;
+
+use crate::result::LineResult;
+use crate::session::{CancellationToken, Session};
+use crate::smartcalc::SmartCalc;
+
+/// Lines of a text calculated one by one, created by `SmartCalc::execute_async`.
+///
+/// Lines are calculated on a worker thread of the stream, so big documents do not block the tasks of the runtime. The stream and
+/// its lines are `Send`, it can be used in a spawned task. The calculation is cancelled when the stream is dropped.
+pub struct ExecuteStream {
+    lines: Receiver<(usize, Option<LineResult>)>,
+    waker: Arc<Mutex<Option<Waker>>>,
+    cancellation_token: CancellationToken
+}
+
+impl ExecuteStream {
+    pub(crate) fn new(smartcalc: SmartCalc, language: String, text: String) -> Self {
+        /* Worker calculates at most one line before the stream reads it */
+        let (sender, lines) = sync_channel(1);
+        let waker: Arc<Mutex<Option<Waker>>> = Arc::default();
+        let cancellation_token = CancellationToken::new();
+
+        let worker_waker = waker.clone();
+        let worker_token = cancellation_token.clone();
+        std::thread::spawn(move || {
+            /* Session and the reference counted values of the lines are only used on this thread */
+            let mut session = Session::new();
+            session.set_text(text);
+            session.set_language(language);
+            session.set_cancellation_token(worker_token.clone());
+
+            let wake = || if let Some(waker) = worker_waker.lock().ok().and_then(|mut waker| waker.take()) {
+                waker.wake();
+            };
+
+            if session.has_value() {
+                loop {
+                    let line = smartcalc.execute_current_line(&session);
+                    if worker_token.is_cancelled() || sender.send((session.current_line_index(), line.as_ref().map(LineResult::from))).is_err() {
+                        break;
+                    }
+
+                    wake();
+                    if session.next_line().is_none() {
+                        break;
+                    }
+                }
+            }
+
+            drop(sender);
+            wake();
+        });
+
+        ExecuteStream { lines, waker, cancellation_token }
+    }
+}
+
+impl Stream for ExecuteStream {
+    type Item = (usize, Option<LineResult>);
+
+    fn poll_next(self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        /* Waker is saved before the channel is read, so a line sent after the read wakes the task */
+        if let Ok(mut waker) = self.waker.lock() {
+            *waker = Some(context.waker().clone());
+        }
+
+        match self.lines.try_recv() {
+            Ok(line) => Poll::Ready(Some(line)),
+            Err(TryRecvError::Empty) => Poll::Pending,
+            Err(TryRecvError::Disconnected) => Poll::Ready(None)
+        }
+    }
+}
+
+impl Drop for ExecuteStream {
+    fn drop(&mut self) {
+        self.cancellation_token.cancel();
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn execute_stream_test() {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    fn assert_send<T: Send + 'static>(_: &T) {}
+    let calc = SmartCalc::default();
+    let mut stream = calc.execute_async("en", "a = 2\n\na * 3");
+    assert_send(&stream);
+
+    /* Stream is polled on an other thread, like a task moved between the threads of a runtime */
+    let lines = std::thread::spawn(move || {
+        let mut context = Context::from_waker(Waker::noop());
+        let mut lines = Vec::new();
+        loop {
+            match Pin::new(&mut stream).poll_next(&mut context) {
+                Poll::Ready(Some((index, line))) => lines.push((index, line.map(|line| line.formatted))),
+                Poll::Ready(None) => break,
+                Poll::Pending => std::thread::yield_now()
+            };
+        }
+        lines
+    }).join().unwrap();
+
+    assert_eq!(lines, [(0, Some("2".to_string())), (1, None), (2, Some("6".to_string()))]);
+}
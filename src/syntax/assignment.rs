@@ -9,13 +9,57 @@ use alloc::string::String;
 use crate::types::*;
 use crate::syntax::{SyntaxParser, SyntaxParserTrait};
 use crate::variable::VariableInfo;
+use crate::constants::ConstantType;
 use alloc::rc::Rc;
 use crate::syntax::binary::AddSubtractParser;
 use core::ops::Deref;
 use crate::alloc::string::ToString;
 
+const TYPE_ANNOTATIONS: [(&str, &str); 7] = [
+    ("number_annotation_group", "NUMBER"),
+    ("money_annotation_group", "MONEY"),
+    ("percent_annotation_group", "PERCENT"),
+    ("duration_annotation_group", "DURATION"),
+    ("date_annotation_group", "DATE"),
+    ("date_time_annotation_group", "DATE_TIME"),
+    ("time_annotation_group", "TIME")
+];
+
 pub struct AssignmentParser;
 
+impl AssignmentParser {
+    /* Variable name as written in the line, with its byte span in the line and the optional type annotation */
+    fn variable_name(parser: &SyntaxParser) -> Option<(String, Option<String>, usize, usize)> {
+        let tokinizer = parser.tokinizer;
+        let assignment = tokinizer.token_infos.iter().find(|token| matches!(token.token_type.borrow().deref(), Some(TokenType::Operator('='))))?;
+        let text = tokinizer.data.get(..assignment.start)?.to_string();
+        let (name, annotation) = match text.split_once(':') {
+            Some((name, annotation)) => (name.to_string(), Some(annotation.trim().to_lowercase())),
+            None => (text, None)
+        };
+
+        let start = tokinizer.line_offset + tokinizer.original_position(name.len() - name.trim_start().len());
+        let end = tokinizer.line_offset + tokinizer.original_position(name.trim_end().len());
+        Some((name.trim().to_string(), annotation, start, end))
+    }
+
+    /* Annotation words are language specific, they are mapped to the calculated item type */
+    fn annotation_type(parser: &SyntaxParser, annotation: &str) -> Option<&'static str> {
+        let tokinizer = parser.tokinizer;
+        let word_groups = tokinizer.config.word_group.get(&tokinizer.language)?;
+        TYPE_ANNOTATIONS.iter()
+            .find(|(group, _)| word_groups.get(*group).map_or(false, |words| words.iter().any(|word| word == annotation)))
+            .map(|(_, type_name)| *type_name)
+    }
+
+    /* Math constants and previous result references are resolved by the tokinizer, so an assignment would silently shadow them */
+    fn is_constant_assignment(parser: &SyntaxParser, name: &str) -> bool {
+        let tokinizer = parser.tokinizer;
+        matches!(tokinizer.config.constant_pair.get(&tokinizer.language).and_then(|constants| constants.get(&name.to_lowercase())),
+            Some(ConstantType::Pi) | Some(ConstantType::E) | Some(ConstantType::Tau) | Some(ConstantType::PreviousResult))
+    }
+}
+
 impl SyntaxParserTrait for AssignmentParser {
     fn parse(parser: &mut SyntaxParser) -> AstResult {
         let index_backup      = parser.get_index();
@@ -29,8 +73,21 @@ impl SyntaxParserTrait for AssignmentParser {
         }
 
         if assignment_index.is_some() {
+            let (name, annotation, name_start, name_end) = AssignmentParser::variable_name(parser).unwrap_or_default();
+            if AssignmentParser::is_constant_assignment(parser, &name) {
+                return Err(("Constants cannot be assigned", 0, 0));
+            }
+
+            let type_lock = match annotation {
+                Some(annotation) => match AssignmentParser::annotation_type(parser, &annotation) {
+                    Some(type_name) => Some(type_name.to_string()),
+                    None => return Err(("Unknown variable type", 0, 0))
+                },
+                None => None
+            };
+
             let start = parser.get_index();
-            let end;
+            let mut end = None;
             let mut variable_name = String::new();
             variable_name.push_str(&parser.peek_token().unwrap().to_string().to_lowercase()[..]);
             
@@ -41,12 +98,18 @@ impl SyntaxParserTrait for AssignmentParser {
                             parser.consume_token();
                             break;
                         }
+
+                        /* Type annotation is not part of the variable name */
+                        if *operator == ':' && end.is_none() {
+                            end = Some(parser.get_index());
+                        }
                     }
+                    _ if end.is_some() => (),
                     _ => variable_name.push_str(&token.to_string().to_lowercase()[..])
                 };
             }
 
-            end = parser.get_index() - 1;
+            let end = end.unwrap_or(parser.get_index() - 1);
 
             let expression = AddSubtractParser::parse(parser);
             match expression {
@@ -62,7 +125,12 @@ impl SyntaxParserTrait for AssignmentParser {
                 false => {
                     let variable = Rc::new(VariableInfo {
                         tokens: parser.tokinizer.tokens[start..end].to_vec(),
-                        data: RefCell::new(Rc::new(SmartCalcAstType::None))
+                        data: RefCell::new(Rc::new(SmartCalcAstType::None)),
+                        name,
+                        line: parser.session.current_line_index(),
+                        start: name_start,
+                        end: name_end,
+                        type_lock: RefCell::new(None)
                     });
         
                     parser.session.add_variable(variable.clone());
@@ -70,6 +138,11 @@ impl SyntaxParserTrait for AssignmentParser {
                 }
             };
             
+            /* Annotated assignment sets the type, the following assignments have to keep it */
+            if type_lock.is_some() {
+                *variable.type_lock.borrow_mut() = type_lock;
+            }
+
             let assignment_ast = SmartCalcAstType::Assignment {
                 variable,
                 expression: Rc::new(expression.unwrap())
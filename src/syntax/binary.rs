@@ -7,10 +7,12 @@
 use crate::types::*;
 use crate::syntax::{SyntaxParser, SyntaxParserTrait};
 use crate::syntax::unary::UnaryParser;
+use crate::syntax::primative::PrimativeParser;
 use alloc::rc::Rc;
 
 pub struct ModuloParser;
 pub struct MultiplyDivideParser;
+pub struct ExponentParser;
 pub struct AddSubtractParser;
 
 impl SyntaxParserTrait for ModuloParser {
@@ -25,6 +27,38 @@ impl SyntaxParserTrait for MultiplyDivideParser {
     }
 }
 
+/* Exponent is right associative, 2 ^ 3 ^ 2 is 2 ^ (3 ^ 2) */
+impl SyntaxParserTrait for ExponentParser {
+    fn parse(parser: &mut SyntaxParser) -> AstResult {
+        let left_expr = PrimativeParser::parse(parser)?;
+
+        if let SmartCalcAstType::None = left_expr {
+            return Ok(left_expr)
+        }
+
+        ExponentParser::parse_exponent(parser, left_expr)
+    }
+}
+
+impl ExponentParser {
+    /* Exponent can have its own sign, 2 ^ -3 */
+    pub fn parse_exponent(parser: &mut SyntaxParser, left_expr: SmartCalcAstType) -> AstResult {
+        let index_backup = parser.get_index();
+        if let Some(operator) = parser.match_operator(&['^']) {
+            match UnaryParser::parse(parser)? {
+                SmartCalcAstType::None => parser.set_index(index_backup),
+                right_expr => return Ok(SmartCalcAstType::Binary {
+                    left: Rc::new(left_expr),
+                    operator,
+                    right: Rc::new(right_expr)
+                })
+            };
+        }
+
+        Ok(left_expr)
+    }
+}
+
 impl SyntaxParserTrait for AddSubtractParser {
     fn parse(parser: &mut SyntaxParser) -> AstResult {
         parse_binary::<ModuloParser>(parser, &['+', '-'])
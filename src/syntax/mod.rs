@@ -10,6 +10,7 @@ pub mod util;
 pub mod binary;
 pub mod assignment;
 pub mod statement;
+pub mod unset;
 
 use core::cell::Cell;
 
@@ -20,6 +21,7 @@ use crate::types::*;
 use alloc::rc::Rc;
 use crate::session::Session;
 use crate::syntax::assignment::AssignmentParser;
+use crate::syntax::unset::UnsetParser;
 use crate::syntax::binary::AddSubtractParser;
 use core::ops::Deref;
 
@@ -45,7 +47,7 @@ impl<'a> SyntaxParser<'a> {
     }
 
     pub fn parse(&mut self) -> AstResult {
-        let ast = map_parser(self, &[AssignmentParser::parse, AddSubtractParser::parse])?;
+        let ast = map_parser(self, &[UnsetParser::parse, AssignmentParser::parse, AddSubtractParser::parse])?;
         Ok(ast)
     }
 
@@ -6,6 +6,8 @@
 
 
 use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
 
 use crate::compiler::date::DateItem;
 use crate::compiler::date_time::DateTimeItem;
@@ -15,6 +17,10 @@ use crate::compiler::dynamic_type::DynamicTypeItem;
 use crate::compiler::number::NumberItem;
 use crate::compiler::percent::PercentItem;
 use crate::compiler::time::TimeItem;
+use crate::compiler::bool::BoolItem;
+use crate::compiler::text::TextItem;
+use crate::compiler::DataItem;
+use crate::compiler::function::is_function;
 use crate::types::*;
 use crate::syntax::util::*;
 use crate::syntax::{SyntaxParser, SyntaxParserTrait};
@@ -38,19 +44,15 @@ impl PrimativeParser {
                 parser.consume_token();
                 return Ok(SmartCalcAstType::None);
             },
-            TokenType::DynamicType(number, dynamic_type)     => Ok(SmartCalcAstType::Item(Rc::new(DynamicTypeItem(*number, dynamic_type.clone())))),
-            TokenType::Money(price, currency)     => Ok(SmartCalcAstType::Item(Rc::new(MoneyItem(*price, currency.clone())))),
-            TokenType::Number(double, number_type)     => Ok(SmartCalcAstType::Item(Rc::new(NumberItem(*double, *number_type)))),
             TokenType::Field(field_type)  => Ok(SmartCalcAstType::Field(field_type.clone())),
-            TokenType::Percent(percent)   => Ok(SmartCalcAstType::Item(Rc::new(PercentItem(*percent)))),
-            TokenType::Time(time, tz)         => Ok(SmartCalcAstType::Item(Rc::new(TimeItem(*time, tz.clone())))),
-            TokenType::Date(date, tz)         => Ok(SmartCalcAstType::Item(Rc::new(DateItem(*date, tz.clone())))),
-            TokenType::DateTime(date_time, tz)         => Ok(SmartCalcAstType::Item(Rc::new(DateTimeItem(*date_time, tz.clone())))),
-            TokenType::Duration(duration)         => Ok(SmartCalcAstType::Item(Rc::new(DurationItem(*duration)))),
             TokenType::Variable(variable) => Ok(SmartCalcAstType::Variable(variable.clone())),
-            _ => {
-                parser.consume_token();
-                return Err(("No more token", 0, 0));
+            TokenType::Range(start, end, aggregate) => Ok(SmartCalcAstType::Range { start: *start, end: *end, aggregate: *aggregate }),
+            token => match PrimativeParser::token_to_item(token) {
+                Some(item) => Ok(SmartCalcAstType::Item(item)),
+                None => {
+                    parser.consume_token();
+                    return Err(("No more token", 0, 0));
+                }
             }
         };
 
@@ -59,6 +61,10 @@ impl PrimativeParser {
                 parser.set_index(index_backup);
                 Ok(SmartCalcAstType::None)
             },
+            Ok(SmartCalcAstType::Variable(variable)) => {
+                parser.consume_token();
+                Ok(PrimativeParser::parse_aggregate(parser, SmartCalcAstType::Variable(variable)))
+            },
             Ok(ast) => {
                 parser.consume_token();
                 Ok(ast)
@@ -67,6 +73,110 @@ impl PrimativeParser {
         }
     }
 
+    pub(crate) fn token_to_item(token: &TokenType) -> Option<Rc<dyn DataItem>> {
+        Some(match token {
+            TokenType::DynamicType(number, dynamic_type) => Rc::new(DynamicTypeItem(*number, dynamic_type.clone())),
+            TokenType::Money(price, currency)            => Rc::new(MoneyItem(*price, currency.clone())),
+            TokenType::Number(double, number_type)       => Rc::new(NumberItem(*double, *number_type)),
+            TokenType::Percent(percent)                  => Rc::new(PercentItem(*percent)),
+            TokenType::Time(time, tz)                    => Rc::new(TimeItem(*time, tz.clone())),
+            TokenType::Date(date, tz)                    => Rc::new(DateItem(*date, tz.clone())),
+            TokenType::DateTime(date_time, tz)           => Rc::new(DateTimeItem(*date_time, tz.clone())),
+            TokenType::Duration(duration)                => Rc::new(DurationItem(*duration)),
+            TokenType::Bool(value)                       => Rc::new(BoolItem(*value)),
+            TokenType::TextValue(text)                   => Rc::new(TextItem(text.clone())),
+            _ => return None
+        })
+    }
+
+    /* name(argument, argument), arguments are full expressions */
+    pub fn parse_function_call(parser: &mut SyntaxParser) -> AstResult {
+        let index_backup = parser.get_index();
+        let name = match parser.peek_token() {
+            Ok(token) => match token.deref() {
+                TokenType::Text(name) if is_function(name) => name.to_lowercase(),
+                _ => return Ok(SmartCalcAstType::None)
+            },
+            _ => return Ok(SmartCalcAstType::None)
+        };
+
+        parser.consume_token();
+        if parser.match_operator(&['(']).is_none() {
+            parser.set_index(index_backup);
+            return Ok(SmartCalcAstType::None);
+        }
+
+        let mut arguments = Vec::new();
+        if parser.match_operator(&[')']).is_none() {
+            loop {
+                let ast = AddSubtractParser::parse(parser);
+                if is_ast_empty(&ast) {
+                    parser.set_index(index_backup);
+                    return err_or_message(&ast, "Invalid expression");
+                }
+
+                arguments.push(Rc::new(ast.unwrap()));
+
+                match parser.match_operator(&[',', ')']) {
+                    Some(',') => (),
+                    Some(_) => break,
+                    None => {
+                        parser.set_index(index_backup);
+                        return Err(("Parentheses not closed", 0, 0));
+                    }
+                };
+            }
+        }
+
+        Ok(SmartCalcAstType::Call { name, arguments })
+    }
+
+    /* Function name after a list or a variable aggregates it, like [10, 25, 40] sum */
+    fn parse_aggregate(parser: &mut SyntaxParser, ast: SmartCalcAstType) -> SmartCalcAstType {
+        let name = match parser.peek_token() {
+            Ok(token) => match token.deref() {
+                TokenType::Text(name) if is_function(name) => name.to_lowercase(),
+                _ => return ast
+            },
+            _ => return ast
+        };
+
+        parser.consume_token();
+        SmartCalcAstType::Call { name, arguments: vec![Rc::new(ast)] }
+    }
+
+    /* [item, item], items are full expressions */
+    pub fn parse_list(parser: &mut SyntaxParser) -> AstResult {
+        let index_backup = parser.get_index();
+        if parser.match_operator(&['[']).is_none() {
+            return Ok(SmartCalcAstType::None);
+        }
+
+        let mut items = Vec::new();
+        if parser.match_operator(&[']']).is_none() {
+            loop {
+                let ast = AddSubtractParser::parse(parser);
+                if is_ast_empty(&ast) {
+                    parser.set_index(index_backup);
+                    return err_or_message(&ast, "Invalid expression");
+                }
+
+                items.push(Rc::new(ast.unwrap()));
+
+                match parser.match_operator(&[',', ']']) {
+                    Some(',') => (),
+                    Some(_) => break,
+                    None => {
+                        parser.set_index(index_backup);
+                        return Err(("List not closed", 0, 0));
+                    }
+                };
+            }
+        }
+
+        Ok(PrimativeParser::parse_aggregate(parser, SmartCalcAstType::List(items)))
+    }
+
     pub fn parse_parenthesis(parser: &mut SyntaxParser) -> AstResult {
         let index_backup = parser.get_index();
         if parser.match_operator(&['(']).is_some() {
@@ -92,6 +202,6 @@ impl PrimativeParser {
 
 impl SyntaxParserTrait for PrimativeParser {
     fn parse(parser: &mut SyntaxParser) -> AstResult {
-        map_parser(parser, &[Self::parse_parenthesis, Self::parse_basic_primatives])
+        map_parser(parser, &[Self::parse_function_call, Self::parse_parenthesis, Self::parse_list, Self::parse_basic_primatives])
     }
 }
\ No newline at end of file
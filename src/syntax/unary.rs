@@ -4,56 +4,69 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use crate::compiler::money::MoneyItem;
 use crate::compiler::number::NumberItem;
-use crate::compiler::percent::PercentItem;
 use crate::types::*;
 use crate::syntax::{SyntaxParser, SyntaxParserTrait};
-use crate::syntax::util::map_parser;
-use crate::syntax::primative::PrimativeParser;
+use crate::syntax::binary::ExponentParser;
 use core::ops::Deref;
 use alloc::rc::Rc;
 
 pub struct UnaryParser;
 
+/* Unary binds looser than exponent, -2 ^ 2 is -(2 ^ 2) */
 impl SyntaxParserTrait for UnaryParser {
     fn parse(parser: &mut SyntaxParser) -> AstResult {
-        let ast = map_parser(parser, &[Self::parse_prefix_unary, PrimativeParser::parse])?;
-        
         let index_backup = parser.get_index();
-        parser.set_index(index_backup);
-        Ok(ast)
+
+        if let Some(operator) = parser.match_operator(&['-', '+']) {
+            if Self::is_signed(parser) {
+                parser.set_index(index_backup);
+                return Err(("Unary operator can not be repeated", 0, 0));
+            }
+
+            return match ExponentParser::parse(parser)? {
+                SmartCalcAstType::None => {
+                    parser.set_index(index_backup);
+                    Err(("Unary works with number", 0, 0))
+                },
+                ast => Ok(SmartCalcAstType::PrefixUnary(operator, Rc::new(ast)))
+            };
+        }
+
+        Self::parse_signed_base(parser)
     }
 }
 
 impl UnaryParser {
-    fn parse_prefix_unary(parser: &mut SyntaxParser) -> AstResult {
+    /* Next token starts with a sign, either an operator or a number that already has it */
+    fn is_signed(parser: &SyntaxParser) -> bool {
+        match parser.peek_token() {
+            Ok(token) => match token.deref() {
+                TokenType::Operator('-' | '+') => true,
+                TokenType::Number(number, _) => number.is_sign_negative(),
+                _ => false
+            },
+            Err(_) => false
+        }
+    }
+
+    /* Tokinizer keeps the sign inside the number, it is taken back out when the number is an exponent base */
+    fn parse_signed_base(parser: &mut SyntaxParser) -> AstResult {
         let index_backup = parser.get_index();
 
-        if let Some(operator) = parser.match_operator(&['-', '+']) {
-            match parser.peek_token() {
-                Ok(token) => {
-                    let opt = match operator {
-                        '+' => 1_f64,
-                        '-' => -1_f64,
-                        _   => 1_f64
-                    };
-
-                    match token.deref() {
-                        TokenType::Number(double, number_type)         => return Ok(SmartCalcAstType::Item(Rc::new(NumberItem(double * opt, *number_type)))),
-                        TokenType::Variable(variable)     => return Ok(SmartCalcAstType::PrefixUnary(operator, Rc::new(SmartCalcAstType::Variable(variable.clone())))),
-                        TokenType::Percent(percent)       => return Ok(SmartCalcAstType::PrefixUnary(operator, Rc::new(SmartCalcAstType::Item(Rc::new(PercentItem(*percent)))))),
-                        TokenType::Money(money, currency) => return Ok(SmartCalcAstType::PrefixUnary(operator, Rc::new(SmartCalcAstType::PrefixUnary(operator, Rc::new(SmartCalcAstType::Item(Rc::new(MoneyItem(*money, currency.clone())))))))),
-                        _ => {
-                            parser.set_index(index_backup);
-                            return Err(("Unary works with number", 0, 0));
-                        }
-                    };
-                },
-                 _=> return Ok(SmartCalcAstType::None)
+        if let Ok(token) = parser.peek_token() {
+            if let TokenType::Number(number, number_type) = token.deref() {
+                if number.is_sign_negative() {
+                    parser.consume_token();
+                    if parser.check_operator('^') {
+                        let base = SmartCalcAstType::Item(Rc::new(NumberItem(-number, *number_type)));
+                        return Ok(SmartCalcAstType::PrefixUnary('-', Rc::new(ExponentParser::parse_exponent(parser, base)?)));
+                    }
+                    parser.set_index(index_backup);
+                }
             }
         }
 
-        Ok(SmartCalcAstType::None)
+        ExponentParser::parse(parser)
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,37 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use alloc::string::ToString;
+use crate::types::*;
+use crate::constants::ConstantType;
+use crate::syntax::{SyntaxParser, SyntaxParserTrait};
+use core::ops::Deref;
+
+pub struct UnsetParser;
+
+impl SyntaxParserTrait for UnsetParser {
+    fn parse(parser: &mut SyntaxParser) -> AstResult {
+        /* Text tokens are cleaned up before parsing, so the keyword is read from the line */
+        let keyword = parser.tokinizer.data.split_whitespace().next().unwrap_or_default().to_lowercase();
+        let is_unset = matches!(parser.tokinizer.config.constant_pair.get(&parser.tokinizer.language).and_then(|constants| constants.get(&keyword)), Some(ConstantType::Unset));
+
+        if !is_unset {
+            return Ok(SmartCalcAstType::None);
+        }
+
+        let variable = match parser.tokinizer.tokens.as_slice() {
+            [token] => match token.deref() {
+                TokenType::Variable(variable) => variable.clone(),
+                _ => return Err(("Variable not found", 0, 0))
+            },
+            _ => return Err(("Variable not found", 0, 0))
+        };
+
+        parser.consume_token();
+        parser.session.remove_variable(&variable.to_string());
+        Ok(SmartCalcAstType::Symbol(variable.to_string()))
+    }
+}
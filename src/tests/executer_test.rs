@@ -408,7 +408,7 @@ fn execute_21() {
     assert_eq!(results.lines.len(), 1);
     match results.lines[0].as_ref().unwrap().result.as_ref().unwrap().ast.deref() {
         SmartCalcAstType::Item(item) => {
-            assert_eq!(item.as_any().downcast_ref::<DateItem>().unwrap().get_date(), NaiveDate::from_ymd(2018, 11, 28));
+            assert_eq!(item.as_any().downcast_ref::<DateItem>().unwrap().get_date(), NaiveDate::from_ymd(2017, 11, 28));
         },
         _ => assert!(false)
     };
@@ -423,7 +423,7 @@ fn execute_22() {
     assert_eq!(results.lines.len(), 1);
     match results.lines[0].as_ref().unwrap().result.as_ref().unwrap().ast.deref() {
         SmartCalcAstType::Item(item) => {
-            assert_eq!(item.as_any().downcast_ref::<DateItem>().unwrap().get_date(), NaiveDate::from_ymd(2018, 11, 18));
+            assert_eq!(item.as_any().downcast_ref::<DateItem>().unwrap().get_date(), NaiveDate::from_ymd(2017, 11, 18));
         },
         _ => assert!(false)
     };
@@ -438,7 +438,7 @@ fn execute_23() {
     assert_eq!(results.lines.len(), 1);
     match results.lines[0].as_ref().unwrap().result.as_ref().unwrap().ast.deref() {
         SmartCalcAstType::Item(item) => {
-            assert_eq!(item.as_any().downcast_ref::<DateItem>().unwrap().get_date(), NaiveDate::from_ymd(2018, 10, 25));
+            assert_eq!(item.as_any().downcast_ref::<DateItem>().unwrap().get_date(), NaiveDate::from_ymd(2017, 10, 25));
         },
         _ => assert!(false)
     };
@@ -751,3 +751,1173 @@ fn execute_session() {
     session.set_language("en".to_string());
     evaluate_line!(calc with session, r"foo + bar" => Err);
 }
+
+#[test]
+fn fiscal_year_start_month() {
+    let mut calc = SmartCalc::default();
+    evaluate_line!(calc, r"fiscal year 2024" => r"1 Jan 2024");
+    evaluate_line!(calc, r"end of fiscal year 2024" => r"31 Dec 2024");
+    evaluate_line!(calc, r"fiscal year 2024 for start month april" => r"1 Apr 2023");
+
+    assert!(calc.set_fiscal_year_start_month(13).is_err());
+    assert!(calc.set_fiscal_year_start_month(10).is_ok());
+    evaluate_line!(calc, r"fiscal year 2024" => r"1 Oct 2023");
+    evaluate_line!(calc, r"end of fiscal year 2024" => r"30 Sep 2024");
+    evaluate_line!(calc, r"start of fiscal year 2024 for start month july" => r"1 Jul 2023");
+}
+
+#[test]
+fn leap_year_turkish() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("tr".to_string(), "2024 artık yıl mı\n2023 artık yıl mı\n2019 ile 2025 arası artık yıllar".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.clone()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["doğru", "yanlış", "2020, 2024"]);
+}
+
+#[test]
+fn duration_configuration() {
+    use crate::config::{DurationStyle, DurationGranularity};
+    use crate::constants::DurationFormatType;
+    let mut calc = SmartCalc::default();
+    calc.set_decimal_seperator(".".to_string());
+    calc.set_thousand_separator(",".to_string());
+    evaluate_line!(calc, r"1 hour 31 minutes 30 seconds" => r"1 hour 31 minutes 30 seconds");
+
+    calc.set_duration_configuration(DurationStyle::Short, DurationGranularity::Largest(2));
+    evaluate_line!(calc, r"1 hour 31 minutes 30 seconds" => r"1h 31m");
+
+    calc.set_duration_configuration(DurationStyle::Long, DurationGranularity::Decimal(DurationFormatType::Minute));
+    evaluate_line!(calc, r"1 hour 31 minutes 30 seconds" => r"91.5 minutes");
+}
+
+#[test]
+fn negative_duration() {
+    use crate::config::{DurationStyle, DurationGranularity, NegativeDurationStyle};
+    let mut calc = SmartCalc::default();
+    calc.set_decimal_seperator(".".to_string());
+    calc.set_thousand_separator(",".to_string());
+    evaluate_line!(calc, r"3 days - 5 days" => r"-2 days");
+    evaluate_line!(calc, r"1 hour - 3 hours 30 minutes" => r"-2 hours 30 minutes");
+    evaluate_line!(calc, r"3 days + -5 days" => r"-2 days");
+    evaluate_line!(calc, r"11:00 - 2 hours 30 minutes" => r"08:30:00 UTC");
+    evaluate_line!(calc, r"11:00 + (1 hour - 3 hours)" => r"09:00:00 UTC");
+    evaluate_line!(calc, r"11:00 - (1 hour - 3 hours)" => r"13:00:00 UTC");
+    evaluate_line!(calc, r"1/1/2024 + (3 days - 5 days)" => r"30 Dec 2023");
+
+    calc.set_negative_duration_style(NegativeDurationStyle::Relative);
+    evaluate_line!(calc, r"3 days - 5 days" => r"2 days ago");
+
+    calc.set_duration_configuration(DurationStyle::Clock, DurationGranularity::Full);
+    evaluate_line!(calc, r"1 hour - 3 hours 30 minutes" => r"-2:30:00");
+}
+
+#[test]
+fn time_provider() {
+    use chrono::{TimeZone, Utc};
+    let mut calc = SmartCalc::default();
+    calc.set_time_provider(|| Utc.with_ymd_and_hms(2024, 1, 31, 22, 15, 0).unwrap());
+    evaluate_line!(calc, r"today" => r"31 January");
+    evaluate_line!(calc, r"tomorrow" => r"1 February");
+    evaluate_line!(calc, r"yesterday" => r"30 January");
+    evaluate_line!(calc, r"now" => r"22:15:00 UTC");
+    evaluate_line!(calc, r"start of next month" => r"1 February");
+    evaluate_line!(calc, r"12 march" => r"12 March");
+    evaluate_line!(calc, r"1 jan 2023" => r"1 Jan 2023");
+    evaluate_line!(calc, r"3 weeks later" => r"21 February");
+    evaluate_line!(calc, r"2 days ago" => r"29 January");
+    evaluate_line!(calc, r"2 hours later" => r"1 February 00:15:00 UTC");
+    evaluate_line!(calc, r"days until 14 feb" => r"14");
+
+    calc.set_time_provider(|| Utc.with_ymd_and_hms(2023, 6, 1, 0, 0, 0).unwrap());
+    evaluate_line!(calc, r"today" => r"1 June");
+    evaluate_line!(calc, r"1 jan 2023" => r"1 January");
+}
+
+#[test]
+fn date_format() {
+    use chrono::{TimeZone, Utc};
+    let mut calc = SmartCalc::default();
+    calc.set_time_provider(|| Utc.with_ymd_and_hms(2024, 1, 31, 22, 15, 0).unwrap());
+    assert!(calc.set_date_format("en", "full_date", "%d.%m.%Y"));
+    assert!(calc.set_date_format("en", "current_year", "%B %e"));
+    assert!(calc.set_date_format("en", "current_year_with_time", "%d/%m %H:%M"));
+    assert!(!calc.set_date_format("en", "short_date", "%d.%m.%Y"));
+    assert!(!calc.set_date_format("xx", "full_date", "%d.%m.%Y"));
+
+    evaluate_line!(calc, r"1 jan 2023" => r"01.01.2023");
+    evaluate_line!(calc, r"tomorrow" => r"February 1");
+    evaluate_line!(calc, r"2024-01-15 13:45" => r"15/01 13:45");
+
+    /* Other languages keep their formats */
+    let results = calc.execute("tr", "1 ocak 2023");
+    assert_eq!(results.lines[0].as_ref().unwrap().result.as_ref().unwrap().output, "1 Oca 2023");
+}
+
+#[test]
+fn relative_dates() {
+    use chrono::{TimeZone, Utc};
+    let mut calc = SmartCalc::default();
+    calc.set_time_provider(|| Utc.with_ymd_and_hms(2024, 1, 31, 22, 15, 0).unwrap());
+    calc.set_relative_dates(Some(30));
+    evaluate_line!(calc, r"today" => r"today");
+    evaluate_line!(calc, r"tomorrow" => r"tomorrow");
+    evaluate_line!(calc, r"yesterday" => r"yesterday");
+    evaluate_line!(calc, r"today + 3 days" => r"in 3 days");
+    evaluate_line!(calc, r"today - 1 week" => r"7 days ago");
+    evaluate_line!(calc, r"today - 2 weeks" => r"2 weeks ago");
+    evaluate_line!(calc, r"12 march" => r"12 March");
+
+    let results = calc.execute("tr", "yarın\nbugün + 3 gün");
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["yarın", "3 gün sonra"]);
+
+    calc.set_relative_dates(None);
+    evaluate_line!(calc, r"tomorrow" => r"1 February");
+}
+
+#[test]
+fn turkish_rules() {
+    use chrono::{TimeZone, Utc};
+    let mut calc = SmartCalc::default();
+    calc.set_time_provider(|| Utc.with_ymd_and_hms(2024, 1, 31, 22, 15, 0).unwrap());
+
+    let outputs = |data: &str| calc.execute("tr", data).lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+
+    /* Percent phrases */
+    assert_eq!(outputs("200 sayısının %10\n$200 tutarının %10\n40 üzerine %6\n100 %20 indirimli\n%20 indirimle 100"), ["20", "$20,00", "42,40", "80", "80"]);
+    assert_eq!(outputs("50 sayısı 200 sayısının yüzde kaçı\n20 hangi sayının %10"), ["%25", "200"]);
+
+    /* Durations and date words */
+    assert_eq!(outputs("3 hafta sonra\n2 gün önce\n90 dk\n14 şubat tarihine kaç gün kaldı"), ["21 Şubat", "29 Ocak", "1 saat 30 dakika", "14"]);
+    assert_eq!(outputs("şubat 2024 son günü\nmart başı\ngelecek ay sonu\ngeçen yıl başı\nq2 2024 sonu\n2024 mali yılı sonu"), ["29 Şubat", "1 Mart", "29 Şubat", "1 Oca 2023", "30 Haziran", "31 Aralık"]);
+    assert_eq!(outputs("1 şubat saat 15:30"), ["1 Şubat 15:30:00"]);
+
+    /* Conversions and money phrases */
+    assert_eq!(outputs("255 hex\n1 ile 10 arası toplam\n100 dolar usd olarak\n10 lira try cinsinden"), ["0xFF", "55", "$100,00", "₺10,00"]);
+}
+
+#[test]
+fn math_constants() {
+    let mut calc = SmartCalc::default();
+    calc.set_decimal_seperator(".".to_string());
+    calc.set_thousand_separator(",".to_string());
+    evaluate_line!(calc, r"2 * pi * 5" => r"31.42");
+    evaluate_line!(calc, r"π * 2" => r"6.28");
+    evaluate_line!(calc, r"e" => r"2.72");
+    evaluate_line!(calc, r"2 * e" => r"5.44");
+    evaluate_line!(calc, r"PI" => r"3.14");
+    evaluate_line!(calc, r"tau / 2" => r"3.14");
+
+    let results = calc.execute("en".to_string(), "pi = 3\npi".to_string());
+    assert!(results.lines[0].as_ref().unwrap().result.is_err());
+    assert_eq!(results.lines[1].as_ref().unwrap().result.as_ref().unwrap().output, "3.14");
+
+    let results = calc.execute("en".to_string(), "Pi = 3\npi".to_string());
+    assert!(results.lines[0].as_ref().unwrap().result.is_err());
+    assert_eq!(results.lines[1].as_ref().unwrap().result.as_ref().unwrap().output, "3.14");
+
+}
+
+#[test]
+fn previous_result() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "100 * 3\nans + 50\n\nprev * 2\nfoo bar\nans / 7".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().and_then(|line| line.result.as_ref().ok()).map(|result| result.output.clone())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Some("300".to_string()), Some("350".to_string()), None, Some("700".to_string()), None, Some("100".to_string())]);
+
+    let results = calc.execute("tr".to_string(), "12 * 2\nönceki + 1".to_string());
+    assert_eq!(results.lines[1].as_ref().unwrap().result.as_ref().unwrap().output, "25");
+}
+
+#[test]
+fn line_reference_turkish() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("tr".to_string(), "7 * 6\nsatır 1 / 2".to_string());
+    assert_eq!(results.lines[1].as_ref().unwrap().result.as_ref().unwrap().output, "21");
+}
+
+#[test]
+fn block_aggregate_heading() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "10\n# Rent\n5\n7\nsubtotal\ntotal expenses = 3\ntotal expenses".to_string());
+    assert_eq!(results.lines[4].as_ref().unwrap().result.as_ref().unwrap().output, "12");
+    assert_eq!(results.lines[6].as_ref().unwrap().result.as_ref().unwrap().output, "3");
+
+    let results = calc.execute("tr".to_string(), "10\n20\ntoplam\nortalama".to_string());
+    assert_eq!(results.lines[2].as_ref().unwrap().result.as_ref().unwrap().output, "30");
+    assert_eq!(results.lines[3].as_ref().unwrap().result.as_ref().unwrap().output, "15");
+
+    /* Keyword between two values is still the addition operator */
+    let results = calc.execute("en".to_string(), "10\n5 sum 3\nsum".to_string());
+    assert_eq!(results.lines[1].as_ref().unwrap().result.as_ref().unwrap().output, "8");
+    assert_eq!(results.lines[2].as_ref().unwrap().result.as_ref().unwrap().output, "18");
+
+    let results = calc.execute("tr".to_string(), "5 toplam 3".to_string());
+    assert_eq!(results.lines[0].as_ref().unwrap().result.as_ref().unwrap().output, "8");
+}
+
+#[test]
+fn section_totals() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "# Groceries\n10 usd\n20 usd\nsubtotal\n\nRent:\n500 usd\n\n50 usd\nsubtotal\nsum\ntotal".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().and_then(|line| line.result.as_ref().ok()).map(|result| result.output.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [None, Some("$10,00".to_string()), Some("$20,00".to_string()), Some("$30,00".to_string()), None, None, Some("$500,00".to_string()), None, Some("$50,00".to_string()), Some("$550,00".to_string()), Some("$50,00".to_string()), Some("$580,00".to_string())]);
+}
+
+#[test]
+fn aggregate_kinds() {
+    let calc = SmartCalc::default();
+    let result = |text: &str| calc.execute("en".to_string(), text.to_string()).lines.last().cloned().flatten().unwrap().result.map(|result| result.output.to_string());
+
+    /* Numbers and durations are not added as money */
+    assert!(result("hour = 5\n2 hour\n5\n$10\ntotal").is_err());
+    assert!(result("10 usd\n2 hours\n5\ntotal").is_err());
+    assert_eq!(result("$10\n$20\n$60\naverage"), Ok("$30,00".to_string()));
+}
+
+#[test]
+fn unset_variable() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "price = 10\nprice * 2\nunset price\nunset price\nprice = 3\nprice * 2".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Ok("10".to_string()), Ok("20".to_string()), Ok("".to_string()), Err("Variable not found".to_string()), Ok("3".to_string()), Ok("6".to_string())]);
+}
+
+#[test]
+fn remove_variable() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("total price = 10".to_string());
+    calc.execute_session(&session);
+
+    assert!(session.remove_variable("Total Price"));
+    assert!(!session.remove_variable("total price"));
+    assert!(session.variables.borrow().is_empty());
+}
+
+#[test]
+fn variable_listing() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("rent = 1000 usd\n\n  total price = rent * 2\nrent = 1200 usd".to_string());
+    calc.execute_session(&session);
+
+    let variables = calc.variables(&session).map(|(name, value, line, span)| (name, value.type_name(), line, span)).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(variables, [("rent".to_string(), "MONEY".to_string(), 0, (0, 4)), ("total price".to_string(), "MONEY".to_string(), 2, (2, 13))]);
+
+    /* Spans are byte ranges of the line, like the token positions */
+    let mut session = Session::new();
+    session.set_language("tr".to_string());
+    session.set_text("  çay fiyatı = 12
+x = 1; büyük = 2".to_string());
+    calc.execute_session(&session);
+
+    let spans = session.variables().map(|(name, _, line, (start, end))| (name, line, session.get_text().lines().nth(line).unwrap()[start..end].to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(spans, [("çay fiyatı".to_string(), 0, "çay fiyatı".to_string()), ("x".to_string(), 1, "x".to_string()), ("büyük".to_string(), 1, "büyük".to_string())]);
+}
+
+#[test]
+fn typed_variables() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "price: money = 100 usd\nprice = 5\nprice\nprice = 20 usd\nx: foo = 5\nprice: number = 5\nprice = 6".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Ok("$100,00".to_string()), Err("Variable type does not match. (money)".to_string()), Ok("$100,00".to_string()), Ok("$20,00".to_string()), Err("Unknown variable type".to_string()), Ok("5".to_string()), Ok("6".to_string())]);
+
+    let results = calc.execute("tr".to_string(), "çalışma: süre = 2 saat\nçalışma = 10".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.is_ok()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [true, false]);
+}
+
+#[test]
+fn global_variables() {
+    let calc = SmartCalc::default();
+    let mut globals = Session::new();
+    globals.set_language("en".to_string());
+    globals.set_text("euro rate = 2\ncompany tax = 20%".to_string());
+    calc.execute_session(&globals);
+    let globals = alloc::rc::Rc::new(globals);
+
+    let mut invoice = Session::new();
+    invoice.set_language("en".to_string());
+    invoice.set_globals(globals.clone());
+    invoice.set_text("price = 100\nprice * euro rate\nprice + company tax".to_string());
+    let results = calc.execute_session(&invoice);
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["100", "200", "120"]);
+
+    let mut report = Session::new();
+    report.set_language("en".to_string());
+    report.set_globals(globals.clone());
+    report.set_text("euro rate = 3\n10 * euro rate\nprice".to_string());
+    let results = calc.execute_session(&report);
+    assert_eq!(results.lines[1].as_ref().unwrap().result.as_ref().unwrap().output, "30");
+    assert!(results.lines[2].as_ref().unwrap().result.is_err());
+
+    /* Locals do not leak to the globals */
+    assert_eq!(globals.variables().map(|(name, _, _, _)| name).collect::<alloc::vec::Vec<_>>(), ["euro rate".to_string(), "company tax".to_string()]);
+}
+
+#[test]
+fn rename_variable() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("total price = 10\ntax = total price * 2\ntotal price: number = total price + tax\nprice = 1".to_string());
+    calc.execute_session(&session);
+
+    let results = calc.rename_variable(&mut session, "Total Price", "amount").unwrap();
+    assert_eq!(session.get_text(), "amount = 10\ntax = amount * 2\namount: number = amount + tax\nprice = 1");
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["10", "20", "30", "1"]);
+    assert_eq!(session.variables().map(|(name, _, _, _)| name).collect::<alloc::vec::Vec<_>>(), ["amount".to_string(), "tax".to_string(), "price".to_string()]);
+
+    assert_eq!(calc.rename_variable(&mut session, "total price", "cost").err(), Some("Variable not found".to_string()));
+    assert_eq!(calc.rename_variable(&mut session, "amount", "tax").err(), Some("Variable already exists".to_string()));
+}
+
+#[test]
+fn function_call_errors() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "sqrt(-1)\nround(1, 2, 3)\nsqrt(4\nsqrt()\nx = sqrt(81)\nx + max(1, x)".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Err("Square root of negative number".to_string()), Err("Wrong number of arguments. (round)".to_string()), Err("Parentheses not closed".to_string()), Err("Wrong number of arguments. (sqrt)".to_string()), Ok("9".to_string()), Ok("18".to_string())]);
+}
+
+#[test]
+fn multiple_statements() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("a = 5; b = 6; a + b\na * b;\nc = 1; c + ; 3".to_string());
+    let results = calc.execute_session(&session);
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Ok("11".to_string()), Ok("30".to_string()), Err("No more token".to_string())]);
+
+    let ui_tokens = &results.lines[0].as_ref().unwrap().ui_tokens;
+    assert!(ui_tokens.iter().any(|token| token.start == 5 && token.end == 6 && token.ui_type == crate::UiTokenType::Operator));
+    assert!(ui_tokens.iter().any(|token| token.start == 7 && token.end == 8 && token.ui_type == crate::UiTokenType::VariableDefination));
+    assert!(ui_tokens.iter().any(|token| token.start == 18 && token.end == 19 && token.ui_type == crate::UiTokenType::VariableUse));
+
+    let tokens = results.lines[0].as_ref().unwrap().calculated_tokens.iter().map(|token| token.original_text()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(tokens, ["a", "=", "5", "b", "=", "6", "a", "+", "b"]);
+
+    calc.rename_variable(&mut session, "b", "width").unwrap();
+    assert_eq!(session.get_text(), "a = 5; width = 6; a + width\na * width;\nc = 1; c + ; 3");
+}
+
+#[test]
+fn list_values() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "[10, 25, 40] sum\navg(3, 9, 12)\nscores = [1, 2, 3]\nscores sum\nscores max\nsum(scores, 4)\n[1, $2]\n[1, 2".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Ok("75".to_string()), Ok("8".to_string()), Ok("[1; 2; 3]".to_string()), Ok("6".to_string()), Ok("3".to_string()), Ok("10".to_string()), Err("List items should have the same type".to_string()), Err("List not closed".to_string())]);
+}
+
+#[test]
+fn result_labels() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("rent: $1500\ngroceries — $320\nrent: $200\ntotal rent\ntotal\ncost: number = 5".to_string());
+    let results = calc.execute_session(&session);
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["$1.500,00", "$320,00", "$200,00", "$1.700,00", "$2.020,00", "5"]);
+
+    assert_eq!(results.lines[1].as_ref().unwrap().label, Some("groceries".to_string()));
+    assert_eq!(session.line_label(0), Some("rent".to_string()));
+    assert_eq!(session.line_label(3), None);
+    assert_eq!(session.line_label(5), None);
+}
+
+#[test]
+fn unit_dimensions() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "5 kg + 3 meters\n5 meters + 20 cm\n1 GB - 5 kg\n5 kg * 3 m\n6 kg / 2 kg\n5 kg in meter".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Err("Units are not compatible. (mass and length)".to_string()), Ok("5,20 Meter".to_string()), Err("Units are not compatible. (information and mass)".to_string()), Err("Unit is not supported. (length*mass)".to_string()), Ok("3".to_string()), Ok("5 Kilogram".to_string())]);
+}
+
+#[test]
+fn repeated_unary() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "--5\n- -5\n+-5\n-(-5)".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Err("Unary operator can not be repeated".to_string()), Err("Unary operator can not be repeated".to_string()), Err("Unary operator can not be repeated".to_string()), Ok("5".to_string())]);
+}
+
+#[test]
+fn token_parser() {
+    use crate::{Tokinizer, TokenType, NumberType};
+
+    fn dozen_parser(_: &SmartCalcConfig, tokinizer: &mut Tokinizer) {
+        let data = tokinizer.data.to_string();
+        for (start, _) in data.match_indices("dozen") {
+            tokinizer.add_token_location(start, start + 5, Some(TokenType::Number(12.0, NumberType::Decimal)));
+        }
+    }
+
+    let mut calc = SmartCalc::default();
+    assert!(calc.add_token_parser("dozen", dozen_parser));
+    assert!(!calc.add_token_parser("dozen", dozen_parser));
+
+    let results = calc.execute("en".to_string(), "dozen * 3\n2 + dozen # dozen".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["36", "14"]);
+}
+
+#[test]
+fn merge_json() {
+    let mut calc = SmartCalc::default();
+    calc.set_decimal_seperator(".".to_string());
+    calc.set_thousand_separator(",".to_string());
+
+    let results = calc.execute("en".to_string(), "5 quid".to_string());
+    assert_ne!(results.lines[0].as_ref().unwrap().result.as_ref().unwrap().output, "£5.00");
+
+    calc.merge_json(r#"{"currency_alias": {"quid": "gbp"}, "languages": {"en": {"alias": {"lots": "[OPERATOR:*]"}}}}"#).unwrap();
+    let results = calc.execute("en".to_string(), "5 quid\n2 lots 4\n$10 + 5\n10 March 2021".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["£5.00", "8", "$15.00", "10 Mar 2021"]);
+
+    assert!(calc.merge_json("{").is_err());
+    assert!(calc.merge_json(r#"{"timezones": 5}"#).is_err());
+    assert!(SmartCalc::from_json("[]").is_err());
+    assert!(SmartCalcConfig::from_json(crate::constants::JSON_DATA).is_ok());
+}
+
+#[test]
+fn ui_token_types() {
+    use crate::UiTokenType;
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "10 march 2021\n2 hours 30 minutes\n5 kg in g\n20% of $100\n3 times 4".to_string());
+    let ui_types = results.lines.iter().map(|line| line.as_ref().unwrap().ui_tokens.iter().map(|token| token.ui_type.clone()).collect::<alloc::vec::Vec<_>>()).collect::<alloc::vec::Vec<_>>();
+
+    assert_eq!(ui_types[0], [UiTokenType::Date]);
+    assert_eq!(ui_types[1], [UiTokenType::Duration]);
+    assert_eq!(ui_types[2], [UiTokenType::Number, UiTokenType::Unit, UiTokenType::Keyword, UiTokenType::Unit]);
+    assert_eq!(ui_types[3], [UiTokenType::Number, UiTokenType::Symbol2, UiTokenType::Keyword, UiTokenType::Symbol1, UiTokenType::Number]);
+    assert_eq!(ui_types[4], [UiTokenType::Number, UiTokenType::Operator, UiTokenType::Number]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn execute_reader() {
+    let text = "a = 2\r\nb = a * 3\n\n10\n20\ntotal\n# rent:\n$5\nb + 1";
+    let calc = SmartCalc::default();
+
+    let outputs = |results: crate::smartcalc::ExecuteResult| results.lines.iter().map(|line| line.as_ref().map(|line| line.result.as_ref().unwrap().output.to_string())).collect::<alloc::vec::Vec<_>>();
+    let expected = outputs(calc.execute("en".to_string(), text.to_string()));
+    let streamed = outputs(calc.execute_reader("en", text.as_bytes()).unwrap());
+
+    assert_eq!(streamed, expected);
+    assert_eq!(streamed[5], Some("38".to_string()));
+    assert!(!calc.execute_reader("en", "".as_bytes()).unwrap().status);
+}
+
+#[test]
+fn unicode_digits() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "１２ + ٣\nfiyat = ۱۰ usd\nfiyat * ２".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["15", "$10,00", "$20,00"]);
+
+    let spans = results.lines[0].as_ref().unwrap().ui_tokens.iter().map(|token| (token.start, token.end)).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(spans, [(0, 2), (3, 4), (5, 6)]);
+
+    let tokens = &results.lines[2].as_ref().unwrap().calculated_tokens;
+    assert_eq!((tokens[2].start, tokens[2].end), (8, 11));
+    assert_eq!(tokens[2].original_text(), "２");
+}
+
+#[test]
+fn block_comments() {
+    use crate::UiTokenType;
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "5 // five\n/* start\n10\n7 */ 3 * 2\n1; 2 /* ; */ + 1");
+    let outputs = results.lines.iter().map(|line| line.as_ref().map(|line| line.result.as_ref().unwrap().output.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Some("5".to_string()), None, None, Some("6".to_string()), Some("3".to_string())]);
+
+    let ui_tokens = &results.lines[3].as_ref().unwrap().ui_tokens;
+    assert_eq!((ui_tokens[0].start, ui_tokens[0].end, ui_tokens[0].ui_type.clone()), (0, 4, UiTokenType::Comment));
+}
+
+#[test]
+fn rational_mode() {
+    let mut calc = SmartCalc::default();
+    let text = "1/3 * 3 - 1\n0,1 + 0,2 - 0,3\nx = 1/7\nx * 7 - 1\n$10 / 3 * 3\n2 ^ 0,5";
+
+    let numbers = |calc: &SmartCalc| calc.execute("en".to_string(), text).lines.iter().map(|line| match line.as_ref().unwrap().result.as_ref().unwrap().ast.deref() {
+        SmartCalcAstType::Item(item) => item.get_underlying_number(),
+        _ => f64::NAN
+    }).collect::<alloc::vec::Vec<_>>();
+
+    let float_numbers = numbers(&calc);
+
+    assert_ne!(float_numbers[1], 0.0);
+
+    calc.set_rational_mode(true);
+    let rational_numbers = numbers(&calc);
+    assert_eq!(rational_numbers[0], 0.0);
+    assert_eq!(rational_numbers[1], 0.0);
+    assert_eq!(rational_numbers[3], 0.0);
+    assert_eq!(rational_numbers[4], float_numbers[4]);
+    assert_eq!(rational_numbers[5], float_numbers[5]);
+}
+
+#[test]
+fn number_overflow() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "10 ^ 400\n2 ^ 1024 - 1\n(-8) ^ 0,5\n10 ^ 300 * 10 ^ 8".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs[0], Err("Number overflow. (10 ^ 400)".to_string()));
+    assert_eq!(outputs[1], Err("Number overflow. (2 ^ 1024)".to_string()));
+    assert_eq!(outputs[2], Err("Result is not a number. (-8 ^ 0.5)".to_string()));
+    assert!(outputs[3].as_ref().unwrap().starts_with("100.000.000.000.000.001"));
+}
+
+#[test]
+fn division_by_zero() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "1 / 0\n0 / 0\n5 + 1/0\n$10 / 0\n5 is what % of 0".to_string());
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [
+        Err("Number overflow. (1 / 0)".to_string()),
+        Err("Result is not a number. (0 / 0)".to_string()),
+        Err("Number overflow. (1 / 0)".to_string()),
+        Err("Number overflow. (10 USD / 0)".to_string()),
+        Err("Division by zero".to_string())
+    ]);
+}
+
+#[test]
+fn line_cache() {
+    use alloc::rc::Rc;
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_line_cache(true);
+
+    let execute = |session: &Session| calc.execute_session(session).lines.into_iter().map(|line| line.unwrap().result.unwrap()).collect::<alloc::vec::Vec<_>>();
+
+    session.set_text("price = 10\ntax = 2\nprice + tax\ntax * 3\ntotal".to_string());
+    let first = execute(&session);
+
+    session.set_text("price = 10\ntax = 2\nprice + tax\ntax * 3\ntotal".to_string());
+    let second = execute(&session);
+    assert!(first.iter().zip(second.iter()).take(4).all(|(first, second)| Rc::ptr_eq(&first.ast, &second.ast)));
+    assert_eq!(second.iter().map(|result| result.output.to_string()).collect::<alloc::vec::Vec<_>>(), ["10", "2", "12", "6", "30"]);
+
+    /* Lines using the changed variable are calculated again */
+    session.set_text("price = 20\ntax = 2\nprice + tax\ntax * 3\ntotal".to_string());
+    let third = execute(&session);
+    assert!(!Rc::ptr_eq(&second[2].ast, &third[2].ast));
+    assert!(Rc::ptr_eq(&second[3].ast, &third[3].ast));
+    assert_eq!(third.iter().map(|result| result.output.to_string()).collect::<alloc::vec::Vec<_>>(), ["20", "2", "22", "6", "50"]);
+}
+
+#[test]
+fn token_cache() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use crate::Tokinizer;
+
+    static TOKINIZED_LINES: AtomicUsize = AtomicUsize::new(0);
+    fn counting_parser(_: &SmartCalcConfig, _: &mut Tokinizer) {
+        TOKINIZED_LINES.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let mut calc = SmartCalc::default();
+    calc.add_token_parser("counter", counting_parser);
+
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_line_cache(true);
+    session.set_text("10\n20 * 2\ntotal + 1".to_string());
+    calc.execute_session(&session);
+    assert_eq!(TOKINIZED_LINES.load(Ordering::Relaxed), 3);
+
+    /* Total is calculated again with the tokens of its unchanged text */
+    let results = calc.update_line(&mut session, 0, "15").lines.into_iter().map(|line| line.unwrap().result.unwrap().output).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(results, ["15", "40", "56"]);
+    assert_eq!(TOKINIZED_LINES.load(Ordering::Relaxed), 4);
+
+    session.clear_line_cache();
+    session.execute(&calc);
+    assert_eq!(TOKINIZED_LINES.load(Ordering::Relaxed), 7);
+}
+
+#[test]
+fn execute_with_trace() {
+    use crate::TraceStep;
+    let calc = SmartCalc::default();
+    let results = calc.execute_with_trace("en", "$25/hour * 14 hours\n100 usd to eur\n2 + 3 * 4");
+
+    let trace = &results.lines[0].as_ref().unwrap().trace;
+    assert_eq!(trace[0], TraceStep::Token { text: "$25".to_string(), token_type: "MONEY".to_string() });
+    assert!(trace.contains(&TraceStep::Rule { name: "duration_parse".to_string(), result: "14 hours".to_string() }));
+    assert_eq!(trace.last(), Some(&TraceStep::Calculation { expression: "$25,00 * 14 hours".to_string(), result: "$350,00".to_string() }));
+
+    let trace = &results.lines[1].as_ref().unwrap().trace;
+    assert!(trace.iter().any(|step| matches!(step, TraceStep::Conversion { from, to, .. } if from == "USD" && to == "EUR")));
+
+    /* Literal calculations are not folded while tracing */
+    let calculations = results.lines[2].as_ref().unwrap().trace.iter().filter_map(|step| match step {
+        TraceStep::Calculation { expression, result } => Some((expression.to_string(), result.to_string())),
+        _ => None
+    }).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(calculations, [("3 * 4".to_string(), "12".to_string()), ("2 + 12".to_string(), "14".to_string())]);
+
+    assert!(calc.execute("en", "2 + 3").lines[0].as_ref().unwrap().trace.is_empty());
+}
+
+#[test]
+fn execute_line() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("price = $10\n/* discount".to_string());
+    calc.execute_session(&session);
+
+    let output = |line: crate::smartcalc::ExecutionLine| line.map(|line| line.result.unwrap().output);
+    assert_eq!(output(calc.execute_line(&mut session, "en", "of 50% */ count = 3")), Some("3".to_string()));
+    assert_eq!(output(calc.execute_line(&mut session, "en", "price * count")), Some("$30,00".to_string()));
+    assert_eq!(calc.execute_line(&mut session, "en", "total").unwrap().result.unwrap_err(), "Values can not be aggregated. (money, number)");
+    assert_eq!(session.get_text(), "price = $10\n/* discount\nof 50% */ count = 3\nprice * count\ntotal");
+
+    let mut session = Session::new();
+    assert_eq!(output(calc.execute_line(&mut session, "en", "2 + 2")), Some("4".to_string()));
+    assert_eq!(session.get_text(), "2 + 2");
+}
+
+#[test]
+fn storage_limits() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_max_lines(Some(3));
+    session.set_max_variables(Some(2));
+
+    let output = |line: crate::smartcalc::ExecutionLine| line.and_then(|line| line.result.ok()).map(|result| result.output);
+    assert_eq!(output(calc.execute_line(&mut session, "en", "a = 1")), Some("1".to_string()));
+    assert_eq!(output(calc.execute_line(&mut session, "en", "b = 2")), Some("2".to_string()));
+    assert_eq!(output(calc.execute_line(&mut session, "en", "a + 10")), Some("11".to_string()));
+
+    /* "b" is the least recently used variable */
+    assert_eq!(output(calc.execute_line(&mut session, "en", "c = 3")), Some("3".to_string()));
+    assert!(session.variable("a").is_some());
+    assert!(session.variable("b").is_none());
+    assert!(session.variable("c").is_some());
+    assert_eq!(session.get_text(), "b = 2\na + 10\nc = 3");
+
+    /* Removed lines are not a part of the aggregates, line numbers are not changed */
+    assert_eq!(output(calc.execute_line(&mut session, "en", "total")), Some("14".to_string()));
+    assert_eq!(output(calc.execute_line(&mut session, "en", "line 4 * 2")), Some("6".to_string()));
+    assert_eq!(output(calc.execute_line(&mut session, "en", "line 1 * 2")), None);
+    assert_eq!(session.line_label(6), None);
+    assert_eq!(session.get_text(), "total\nline 4 * 2\nline 1 * 2");
+
+    session.set_max_lines(Some(1));
+    session.set_max_variables(Some(0));
+    session.shrink();
+    assert_eq!(session.get_text(), "line 1 * 2");
+    assert_eq!(session.variables().count(), 0);
+    assert_eq!(output(calc.execute_line(&mut session, "en", "5 * 2")), Some("10".to_string()));
+}
+
+#[test]
+fn update_line() {
+    use alloc::rc::Rc;
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("a = 5\nb = a * 2\nc = 7\nb + c".to_string());
+    calc.execute_session(&session);
+
+    let results = |result: crate::smartcalc::ExecuteResult| result.lines.into_iter().map(|line| line.unwrap().result.unwrap()).collect::<alloc::vec::Vec<_>>();
+
+    /* First update records the lines */
+    let first = results(calc.update_line(&mut session, 0, "a = 6"));
+    assert_eq!(first.iter().map(|result| result.output.to_string()).collect::<alloc::vec::Vec<_>>(), ["6", "12", "7", "19"]);
+
+    let second = results(calc.update_line(&mut session, 0, "a = 10"));
+    assert_eq!(second.iter().map(|result| result.output.to_string()).collect::<alloc::vec::Vec<_>>(), ["10", "20", "7", "27"]);
+    assert!(!Rc::ptr_eq(&first[1].ast, &second[1].ast));
+    assert!(Rc::ptr_eq(&first[2].ast, &second[2].ast));
+    assert_eq!(session.get_text(), "a = 10\nb = a * 2\nc = 7\nb + c");
+
+    assert!(calc.update_line(&mut session, 4, "1").lines.is_empty());
+}
+
+#[test]
+fn update_line_dependents() {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use crate::Tokinizer;
+
+    static TOKINIZED_LINES: AtomicUsize = AtomicUsize::new(0);
+    fn counting_parser(_: &SmartCalcConfig, _: &mut Tokinizer) {
+        TOKINIZED_LINES.fetch_add(1, Ordering::Relaxed);
+    }
+
+    let mut calc = SmartCalc::default();
+    calc.add_token_parser("counter", counting_parser);
+
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("a = 5\nb = a * 2\nc = 7\nnotes\nb + c\ntotal\nx * 2".to_string());
+    calc.execute_session(&session);
+    calc.update_line(&mut session, 0, "a = 6");
+    assert_eq!(TOKINIZED_LINES.load(Ordering::Relaxed), 14);
+
+    let outputs = |result: crate::smartcalc::ExecuteResult| result.lines.into_iter().map(|line| line.unwrap().result.map(|result| result.output).ok()).collect::<alloc::vec::Vec<_>>();
+
+    /* Only the changed line, the line reading c and the total after them are calculated */
+    let results = outputs(calc.update_line(&mut session, 2, "c = 8"));
+    assert_eq!(TOKINIZED_LINES.load(Ordering::Relaxed), 17);
+    assert_eq!(results[..3], [Some("6".to_string()), Some("12".to_string()), Some("8".to_string())]);
+    assert_eq!(results[4..6], [Some("20".to_string()), Some("46".to_string())]);
+
+    /* Lines mentioning a new variable are calculated again, a is not assigned anymore */
+    let results = outputs(calc.update_line(&mut session, 0, "x = 4"));
+    assert_eq!(results[1], Some("0".to_string()));
+    assert_eq!(results[6], Some("8".to_string()));
+
+    /* Split line is calculated with the whole text */
+    let results = outputs(calc.update_line(&mut session, 2, "c = 1\nc + 1"));
+    assert_eq!(results[2..4], [Some("1".to_string()), Some("2".to_string())]);
+    assert_eq!(results[5], Some("1".to_string()));
+
+    /* Line cache of the session is not enabled, every line is tokinized again */
+    let count = TOKINIZED_LINES.load(Ordering::Relaxed);
+    session.execute(&calc);
+    assert_eq!(TOKINIZED_LINES.load(Ordering::Relaxed), count + 8);
+
+    /* Variable is removed when the updated line does not assign it anymore, the lines reading it are calculated again */
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("b = 100\n5\nc = b + 1".to_string());
+    calc.execute_session(&session);
+    assert_eq!(outputs(calc.update_line(&mut session, 1, "6")), [Some("100".to_string()), Some("6".to_string()), Some("101".to_string())]);
+    assert_eq!(outputs(calc.update_line(&mut session, 0, "d = 3")), [Some("3".to_string()), Some("6".to_string()), Some("1".to_string())]);
+    assert!(session.variable("b").is_none());
+
+    /* First update calculates every line without the old variable */
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("b = 100\n5\nc = b + 1".to_string());
+    calc.execute_session(&session);
+    assert_eq!(outputs(calc.update_line(&mut session, 0, "d = 3"))[2], Some("1".to_string()));
+
+    /* Other line assigning the variable gives its value back */
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("b = 5\nb = 100\nc = b + 1".to_string());
+    calc.execute_session(&session);
+    calc.update_line(&mut session, 2, "c = b + 2");
+    assert_eq!(outputs(calc.update_line(&mut session, 1, "d = 3")), [Some("5".to_string()), Some("3".to_string()), Some("7".to_string())]);
+}
+
+#[test]
+fn update_line_changes() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("a = 5\nb = a * 2\nc = 7\n\nb + c".to_string());
+    session.set_line_cache(true);
+    calc.execute_session(&session);
+
+    let changes = |changes: alloc::vec::Vec<(usize, crate::smartcalc::ExecutionLine)>| changes.into_iter().map(|(index, line)| (index, line.unwrap().result.unwrap().output)).collect::<alloc::vec::Vec<_>>();
+
+    assert_eq!(changes(calc.update_line_changes(&mut session, 0, "a = 6")), [(0, "6".to_string()), (1, "12".to_string()), (4, "19".to_string())]);
+    assert_eq!(changes(calc.update_line_changes(&mut session, 2, "c = 8")), [(2, "8".to_string()), (4, "20".to_string())]);
+    assert!(calc.update_line_changes(&mut session, 5, "1").is_empty());
+}
+
+#[test]
+fn syntax_tree_walk() {
+    use alloc::collections::BTreeSet;
+    use alloc::string::String;
+    use crate::types::TokenType;
+    let calc = SmartCalc::default();
+    let results = calc.execute("en", "rent = 1000 usd\nfood = 200 eur; fee = 5 gbp\nrent + food * 2\n-sqrt(rent) - 3");
+
+    let mut currencies = BTreeSet::new();
+    let mut dependencies = alloc::vec::Vec::new();
+    for line in results.lines.iter().flatten() {
+        let mut variables = BTreeSet::new();
+        for tree in line.syntax_trees.iter() {
+            tree.walk(&mut |ast: &SmartCalcAstType| {
+                match ast {
+                    SmartCalcAstType::Item(item) => if let TokenType::Money(_, currency) = item.as_token_type() {
+                        currencies.insert(currency.code.to_string());
+                    },
+                    SmartCalcAstType::Variable(variable) => { variables.insert(variable.name.to_string()); },
+                    _ => ()
+                };
+                true
+            });
+        }
+        dependencies.push(variables.into_iter().collect::<alloc::vec::Vec<String>>());
+    }
+
+    assert_eq!(currencies.into_iter().collect::<alloc::vec::Vec<_>>(), ["EUR", "GBP", "USD"]);
+    assert_eq!(dependencies, [alloc::vec![], alloc::vec![], alloc::vec!["food".to_string(), "rent".to_string()], alloc::vec!["rent".to_string()]]);
+
+    /* Children are skipped when the visitor returns false */
+    let mut count = 0;
+    results.lines[2].as_ref().unwrap().syntax_trees[0].walk(&mut |_: &SmartCalcAstType| { count += 1; false });
+    assert_eq!(count, 1);
+}
+
+#[test]
+fn execute_with_callback() {
+    let calc = SmartCalc::default();
+    let mut lines = alloc::vec::Vec::new();
+    calc.execute_with("en", "a = 2\n\na * 3\ntotal", |index, line| lines.push((index, line.map(|line| line.result.unwrap().output))));
+
+    assert_eq!(lines, [(0, Some("2".to_string())), (1, None), (2, Some("6".to_string())), (3, Some("8".to_string()))]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn shared_between_threads() {
+    fn thread_safe<T: Send + Sync>(_: &T) {}
+    let calc = SmartCalc::default();
+    thread_safe(&calc);
+
+    /* Every thread runs its own session on the same engine */
+    let outputs = std::thread::scope(|scope| {
+        let handles = (1..=4).map(|number| {
+            let calc = &calc;
+            scope.spawn(move || {
+                let results = calc.execute("en", alloc::format!("price = ${}\nprice * 2", number));
+                results.lines[1].as_ref().unwrap().result.as_ref().unwrap().output.to_string()
+            })
+        }).collect::<alloc::vec::Vec<_>>();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect::<alloc::vec::Vec<_>>()
+    });
+
+    assert_eq!(outputs, ["$2,00", "$4,00", "$6,00", "$8,00"]);
+}
+
+#[test]
+fn format_options() {
+    use crate::{FormatOptions, CurrencyStyle, DurationStyle};
+    let calc = SmartCalc::default();
+    let results = calc.execute("en", "$1234\n1234,5678\n2 hours 30 minutes");
+    let mut session = Session::default();
+    session.set_language("en".to_string());
+
+    let options = FormatOptions {
+        decimal_places: Some(1),
+        thousand_separator: Some(" ".to_string()),
+        currency_style: Some(CurrencyStyle::Code),
+        duration_style: Some(DurationStyle::Short),
+        ..FormatOptions::default()
+    };
+
+    let format = |options: &FormatOptions| results.lines.iter().map(|line| calc.format_result(&session, line.as_ref().unwrap().result.as_ref().unwrap().ast.clone(), options)).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(format(&options), ["1 234,0 USD", "1 234,6", "2h 30m"]);
+
+    /* Nothing is kept after the call */
+    assert_eq!(format(&FormatOptions::default()), ["$1.234,00", "1.234,57", "2 hours 30 minutes"]);
+}
+
+#[test]
+fn language_directive() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en", "2 saat\n#lang: tr\n2 saat\n# LANG: en\n2 saat");
+    let outputs = results.lines.iter().map(|line| line.as_ref().map(|line| line.result.as_ref().unwrap().output.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Some("2".to_string()), None, Some("2 saat".to_string()), None, Some("2".to_string())]);
+
+    /* Lines added later use the last directive */
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("#lang: tr".to_string());
+    session.execute(&calc);
+
+    let line = calc.execute_line(&mut session, "en", "3 saat");
+    assert_eq!(line.unwrap().result.unwrap().output, "3 saat");
+
+    /* Unknown languages fail the lines they are used for */
+    let results = calc.execute("en", "2 saat\n#lang: xx\n2 saat\n\n#lang: tr\n2 saat");
+    let outputs = results.lines.iter().map(|line| line.as_ref().map(|line| line.result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string()))).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [
+        Some(Ok("2".to_string())),
+        Some(Err("Language not found. (xx)".to_string())),
+        Some(Err("Language not found. (xx)".to_string())),
+        None,
+        None,
+        Some(Ok("2 saat".to_string()))
+    ]);
+}
+
+#[test]
+fn load_language() {
+    let mut calc = SmartCalc::default();
+    let language = r#"{
+        "number_notation": {},
+        "long_months": { "januar": 1, "februar": 2, "märz": 3, "april": 4, "mai": 5, "juni": 6, "juli": 7, "august": 8, "september": 9, "oktober": 10, "november": 11, "dezember": 12 },
+        "short_months": { "jan": 1, "feb": 2, "mär": 3, "apr": 4, "mai": 5, "jun": 6, "jul": 7, "aug": 8, "sep": 9, "okt": 10, "nov": 11, "dez": 12 },
+        "word_group": { "duration_group": ["tag", "tage", "stunde", "stunden"] },
+        "constant_pair": { "tag": 1, "tage": 1, "stunde": 7, "stunden": 7 },
+        "rules": {
+            "number_of": { "rules": ["{PERCENT:p} von {NUMBER_OR_MONEY:number}"], "samples": [] },
+            "duration_parse": { "rules": ["{NUMBER:duration} {GROUP:type:duration_group}"], "samples": [] }
+        },
+        "alias": { "mal": "[OPERATOR:*]" },
+        "format": {
+            "duration": [
+                { "count": "n", "format": "{day} Tage", "duration_type": "Day" },
+                { "count": "1", "format": "1 Tag", "duration_type": "Day" },
+                { "count": "n", "format": "{hour} Stunden", "duration_type": "Hour" }
+            ],
+            "date": { "full_date": "{day}. {month_long} {year}", "current_year": "{day}. {month_long}" },
+            "boolean": { "true": "wahr", "false": "falsch" }
+        }
+    }"#;
+
+    assert!(calc.load_language("de", "{").is_err());
+    assert!(calc.load_language("de", language).is_ok());
+    calc.set_date_rule("de", alloc::vec!["{NUMBER:day} {MONTH:month} {NUMBER:year}".to_string()]);
+
+    let results = calc.execute("de", "10% von 200\n3 mal 4\n1 tag + 2 stunden\n5 märz 2020");
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).unwrap_or_else(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["20", "12", "1 Tag 2 Stunden", "5. März 2020"]);
+
+    /* Other languages are not changed */
+    evaluate_line!(calc, r"10% of 200" => r"20");
+}
+
+#[test]
+fn trailing_zeros() {
+    use crate::FormatOptions;
+    let mut calc = SmartCalc::default();
+    let mut session = Session::default();
+    session.set_language("en".to_string());
+
+    let outputs = |calc: &SmartCalc| calc.execute("en", "$5\n$5,5\n2,5\n%12,5\n3").lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs(&calc), ["$5,00", "$5,50", "2,50", "%12,50", "3"]);
+
+    calc.set_remove_trailing_zeros(true, false);
+    assert_eq!(outputs(&calc), ["$5,00", "$5,50", "2,5", "%12,5", "3"]);
+
+    calc.set_remove_trailing_zeros(true, true);
+    calc.set_money_configuration(true, true);
+    assert_eq!(outputs(&calc), ["$5", "$5,5", "2,5", "%12,5", "3"]);
+
+    /* Format options override the configuration */
+    let results = calc.execute("en", "$5\n2,5");
+    let options = FormatOptions { remove_fract_if_zero: Some(false), remove_trailing_zeros: Some(false), ..FormatOptions::default() };
+    let outputs = results.lines.iter().map(|line| calc.format_result(&session, line.as_ref().unwrap().result.as_ref().unwrap().ast.clone(), &options)).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["$5,00", "2,50"]);
+}
+
+#[test]
+fn format_template() {
+    use crate::FormatOptions;
+    let calc = SmartCalc::default();
+    let mut session = Session::default();
+    session.set_language("en".to_string());
+
+    let inputs = ["$10 + $5", "2 * 3"];
+    let results = calc.execute("en", inputs.join("\n"));
+    let format = |template: &str| results.lines.iter().zip(inputs.iter()).map(|(line, input)| {
+        let options = FormatOptions { template: Some(template.to_string()), ..FormatOptions::default() };
+        calc.format_line(&session, input, line.as_ref().unwrap().result.as_ref().unwrap().ast.clone(), &options)
+    }).collect::<alloc::vec::Vec<_>>();
+
+    assert_eq!(format("{input} = {result}"), ["$10 + $5 = $15,00", "2 * 3 = 6"]);
+    assert_eq!(format("`{result}` ({currency_code}{currency_symbol})"), ["`$15,00` (USD$)", "`6` ()"]);
+
+    /* Input is empty for format_result */
+    let options = FormatOptions { template: Some("{input}= {result}".to_string()), ..FormatOptions::default() };
+    assert_eq!(calc.format_result(&session, results.lines[1].as_ref().unwrap().result.as_ref().unwrap().ast.clone(), &options), "= 6");
+}
+
+#[test]
+fn currency_style() {
+    use crate::{FormatOptions, CurrencyStyle};
+    let mut calc = SmartCalc::default();
+    let mut session = Session::default();
+    session.set_language("en".to_string());
+
+    let results = calc.execute("en", "$1500\n1500,5 eur");
+    let format = |calc: &SmartCalc, options: &FormatOptions| results.lines.iter().map(|line| calc.format_result(&session, line.as_ref().unwrap().result.as_ref().unwrap().ast.clone(), options)).collect::<alloc::vec::Vec<_>>();
+
+    let options = |style: CurrencyStyle| FormatOptions { currency_style: Some(style), ..FormatOptions::default() };
+    assert_eq!(format(&calc, &options(CurrencyStyle::Symbol)), ["$1.500,00", "1.500,50 €"]);
+    assert_eq!(format(&calc, &options(CurrencyStyle::Code)), ["1.500,00 USD", "1.500,50 EUR"]);
+    assert_eq!(format(&calc, &options(CurrencyStyle::Name)), ["1.500 US dollars", "1.500,50 euros"]);
+
+    calc.set_currency_style(CurrencyStyle::Code);
+    assert_eq!(format(&calc, &FormatOptions::default()), ["1.500,00 USD", "1.500,50 EUR"]);
+
+    let results = calc.execute("tr", "$1500");
+    assert_eq!(results.lines[0].as_ref().unwrap().result.as_ref().unwrap().output, "1.500,00 USD");
+
+    calc.set_currency_style(CurrencyStyle::Name);
+    let results = calc.execute("tr", "$1500\n10 sek");
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["1.500 ABD doları", "10 İsveç kronu"]);
+}
+
+#[test]
+fn pluralization() {
+    use crate::config::CurrencyStyle;
+    use crate::constants::PluralRule;
+    let mut calc = SmartCalc::default();
+    calc.set_currency_style(CurrencyStyle::Name);
+
+    let outputs = |language: &str, data: &str| calc.execute(language, data).lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs("en", "1 minute\n2 minutes\n1 day 1 hour\n$1\n$2\n1 jpy"), ["1 minute", "2 minutes", "1 day 1 hour", "1 US dollar", "2 US dollars", "1 Japanese yen"]);
+    assert_eq!(outputs("tr", "1 gün\n2 gün\n$1\n$2"), ["1 gün", "2 gün", "1 ABD doları", "2 ABD doları"]);
+
+    let forms = "минута|минуты|минут";
+    assert_eq!([1.0, 3.0, 5.0, 11.0, 21.0, 22.0].map(|count| PluralRule::OneFewMany.select(forms, count)), ["минута", "минуты", "минут", "минут", "минута", "минуты"]);
+    assert_eq!([0.0, 1.5, 2.0].map(|count| PluralRule::ZeroOneOther.select("jour|jours", count)), ["jour", "jour", "jours"]);
+    assert_eq!(PluralRule::Invariant.select("gün", 5.0), "gün");
+}
+
+#[test]
+fn language_separators() {
+    let mut calc = SmartCalc::default();
+    calc.set_language_separators(true);
+
+    let outputs = |language: &str, data: &str| calc.execute(language, data).lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs("en", "1234.56 + 1\n$1,500.5\n12.5%"), ["1,235.56", "$1,500.50", "%12.50"]);
+    assert_eq!(outputs("tr", "1234,56 + 1\n$1.500,5\n%12,5"), ["1.235,56", "$1.500,50", "%12,50"]);
+}
+
+#[test]
+fn rule_matches() {
+    let calc = SmartCalc::default();
+    let results = calc.execute("en", "10% of 200\n1 + 2\nx = 5; 20% off 50");
+
+    let matches = |index: usize| results.lines[index].as_ref().unwrap().rule_matches.iter().map(|rule_match| (rule_match.name.to_string(), rule_match.start, rule_match.end, rule_match.text.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(matches(0), [("number_of".to_string(), 0, 10, "10% of 200".to_string())]);
+    assert!(matches(1).is_empty());
+    assert_eq!(matches(2), [("number_off".to_string(), 7, 17, "20% off 50".to_string())]);
+}
+
+#[test]
+fn add_vocabulary() {
+    use crate::{CurrencyInfo, ConstantType};
+    let mut calc = SmartCalc::default();
+    assert!(calc.add_currency(CurrencyInfo {
+        code: "XTS".to_string(),
+        symbol: "¤".to_string(),
+        thousands_separator: ".".to_string(),
+        decimal_separator: ",".to_string(),
+        symbol_on_left: false,
+        space_between_amount_and_symbol: true,
+        decimal_digits: 2,
+        symbol_only: false
+    }, 4.0));
+    assert!(calc.add_constant_word("en", "wk", ConstantType::Week));
+    assert!(calc.add_constant_word("en", "wks", ConstantType::Week));
+    assert!(calc.add_constant_word("en", "archimedes", ConstantType::Pi));
+    assert!(calc.add_month_names("en", alloc::vec![("sept", 9)]));
+
+    let usd = SmartCalcConfig::default().get_currency("usd".to_string()).unwrap();
+    assert!(!calc.add_currency(usd.deref().clone(), 1.0));
+    assert!(!calc.add_constant_word("xx", "wk", ConstantType::Week));
+    assert!(!calc.add_month_names("en", alloc::vec![("smarch", 13)]));
+
+    let outputs = |calc: &SmartCalc| {
+        let results = calc.execute("en", "10 xts\n¤10 in usd\n3 wks\narchimedes * 2\n1 sept 2024");
+        results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>()
+    };
+    assert_eq!(outputs(&calc), ["10,00 ¤", "$2,50", "3 weeks", "6,28", "1 Sep 2024"]);
+
+    /* Words are kept when the configuration is merged */
+    calc.merge_json("{}").unwrap();
+    assert_eq!(outputs(&calc), ["10,00 ¤", "$2,50", "3 weeks", "6,28", "1 Sep 2024"]);
+}
+
+#[test]
+fn session_api() {
+    use crate::ResultValue;
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("price = $10\nprice * 2\ntotal".to_string());
+
+    let outputs = |results: crate::smartcalc::ExecuteResult| results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs(session.execute(&calc)), [Ok("$10,00".to_string()), Ok("$20,00".to_string()), Ok("$30,00".to_string())]);
+
+    /* Executed again from the first line */
+    assert_eq!(outputs(session.execute(&calc)), [Ok("$10,00".to_string()), Ok("$20,00".to_string()), Ok("$30,00".to_string())]);
+    assert_eq!(ResultValue::from_ast(&session.variable("Price").unwrap()), Some(ResultValue::Money { amount: 10.0, currency: "USD".to_string() }));
+
+    session.clear();
+    assert!(session.variable("price").is_none());
+    assert_eq!(session.get_text(), "");
+    assert_eq!(session.get_language(), "en");
+
+    session.set_text("price = $5\nprice * 2".to_string());
+    assert_eq!(outputs(session.execute(&calc)), [Ok("$5,00".to_string()), Ok("$10,00".to_string())]);
+}
+
+#[test]
+fn cancel_execution() {
+    use alloc::rc::Rc;
+    use alloc::sync::Arc;
+    use crate::{CancellationToken, Evaluator, Interpreter};
+
+    /* Cancels the execution while the second line is evaluated */
+    struct CancellingEvaluator(CancellationToken, core::sync::atomic::AtomicUsize);
+    impl Evaluator for CancellingEvaluator {
+        fn evaluate(&self, config: &SmartCalcConfig, ast: Rc<SmartCalcAstType>, session: &Session) -> Result<Rc<SmartCalcAstType>, alloc::string::String> {
+            if self.1.fetch_add(1, core::sync::atomic::Ordering::Relaxed) == 1 {
+                self.0.cancel();
+            }
+            Interpreter.evaluate(config, ast, session)
+        }
+    }
+
+    let token = CancellationToken::new();
+    let mut calc = SmartCalc::default();
+    calc.set_evaluator(Arc::new(CancellingEvaluator(token.clone(), core::sync::atomic::AtomicUsize::new(0))));
+
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("1 + 1\n2 + 2\n3 + 3".to_string());
+    session.set_cancellation_token(token);
+
+    let results = session.execute(&calc);
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert!(!results.status);
+    assert_eq!(outputs, [Ok("2".to_string()), Err("Calculation is cancelled".to_string())]);
+}
+
+#[test]
+#[cfg(feature = "std")]
+fn time_limit_execution() {
+    let calc = SmartCalc::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("1 + 1\n2 + 2".to_string());
+    session.set_time_limit(core::time::Duration::ZERO);
+
+    let results = session.execute(&calc);
+    assert!(!results.status);
+    assert_eq!(results.lines.len(), 1);
+    assert_eq!(results.lines[0].as_ref().unwrap().result.as_ref().unwrap_err(), "Calculation time limit is exceeded");
+
+    session.set_time_limit(core::time::Duration::from_secs(60));
+    assert!(session.execute(&calc).status);
+}
+
+#[test]
+fn execute_many() {
+    let calc = SmartCalc::default();
+    let results = calc.execute_many("en", &["rate = 20%\nprice = $50", "price + rate", "", "price * 2\ntotal"]);
+
+    let outputs = results.iter().map(|result| result.lines.iter().map(|line| line.as_ref().map(|line| line.result.as_ref().unwrap().output.to_string())).collect::<alloc::vec::Vec<_>>()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [
+        alloc::vec![Some("%20".to_string()), Some("$50,00".to_string())],
+        alloc::vec![Some("$60,00".to_string())],
+        alloc::vec![None],
+        alloc::vec![Some("$100,00".to_string()), Some("$100,00".to_string())]
+    ]);
+}
@@ -9,6 +9,7 @@ extern crate alloc;
 use alloc::vec::Vec;
 use crate::smartcalc::SmartCalc;
 use alloc::string::{String, ToString};
+use chrono::{TimeZone, Utc};
 
 fn execute(test_data: String, decimal_seperator: String, thousand_separator: String, timezone: String) {
     let mut query = String::new();
@@ -32,6 +33,7 @@ fn execute(test_data: String, decimal_seperator: String, thousand_separator: Str
     calculater.set_decimal_seperator(decimal_seperator);
     calculater.set_thousand_separator(thousand_separator);
     calculater.set_timezone(timezone).unwrap();
+    calculater.set_time_provider(|| Utc.with_ymd_and_hms(2022, 3, 4, 12, 0, 0).unwrap());
     let results = calculater.execute("en".to_string(), query);
     
     for (index, result_line) in results.lines.iter().enumerate() {
@@ -160,6 +162,162 @@ a to UTC                | 1 October 00:00:00 UTC
 "#.to_string(), ".".to_string(), ",".to_string(), "CET".to_string());        
 }
 
+#[test]
+fn iso_date_tests() {
+    execute(r#"
+2024-06-15                        | 15 Jun 2024
+2024-06-15T13:45:00Z              | 15 Jun 2024 13:45:00 UTC
+2024-06-15 13:45                  | 15 Jun 2024 13:45:00 CET
+2024-06-15T13:45:00+03:00 to UTC  | 15 Jun 2024 10:45:00 UTC
+2024-06-15 + 3 days               | 18 Jun 2024
+2024-06-15T13:45:00Z as unix      | 1718459100
+2024-5                            | 2,019
+"#.to_string(), ".".to_string(), ",".to_string(), "CET".to_string());
+}
+
+#[test]
+fn month_addition_tests() {
+    execute(r#"
+31 january 2024 + 1 month       | 29 Feb 2024
+31 january 2023 + 1 month       | 28 Feb 2023
+31 march 2024 - 1 month         | 29 Feb 2024
+29 february 2024 + 1 year       | 28 Feb 2025
+15 december 2023 + 1 month      | 15 Jan 2024
+31 october 2023 + 4 months      | 29 Feb 2024
+"#.to_string(), ".".to_string(), ",".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn block_aggregate_tests() {
+    execute(r#"
+# Groceries
+10                      | 10
+20                      | 20
+Sum                     | 30
+
+5 usd                   | $5,00
+7 usd                   | $7,00
+3 usd                   | $3,00
+sum                     | $15,00
+
+4                       | 4
+6                       | 6
+average                 | 5
+
+2 hours                 | 2 hours
+30 minutes              | 30 minutes
+sum                     | 2 hours 30 minutes
+"#.to_string(), ",".to_string(), ".".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn operator_precedence_tests() {
+    execute(r#"
+2 + 3 * 4               | 14
+2 * 3 + 4               | 10
+10 - 4 / 2              | 8
+(2 + 3) * 4             | 20
+2 ^ 3                   | 8
+2 ^ 3 ^ 2               | 512
+2 * 3 ^ 2               | 18
+(1 + 1) ^ (1 + 2)       | 8
+100 / 10 ^ 2 + 1        | 2
+-2 ^ 2                  | -4
+- 2 ^ 2                 | -4
+(-2) ^ 2                | 4
+2 ^ -3                  | 0,12
+-2 ^ -2                 | -0,25
+5 - -3                  | 8
+2 * - 3                 | -6
+"#.to_string(), ",".to_string(), ".".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn math_symbol_tests() {
+    execute(r#"
+10 − 4                  | 6
+−2 ^ 2                  | -4
+3 × 4                   | 12
+3·4 + 1                 | 13
+10 ÷ 4                  | 2,50
+$20 × 3                 | $60,00
+"#.to_string(), ",".to_string(), ".".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn function_call_tests() {
+    execute(r#"
+sqrt(16)                | 4
+2 * sqrt(9) + 1         | 7
+sqrt(2 + 2) ^ 2         | 4
+min(3, 5)               | 3
+max(3, 5, 2)            | 5
+round(3.14159, 2)       | 3.14
+round(2.5)              | 3
+floor(2.7) + ceil(2.1)  | 5
+abs(-5 usd)             | $5.00
+min($10, 5 eur)         | 5.00 €
+max(10%, 20%)           | %20
+"#.to_string(), ".".to_string(), ",".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn quarter_tests() {
+    execute(r#"
+Q3 2024                 | 1 Jul 2024
+q1 2023                 | 1 Jan 2023
+start of Q2 2024        | 1 Apr 2024
+end of Q2 2024          | 30 Jun 2024
+end of q4 2024          | 31 Dec 2024
+Q3 2024 + 10 days       | 11 Jul 2024
+end of Q1               | 31 March
+"#.to_string(), ".".to_string(), ",".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn period_tests() {
+    execute(r#"
+last day of February 2024     | 29 Feb 2024
+last day of february 2023     | 28 Feb 2023
+first day of march 2020       | 1 Mar 2020
+end of december 2021          | 31 Dec 2021
+start of june 2021 + 1 week   | 8 Jun 2021
+"#.to_string(), ".".to_string(), ",".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn leap_year_tests() {
+    execute(r#"
+is 2024 a leap year                 | true
+is 1900 a leap year                 | false
+is 2000 leap year                   | true
+leap years between 2000 and 2030    | 2000, 2004, 2008, 2012, 2016, 2020, 2024, 2028
+leap years between 1904 and 1896    | 1896, 1904
+"#.to_string(), ".".to_string(), ",".to_string(), "UTC".to_string());
+}
+
+#[test]
+fn unix_timestamp_tests() {
+    execute(r#"
+1718000000 as date                        | 10 Jun 2024 06:13:20 UTC
+1718000000123 as date                     | 10 Jun 2024 06:13:20 UTC
+1718000000 ms to date                     | 20 Jan 1970 21:13:20 UTC
+1 oct 2022 as unix timestamp              | 1664582400
+1 oct 2022 as unix ms                     | 1664582400000
+1 oct 2040 as unix timestamp milliseconds | 2232662400000
+"#.to_string(), ".".to_string(), ",".to_string(), "UTC".to_string());
+}
+
+#[cfg(feature = "tz")]
+#[test]
+fn daylight_saving_tests() {
+    execute(r#"
+9 march 2024 at 23:00 EST               | 9 Mar 2024 23:00:00 EST
+9 march 2024 at 23:00 EST + 5 hours     | 10 Mar 2024 05:00:00 EDT
+9 march 2024 at 23:00 EST + 1 day       | 10 Mar 2024 23:00:00 EDT
+27 october 2024 at 01:00 CEST + 2 hours | 27 Oct 2024 02:00:00 CET
+"#.to_string(), ".".to_string(), ",".to_string(), "UTC".to_string());
+}
 
 #[test]
 fn variable_usage_test() {
@@ -192,3 +350,25 @@ fn weight_height_tests() {
 10 pound to ounce        |   160 Ounce
 "#.to_string(), ".".to_string(), ",".to_string(), "CET".to_string());        
 }
+
+#[test]
+fn compound_measurement_tests() {
+    execute(r#"
+6 ft 2 in in cm                                               | 187.96 Centimeter
+2 lb 3 oz in kg                                               |   0.99 Kilogram
+2 kg 300 g                                                    |   2.30 Kilogram
+1 hour 20 min                                                 | 1 hour 20 minutes
+1 day 2 hours 3 minutes 4 seconds 5 hours 6 minutes 7 seconds | 1 day 7 hours 9 minutes 11 seconds
+"#.to_string(), ".".to_string(), ",".to_string(), "CET".to_string());        
+}
+
+#[test]
+fn range_tests() {
+    execute(r#"
+sum 1 to 100     | 5,050
+1..10 * 3        |   165
+average of 5..15 |    10
+avg 1 to 4       |     2.50
+sum of 10..1     |    55
+"#.to_string(), ".".to_string(), ",".to_string(), "CET".to_string());        
+}
@@ -116,3 +116,19 @@ my age = year - 1985".to_string();
         _ => assert!(false)
     };
 }
+
+#[test]
+fn variable_3() {
+    let test_data = r"hour = 5
+2 hour
+hour * 2
+may = 2
+1 may 2024
+may * 3
+usd = 3
+10 usd".to_string();
+    let calculater = SmartCalc::default();
+    let results = calculater.execute("en".to_string(), test_data);
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().unwrap().output.to_string()).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, ["5", "2 hours", "10", "2", "1 May 2024", "6", "3", "$10,00"]);
+}
@@ -9,6 +9,8 @@ use regex::{Match};
 use core::{borrow::Borrow, iter::Iterator};
 use serde_derive::Serialize;
 
+use crate::types::TokenType;
+
 #[derive(Debug, Clone, Serialize, PartialEq)]
 pub enum UiTokenType {
     Text,
@@ -20,7 +22,49 @@ pub enum UiTokenType {
     Comment,
     VariableDefination,
     VariableUse,
-    Month
+    Month,
+    Date,
+    Duration,
+    Unit,
+    Keyword
+}
+
+impl UiTokenType {
+    pub fn from_token(token: &TokenType) -> UiTokenType {
+        match token {
+            TokenType::Number(_, _) | TokenType::Money(_, _) | TokenType::Percent(_) | TokenType::Range(_, _, _) => UiTokenType::Number,
+            TokenType::Date(_, _) => UiTokenType::Date,
+            TokenType::Time(_, _) | TokenType::DateTime(_, _) => UiTokenType::DateTime,
+            TokenType::Duration(_) => UiTokenType::Duration,
+            TokenType::DynamicType(_, _) => UiTokenType::Unit,
+            TokenType::Month(_) => UiTokenType::Month,
+            TokenType::Operator(_) => UiTokenType::Operator,
+            TokenType::Variable(_) => UiTokenType::VariableUse,
+            TokenType::Text(_) => UiTokenType::Text,
+            TokenType::Timezone(_, _) | TokenType::Field(_) => UiTokenType::Symbol1,
+            _ => UiTokenType::Symbol2
+        }
+    }
+
+    /// Stable name of the type, can be used as a CSS class or an editor decoration name.
+    pub fn css_name(&self) -> &'static str {
+        match self {
+            UiTokenType::Text => "text",
+            UiTokenType::Number => "number",
+            UiTokenType::Symbol1 => "symbol-1",
+            UiTokenType::Symbol2 => "symbol-2",
+            UiTokenType::DateTime => "date-time",
+            UiTokenType::Operator => "operator",
+            UiTokenType::Comment => "comment",
+            UiTokenType::VariableDefination => "variable-def",
+            UiTokenType::VariableUse => "variable-use",
+            UiTokenType::Month => "month",
+            UiTokenType::Date => "date",
+            UiTokenType::Duration => "duration",
+            UiTokenType::Unit => "unit",
+            UiTokenType::Keyword => "keyword"
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, PartialEq)]
@@ -30,7 +74,18 @@ pub struct UiToken {
     pub ui_type: UiTokenType
 }
 
+impl UiToken {
+    /// Start and end of the token in UTF-16 code units, like the string offsets of JavaScript editors.
+    ///
+    /// Token locations are character based, `line` should be the text of the line the token belongs to.
+    pub fn utf16_range(&self, line: &str) -> (usize, usize) {
+        let offset = |position: usize| line.chars().take(position).map(char::len_utf16).sum();
+        (offset(self.start), offset(self.end))
+    }
+}
+
 #[derive(Default)]
+#[derive(Clone)]
 pub struct UiTokenCollection {
     tokens: Vec<UiToken>,
     char_sizes: Vec<usize>
@@ -78,6 +133,16 @@ impl UiTokenCollection {
         }
     }
 
+    /* Locations are byte based, same as the token locations */
+    pub fn add_from_location(&mut self, start: usize, end: usize, ui_type: UiTokenType) {
+        let start = self.get_position(start);
+        let end = self.get_position(end);
+
+        if start < end && self.check_collision(start, end) {
+            self.tokens.push(UiToken { start, end, ui_type });
+        }
+    }
+
     pub fn add_from_regex_match(&mut self, capture: Option<Match<'_>>, token_type: UiTokenType) {
         if let Some(content) = capture {
             if content.start() < content.end() && self.check_collision(content.start(), content.end()) {
@@ -127,6 +192,16 @@ impl UiTokenCollection {
         self.tokens.sort_by(|a, b| a.start.partial_cmp(&b.start).unwrap());
     }
 
+    /* Only the token at the exact location with the old type is changed */
+    pub fn replace_type(&mut self, position_start: usize, position_end: usize, old_type: UiTokenType, new_type: UiTokenType) {
+        let ui_start_position = self.get_position(position_start);
+        let ui_end_position   = self.get_position(position_end);
+
+        if let Some(ui_token) = self.tokens.iter_mut().find(|ui_token| ui_token.start == ui_start_position && ui_token.end == ui_end_position && ui_token.ui_type == old_type) {
+            ui_token.ui_type = new_type;
+        }
+    }
+
     pub fn update_tokens(&mut self, position_start: usize, position_end: usize, new_type: UiTokenType) {
         let ui_start_position   = self.get_position(position_start);
         let ui_end_position     = self.get_position(position_end);
@@ -280,3 +355,14 @@ fn collection_test_5() {
         ui_type: UiTokenType::VariableDefination
     });
 }
+
+#[cfg(test)]
+#[test]
+fn css_name_test() {
+    let token = UiToken { start: 3, end: 6, ui_type: UiTokenType::VariableUse };
+    assert_eq!(token.ui_type.css_name(), "variable-use");
+    assert_eq!(UiTokenType::VariableDefination.css_name(), "variable-def");
+    assert_eq!(UiTokenType::from_token(&TokenType::Percent(1.0)).css_name(), "number");
+    assert_eq!(token.utf16_range("a𝑥 abc"), (4, 7));
+    assert_eq!(token.utf16_range("abc abc"), (3, 6));
+}
@@ -4,19 +4,21 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
+use core::ops::Deref;
 use alloc::vec::Vec;
-use alloc::string::ToString;
 
 use crate::types::TokenType;
+use crate::token::ui_token::UiTokenType;
 
-use super::{Tokinizer, regex_tokinizer::get_atom};
+use super::{TokenInfo, Tokinizer, regex_tokinizer::get_atom};
 
  
 
 pub fn alias_tokinizer(tokinizer: &mut Tokinizer) {
     for token in tokinizer.token_infos.iter() {
+        let text = token.original_text().to_lowercase();
         for (re, data) in tokinizer.config.alias_regex.iter() {
-            if re.is_match(&token.original_text.to_lowercase()) {
+            if re.is_match(&text) {
                 let new_values = match tokinizer.config.token_parse_regex.get("atom") {
                     Some(items) => get_atom(tokinizer.config, data, items),
                     _ => Vec::new()
@@ -25,12 +27,13 @@ pub fn alias_tokinizer(tokinizer: &mut Tokinizer) {
                 match new_values.len() {
                     1 => {
                         if let Some(token_type) = &new_values[0].2 {
+                            tokinizer.ui_tokens.update_tokens(token.start, token.end, UiTokenType::from_token(token_type));
                             *token.token_type.borrow_mut() = Some(token_type.clone());
                             break;
                         }
                     },
                     0 => {
-                        *token.token_type.borrow_mut() = Some(TokenType::Text(data.to_string()));
+                        *token.token_type.borrow_mut() = Some(TokenType::Text(tokinizer.config.interner.symbol(data)));
                         break;
                     },
                     _ => log::warn!("{} has multiple atoms. It is not allowed", data)
@@ -40,8 +43,14 @@ pub fn alias_tokinizer(tokinizer: &mut Tokinizer) {
     }
 
     for token in tokinizer.token_infos.iter() {
+        let text = token.original_text().to_lowercase();
+        /* Names of the pattern fields, like {GROUP:sum:sum_group}, are not aliased */
+        if matches!(token.token_type.borrow().deref(), Some(TokenType::Field(_))) || is_aggregate_keyword(tokinizer, token, &text) {
+            continue;
+        }
+
         for (re, data) in tokinizer.config.language_alias_regex.get(&tokinizer.language).unwrap().iter() {
-            if re.is_match(&token.original_text.to_lowercase()) {
+            if re.is_match(&text) {
                 let new_values = match tokinizer.config.token_parse_regex.get("atom") {
                     Some(items) => get_atom(tokinizer.config, data, items),
                     _ => Vec::new()
@@ -50,12 +59,13 @@ pub fn alias_tokinizer(tokinizer: &mut Tokinizer) {
                 match new_values.len() {
                     1 => {
                         if let Some(token_type) = &new_values[0].2 {
+                            tokinizer.ui_tokens.update_tokens(token.start, token.end, UiTokenType::from_token(token_type));
                             *token.token_type.borrow_mut() = Some(token_type.clone());
                             break;
                         }
                     },
                     0 => {
-                        *token.token_type.borrow_mut() = Some(TokenType::Text(data.to_string()));
+                        *token.token_type.borrow_mut() = Some(TokenType::Text(tokinizer.config.interner.symbol(data)));
                         break;
                     },
                     _ => log::warn!("{} has multiple atoms. It is not allowed", data)
@@ -64,3 +74,14 @@ pub fn alias_tokinizer(tokinizer: &mut Tokinizer) {
         }
     }
 }
+
+/* Sum is an operator between two values, like 5 sum 3, and the aggregate of the block when it is not */
+fn is_aggregate_keyword(tokinizer: &Tokinizer, token: &TokenInfo, text: &str) -> bool {
+    if !tokinizer.config.is_group_word(&tokinizer.language, "sum_group", text) {
+        return false;
+    }
+
+    let has_left = tokinizer.token_infos.iter().any(|item| item.end <= token.start);
+    let has_right = tokinizer.token_infos.iter().any(|item| item.start >= token.end);
+    !(has_left && has_right)
+}
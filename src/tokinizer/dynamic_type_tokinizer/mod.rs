@@ -5,7 +5,7 @@
  */
 
 use alloc::{collections::BTreeMap, rc::Rc};
-use core::{ops::Deref, cell::{RefCell, Cell}};
+use core::ops::Deref;
 use alloc::string::ToString;
 
 use crate::{types::TokenType, UiTokenType};
@@ -14,7 +14,7 @@ use super::{Tokinizer, TokenInfoStatus, TokenInfo, get_number};
 
 pub fn dynamic_type_tokinizer(tokinizer: &mut Tokinizer) {    
     let mut execute_rules = true;
-    while execute_rules {
+    while execute_rules && tokinizer.session.interruption().is_none() {
         execute_rules = false;
 
         for (_, type_items) in tokinizer.config.types.iter() {
@@ -34,9 +34,9 @@ pub fn dynamic_type_tokinizer(tokinizer: &mut Tokinizer) {
 
                         if let Some(token_type) = &token.token_type.borrow().deref() {
                             if let TokenType::Variable(variable) = &token_type {
-                                let is_same = TokenType::variable_compare(&rule_tokens[rule_token_index], variable.data.borrow().clone());
+                                let is_same = rule_tokens[rule_token_index].matches_ast(&variable.data.borrow());
                                 if is_same {
-                                    match TokenType::get_field_name(&rule_tokens[rule_token_index]) {
+                                    match rule_tokens[rule_token_index].field_name() {
                                         Some(field_name) => fields.insert(field_name.to_string(), token.clone()),
                                         None => None
                                     };
@@ -47,8 +47,8 @@ pub fn dynamic_type_tokinizer(tokinizer: &mut Tokinizer) {
                                     start_token_index   = target_token_index;
                                 }
                             }
-                            else if token == &rule_tokens[rule_token_index] {
-                                match TokenType::get_field_name(&rule_tokens[rule_token_index]) {
+                            else if rule_tokens[rule_token_index].matches(token) {
+                                match rule_tokens[rule_token_index].field_name() {
                                     Some(field_name) => fields.insert(field_name.to_string(), token.clone()),
                                     None => None
                                 };
@@ -83,16 +83,10 @@ pub fn dynamic_type_tokinizer(tokinizer: &mut Tokinizer) {
                         
                         let value = get_number("value", &fields).unwrap();
                         if let Some(data) = fields.get("type") {
-                            tokinizer.ui_tokens.update_tokens(data.start, data.end, UiTokenType::Symbol2)
+                            tokinizer.ui_tokens.update_tokens(data.start, data.end, UiTokenType::Unit)
                         }
 
-                        tokinizer.token_infos.insert(start_token_index, Rc::new(TokenInfo {
-                            start: text_start_position,
-                            end: text_end_position,
-                            token_type: RefCell::new(Some(TokenType::DynamicType(value, dynamic_type.clone()))),
-                            original_text: "".to_string(),
-                            status: Cell::new(TokenInfoStatus::Active)
-                        }));
+                        tokinizer.token_infos.insert(start_token_index, Rc::new(TokenInfo::new(text_start_position, text_end_position, Some(TokenType::DynamicType(value, dynamic_type.clone())), tokinizer.data.clone())));
                         break;
                     }
                 }
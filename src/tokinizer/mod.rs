@@ -8,22 +8,27 @@ mod regex_tokinizer;
 mod alias_tokinizer;
 mod rule_tokinizer;
 mod dynamic_type_tokinizer;
+mod pattern;
 mod tools;
 
 pub use self::rule_tokinizer::rules::date_rules::small_date;
 
 pub use self::regex_tokinizer::regex_tokinizer;
 pub use self::regex_tokinizer::language_tokinizer;
+pub use self::regex_tokinizer::TokenParser;
+pub use self::regex_tokinizer::KeywordFilter;
 pub use self::alias_tokinizer::alias_tokinizer;
 pub use self::dynamic_type_tokinizer::dynamic_type_tokinizer;
 pub use self::tools::*;
-pub use self::rule_tokinizer::{rule_tokinizer, RuleType, RuleItemList, RULE_FUNCTIONS};
+pub use self::pattern::{PatternToken, PatternType};
+pub use self::rule_tokinizer::{rule_tokinizer, RuleType, RuleItemList, RuleMatcher, RULE_FUNCTIONS};
 
 use core::cell::Cell;
 use core::ops::Deref;
 use core::cell::RefCell;
 
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::string::String;
 use alloc::vec::Vec;
 use alloc::string::ToString;
@@ -33,14 +38,15 @@ use regex::Match;
 use crate::UiTokenType;
 use crate::session::Session;
 use crate::config::SmartCalcConfig;
-use crate::variable::update_token_variables;
+use crate::variable::{update_token_variables, update_reserved_token_variables};
 use crate::{token::ui_token::UiTokenCollection, types::*};
+use crate::compiler::function::is_function;
+use crate::smartcalc::{RuleMatch, TraceStep};
 
 
 pub struct Tokinizer<'a> {
     pub column: u16,
-    pub iter: Vec<char>,
-    pub data: String,
+    pub data: Arc<str>,
     pub index: u16,
     pub indexer: usize,
     pub total: usize,
@@ -50,6 +56,13 @@ pub struct Tokinizer<'a> {
     pub language: String,
     pub token_infos: Vec<Rc<TokenInfo>>,
     pub tokens: Vec<Rc<TokenType>>,
+    pub label: Option<String>,
+    pub rule_matches: Vec<RuleMatch>,
+    /// Byte position of the text in the line, statements after a ';' do not start at the beginning of the line.
+    pub line_offset: usize,
+    /// Error of a matched rule that can not be calculated, the line fails with it instead of a syntax error.
+    pub error: RefCell<Option<String>>,
+    offsets: Vec<usize>
 }
 
 #[derive(Debug)]
@@ -61,67 +74,110 @@ pub enum TokenInfoStatus {
     Removed
 }
 
-#[derive(Debug)]
 #[derive(Clone)]
 pub struct TokenInfo {
     pub start: usize,
     pub end: usize,
     pub token_type: RefCell<Option<TokenType>>,
-    pub original_text: String,
+    /// Text the positions point to, shared by every token of the line.
+    pub source: Arc<str>,
     pub status: Cell<TokenInfoStatus>
 }
 
-unsafe impl Send for TokenInfo {}
-unsafe impl Sync for TokenInfo {}
+impl TokenInfo {
+    pub fn new(start: usize, end: usize, token_type: Option<TokenType>, source: Arc<str>) -> Self {
+        TokenInfo { start, end, token_type: RefCell::new(token_type), source, status: Cell::new(TokenInfoStatus::Active) }
+    }
+
+    /// Text of the token as it is written.
+    pub fn original_text(&self) -> &str {
+        self.source.get(self.start..self.end).unwrap_or_default()
+    }
+}
+
+/* The shared line is not printed for every token, only the text of the token */
+impl core::fmt::Debug for TokenInfo {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("TokenInfo")
+            .field("start", &self.start)
+            .field("end", &self.end)
+            .field("token_type", &self.token_type)
+            .field("original_text", &self.original_text())
+            .field("status", &self.status)
+            .finish()
+    }
+}
 
 impl<'a> Tokinizer<'a> {
     pub fn new(config: &'a SmartCalcConfig, session: &'a Session) -> Tokinizer<'a> {
+        Tokinizer::with_text(config, session, session.current_line(), 0)
+    }
+
+    /* Tokinizer for a part of the current line, positions are relative to the given text */
+    pub fn with_text(config: &'a SmartCalcConfig, session: &'a Session, data: &str, line_offset: usize) -> Tokinizer<'a> {
+        let (data, offsets) = normalize_digits(data);
         Tokinizer {
             column: 0,
-            iter: session.current_line().chars().collect(),
-            data: session.current_line().to_string(),
             index: 0,
             indexer: 0,
-            total: session.current_line().chars().count(),
-            ui_tokens: UiTokenCollection::new(session.current_line()),
+            total: data.chars().count(),
+            ui_tokens: UiTokenCollection::new(&data),
+            data: Arc::from(data),
             config,
             session,
             language: session.get_language(),
             token_infos: Vec::new(),
-            tokens: Vec::new()
+            tokens: Vec::new(),
+            label: None,
+            rule_matches: Vec::new(),
+            line_offset,
+            error: RefCell::new(None),
+            offsets
         }
     }
 
-    pub fn token_infos(config: &'a SmartCalcConfig, session: &'a Session) -> Vec<Rc<TokenInfo>> {
-        let mut tokinizer = Tokinizer {
-            column: 0,
-            iter: session.current_line().chars().collect(),
-            data: session.current_line().to_string(),
-            index: 0,
-            indexer: 0,
-            total: session.current_line().chars().count(),
-            ui_tokens: UiTokenCollection::new(session.current_line()),
-            config,
-            session,
-            language: session.get_language(),
-            token_infos: Vec::new(),
-            tokens: Vec::new()
-        };
+    /* Unicode digits are converted to ASCII before tokinizing, byte positions are mapped back to the given text */
+    pub fn original_position(&self, position: usize) -> usize {
+        match self.offsets.get(position) {
+            Some(position) => *position,
+            None => position
+        }
+    }
+
+    /* Positions are mapped back to the given text, the matched text is kept as it is written */
+    pub fn add_rule_match(&mut self, name: String, start: usize, end: usize) {
+        let text = self.data.get(start..end).unwrap_or_default().to_string();
+        self.rule_matches.push(RuleMatch { name, start: self.original_position(start), end: self.original_position(end), text });
+    }
+
+    /* Patterns are kept in the config and shared between threads, so only the values of the tokens are copied out of the tokinizer */
+    pub fn pattern_tokens(config: &'a SmartCalcConfig, session: &'a Session) -> Vec<PatternToken> {
+        let mut tokinizer = Tokinizer::new(config, session);
 
         language_tokinizer(&mut tokinizer);
         regex_tokinizer(&mut tokinizer);
         alias_tokinizer(&mut tokinizer);
-        tokinizer.token_infos
+        tokinizer.token_infos.iter().map(|token| PatternToken::new(token)).collect()
     }
 
     pub fn tokinize(&mut self) -> bool {
-        language_tokinizer(self);
-        regex_tokinizer(self);
-        alias_tokinizer(self);
+        if !self.session.cached_tokens(self) {
+            language_tokinizer(self);
+            regex_tokinizer(self);
+            alias_tokinizer(self);
+            self.session.cache_tokens(self);
+        }
+
+        self.label_tokinizer();
         update_token_variables(self);
         dynamic_type_tokinizer(self);
+        self.trace_tokens();
         rule_tokinizer(self);
 
+        if update_reserved_token_variables(self) {
+            rule_tokinizer(self);
+        }
+
         /* Post process operations */
         self.token_generator();      
         self.token_cleaner();
@@ -130,6 +186,21 @@ impl<'a> Tokinizer<'a> {
         !self.token_infos.is_empty()
     }
 
+    fn trace_tokens(&self) {
+        if !self.session.is_tracing() {
+            return;
+        }
+
+        for token in self.token_infos.iter().filter(|token| token.status.get() == TokenInfoStatus::Active) {
+            if let Some(token_type) = token.token_type.borrow().as_ref() {
+                self.session.add_trace(|| TraceStep::Token {
+                    text: self.data.get(token.start..token.end).unwrap_or_default().to_string(),
+                    token_type: token_type.type_name()
+                });
+            }
+        }
+    }
+
     pub fn basic_tokinize(&mut self) -> bool {
         regex_tokinizer(self);
         alias_tokinizer(self);
@@ -140,9 +211,30 @@ impl<'a> Tokinizer<'a> {
         !self.token_infos.is_empty()
     }
 
+    /* Leading text followed by ':' or a dash is the label of the result, like rent: $1,500 */
+    fn label_tokinizer(&mut self) {
+        if self.token_infos.iter().any(|token| matches!(token.token_type.borrow().deref(), Some(TokenType::Operator('=')))) {
+            return;
+        }
+
+        let separator = match self.token_infos.iter().position(|token| !matches!(token.token_type.borrow().deref(), Some(TokenType::Text(_)))) {
+            Some(separator) if separator > 0 && separator + 1 < self.token_infos.len() => separator,
+            _ => return
+        };
+
+        if !matches!(self.token_infos[separator].token_type.borrow().deref(), Some(TokenType::Operator(':' | '—' | '–'))) {
+            return;
+        }
+
+        self.label = Some(self.data[self.token_infos[0].start..self.token_infos[separator - 1].end].to_string());
+        for token in self.token_infos.iter().take(separator + 1) {
+            token.status.set(TokenInfoStatus::Removed);
+        }
+    }
+
     pub fn add_token_from_match<'t>(&mut self, capture: &Option<Match<'t>>, token_type: Option<TokenType>) -> bool {
         match capture {
-            Some(content) => self.add_token_location(content.start(), content.end(), token_type, content.as_str().to_string()),
+            Some(content) => self.add_token_location(content.start(), content.end(), token_type),
             None => false
         }
     }
@@ -151,20 +243,15 @@ impl<'a> Tokinizer<'a> {
         self.ui_tokens.add_from_regex_match(capture, token_type)
     }
 
-    pub fn add_token_location(&mut self, start: usize, end: usize, token_type: Option<TokenType>, text: String) -> bool {
+    /* Tokens keep only their positions, the text is read from the line buffer they share */
+    pub fn add_token_location(&mut self, start: usize, end: usize, token_type: Option<TokenType>) -> bool {
         for item in self.token_infos.iter() {
             if (item.start <= start && item.end > start) || (item.start < end && item.end >= end) {
                 return false
             }
         }
 
-        self.token_infos.push(Rc::new(TokenInfo {
-            start,
-            end,
-            token_type: RefCell::new(token_type),
-            original_text: text,
-            status: Cell::new(TokenInfoStatus::Active)
-        }));
+        self.token_infos.push(Rc::new(TokenInfo::new(start, end, token_type, self.data.clone())));
         true
     }
 
@@ -194,6 +281,8 @@ impl<'a> Tokinizer<'a> {
 
         while index < self.tokens.len() {
             match self.tokens[index].deref() {
+                /* Function names are kept for the call syntax and for aggregating a list */
+                TokenType::Text(name) if is_function(name) && (matches!(self.tokens.get(index + 1).map(|token| token.deref()), Some(TokenType::Operator('('))) || matches!(index.checked_sub(1).and_then(|previous| self.tokens.get(previous)).map(|token| token.deref()), Some(TokenType::Operator(']') | TokenType::Variable(_)))) => index += 1,
                 TokenType::Text(_) => {
                     self.tokens.remove(index);
                 },
@@ -212,6 +301,7 @@ impl<'a> Tokinizer<'a> {
         for (token_index, token) in self.tokens.iter().enumerate() {
             match token.deref() {
                 TokenType::Operator('=') | 
+                TokenType::Operator('[') |
                 TokenType::Operator('(')=> {
                     index = token_index as usize + 1;
                     break;
@@ -224,19 +314,25 @@ impl<'a> Tokinizer<'a> {
             return;
         }
 
-        if let TokenType::Operator('(') = self.tokens[index].deref() {
+        if let TokenType::Operator('(' | '[') = self.tokens[index].deref() {
             index += 1;
         }
 
         let mut operator_required = false;
 
-        if let TokenType::Operator(_) = self.tokens[index].deref() {
-            self.tokens.insert(index, Rc::new(TokenType::Number(0.0, NumberType::Decimal)));
-        }
+        /* Leading sign is a unary operator, the others need a left side */
+        match self.tokens[index].deref() {
+            TokenType::Operator('-' | '+') => (),
+            TokenType::Operator(_) => self.tokens.insert(index, Rc::new(TokenType::Number(0.0, NumberType::Decimal))),
+            _ => ()
+        };
 
         while index < self.tokens.len() {
             match self.tokens[index].deref() {
                 TokenType::Operator(_) => operator_required = false,
+
+                /* Aggregating function after a value, like x sum */
+                TokenType::Text(name) if is_function(name) && !matches!(self.tokens.get(index + 1).map(|token| token.deref()), Some(TokenType::Operator('('))) => (),
                 _ => {
                     if operator_required {
                         self.tokens.insert(index, Rc::new(TokenType::Operator('+')));
@@ -0,0 +1,168 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::ops::Deref;
+use alloc::sync::Arc;
+use alloc::string::String;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use crate::interner::Symbol;
+use crate::types::{CurrencyInfo, FieldType, SmartCalcAstType, TimeOffset, TokenType};
+
+use super::{TokenInfo, TokenInfoStatus};
+
+/// Value of a pattern token, only the values a line token can be compared with are kept.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PatternType {
+    Text(Symbol),
+    Operator(char),
+    Field(Arc<FieldType>),
+    Number(f64),
+    Percent(f64),
+    Date(NaiveDate, TimeOffset),
+    Time(NaiveDateTime, TimeOffset),
+    Duration(Duration),
+    Money(f64, Arc<CurrencyInfo>),
+    Timezone(String, i32)
+}
+
+impl PatternType {
+    pub fn from_token(token_type: &TokenType) -> Option<Self> {
+        match token_type {
+            TokenType::Text(text) => Some(PatternType::Text(text.clone())),
+            TokenType::Operator(operator) => Some(PatternType::Operator(*operator)),
+            TokenType::Field(field) => Some(PatternType::Field(field.clone())),
+            TokenType::Number(number, _) => Some(PatternType::Number(*number)),
+            TokenType::Percent(percent) => Some(PatternType::Percent(*percent)),
+            TokenType::Date(date, tz) => Some(PatternType::Date(*date, tz.clone())),
+            TokenType::Time(time, tz) => Some(PatternType::Time(*time, tz.clone())),
+            TokenType::Duration(duration) => Some(PatternType::Duration(*duration)),
+            TokenType::Money(price, currency) => Some(PatternType::Money(*price, currency.clone())),
+            TokenType::Timezone(timezone, offset) => Some(PatternType::Timezone(timezone.clone(), *offset)),
+            _ => None
+        }
+    }
+}
+
+/// Token of a rule or a dynamic type pattern.
+///
+/// Patterns are kept in the configuration and shared between threads, so a pattern token is never changed while matching.
+/// Tokens without a value never match.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternToken {
+    pub start: usize,
+    pub end: usize,
+    pub token_type: Option<PatternType>
+}
+
+impl PatternToken {
+    pub fn new(token: &TokenInfo) -> Self {
+        let token_type = match token.status.get() {
+            TokenInfoStatus::Active => token.token_type.borrow().as_ref().and_then(PatternType::from_token),
+            TokenInfoStatus::Removed => None
+        };
+
+        PatternToken { start: token.start, end: token.end, token_type }
+    }
+
+    pub fn field(&self) -> Option<&FieldType> {
+        match &self.token_type {
+            Some(PatternType::Field(field)) => Some(field.deref()),
+            _ => None
+        }
+    }
+
+    pub fn field_name(&self) -> Option<&str> {
+        match self.field()? {
+            FieldType::Text(field_name, _) => Some(field_name),
+            FieldType::DateTime(field_name) => Some(field_name),
+            FieldType::Date(field_name) => Some(field_name),
+            FieldType::Time(field_name) => Some(field_name),
+            FieldType::Money(field_name) => Some(field_name),
+            FieldType::Percent(field_name) => Some(field_name),
+            FieldType::Number(field_name) => Some(field_name),
+            FieldType::Month(field_name) => Some(field_name),
+            FieldType::Duration(field_name) => Some(field_name),
+            FieldType::Group(field_name, _) => Some(field_name),
+            FieldType::TypeGroup(_, field_name) => Some(field_name),
+            FieldType::Timezone(field_name) => Some(field_name),
+            FieldType::DynamicType(field_name, _) => Some(field_name),
+            FieldType::Range(field_name) => Some(field_name)
+        }
+    }
+
+    /* Fields match a type, other tokens match the same value */
+    pub fn matches(&self, token: &TokenInfo) -> bool {
+        if token.status.get() == TokenInfoStatus::Removed {
+            return false;
+        }
+
+        match (token.token_type.borrow().as_ref(), &self.token_type) {
+            (Some(token_type), Some(pattern_type)) => match (token_type, pattern_type) {
+                (_, PatternType::Field(field)) => token_type.field_compare(field),
+                (TokenType::Text(l_value), PatternType::Text(r_value)) => l_value == r_value,
+                (TokenType::Number(l_value, _), PatternType::Number(r_value)) => l_value == r_value,
+                (TokenType::Percent(l_value), PatternType::Percent(r_value)) => l_value == r_value,
+                (TokenType::Operator(l_value), PatternType::Operator(r_value)) => l_value == r_value,
+                (TokenType::Date(l_value, l_tz), PatternType::Date(r_value, r_tz)) => l_value == r_value && l_tz == r_tz,
+                (TokenType::Time(l_value, l_tz), PatternType::Time(r_value, r_tz)) => l_value == r_value && l_tz == r_tz,
+                (TokenType::Duration(l_value), PatternType::Duration(r_value)) => l_value == r_value,
+                (TokenType::Money(l_value, l_symbol), PatternType::Money(r_value, r_symbol)) => l_value == r_value && l_symbol == r_symbol,
+                (TokenType::Timezone(l_value, l_offset), PatternType::Timezone(r_value, r_offset)) => l_value == r_value && l_offset == r_offset,
+                (_, _) => false
+            },
+            _ => false
+        }
+    }
+
+    /* Variables are compared with their values */
+    pub fn matches_ast(&self, ast: &SmartCalcAstType) -> bool {
+        match (&self.token_type, ast) {
+            (Some(PatternType::Field(field)), _) => ast.field_compare(field),
+            (Some(PatternType::Text(l_value)), SmartCalcAstType::Symbol(r_value)) => l_value.matches(r_value),
+            (Some(PatternType::Timezone(l_value, l_offset)), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(l_value.clone(), *l_offset)),
+            (Some(PatternType::Number(l_value)), SmartCalcAstType::Item(r_value)) => r_value.is_same(l_value),
+            (Some(PatternType::Percent(l_value)), SmartCalcAstType::Item(r_value)) => r_value.is_same(l_value),
+            (Some(PatternType::Duration(l_value)), SmartCalcAstType::Item(r_value)) => r_value.is_same(l_value),
+            (Some(PatternType::Time(l_value, l_tz)), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(*l_value, l_tz.clone())),
+            (Some(PatternType::Money(l_value, l_symbol)), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(*l_value, l_symbol.clone())),
+            (Some(PatternType::Date(l_value, l_tz)), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(*l_value, l_tz.clone())),
+            (_, _) => false
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn pattern_token_test() {
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use crate::config::SmartCalcConfig;
+    use crate::session::Session;
+    use crate::tokinizer::{Tokinizer, language_tokinizer, regex_tokinizer};
+
+    fn assert_sync<T: Send + Sync>() {}
+    assert_sync::<PatternToken>();
+
+    let config = SmartCalcConfig::default();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("{PERCENT:p} of {NUMBER:total}".to_string());
+    let pattern = Tokinizer::pattern_tokens(&config, &session);
+
+    assert_eq!(pattern.iter().map(|token| token.field_name()).collect::<Vec<_>>(), [Some("p"), None, Some("total")]);
+    assert_eq!(pattern[1].token_type, Some(PatternType::Text("of".into())));
+
+    session.set_text("10% OF 200".to_string());
+    let mut tokinizer = Tokinizer::new(&config, &session);
+    language_tokinizer(&mut tokinizer);
+    regex_tokinizer(&mut tokinizer);
+    let line = &tokinizer.token_infos;
+
+    assert!(pattern.iter().zip(line.iter()).all(|(pattern_token, token)| pattern_token.matches(token)));
+    assert!(!pattern[0].matches(&line[2]));
+    assert!(pattern[1].matches_ast(&SmartCalcAstType::Symbol("Of".to_string())));
+}
@@ -4,20 +4,18 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::string::String;
 use alloc::string::ToString;
 use alloc::vec::Vec;
-use alloc::borrow::ToOwned;
 use chrono::NaiveDateTime;
-use chrono::Utc;
 
 use crate::config::SmartCalcConfig;
 use crate::types::*;
 use crate::tokinizer::Tokinizer;
+use crate::token::ui_token::UiTokenType;
 use chrono::NaiveTime;
 use regex::Regex;
 
-pub fn get_atom(config: &SmartCalcConfig, data: &str, group_item: &[Regex]) -> Vec<(usize, usize, Option<TokenType>, String)> {
+pub fn get_atom(config: &SmartCalcConfig, data: &str, group_item: &[Regex]) -> Vec<(usize, usize, Option<TokenType>)> {
     let mut atoms = Vec::new();
 
     for re in group_item.iter() {
@@ -28,7 +26,7 @@ pub fn get_atom(config: &SmartCalcConfig, data: &str, group_item: &[Regex]) -> V
             let token_type = match atom_type {
                 "TIME" => {
                     let seconds = data.parse::<u32>().unwrap();
-                    let date = Utc::now().naive_local().date();
+                    let date = config.get_today();
                     let time = NaiveTime::from_num_seconds_from_midnight(seconds, 0);
                     let date_time = NaiveDateTime::new(date, time);
                     
@@ -59,17 +57,22 @@ pub fn get_atom(config: &SmartCalcConfig, data: &str, group_item: &[Regex]) -> V
                 }
             };
 
-            atoms.push((capture.get(0).unwrap().start(), capture.get(0).unwrap().end(), Some(token_type), capture.get(0).unwrap().as_str().to_string()))
+            atoms.push((capture.get(0).unwrap().start(), capture.get(0).unwrap().end(), Some(token_type)))
         }
     }
     atoms
 }
 
 
-pub fn atom_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
-    let atoms =  get_atom(config, &tokinizer.data.to_owned(), group_item);
-    for (start, end, token_type, text) in atoms {
-        tokinizer.add_token_location(start, end, token_type, text);
+pub fn atom_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
+    let atoms =  get_atom(config, data, group_item);
+    for (start, end, token_type) in atoms {
+        let ui_type = token_type.as_ref().map(UiTokenType::from_token);
+        if tokinizer.add_token_location(start, end, token_type) {
+            if let Some(ui_type) = ui_type {
+                tokinizer.ui_tokens.add_from_location(start, end, ui_type);
+            }
+        }
     }
 }
 
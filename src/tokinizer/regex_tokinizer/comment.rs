@@ -4,15 +4,14 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::borrow::ToOwned;
 use regex::Regex;
 use crate::config::SmartCalcConfig;
 use crate::tokinizer::Tokinizer;
 use crate::token::ui_token::UiTokenType;
 
-pub fn comment_regex_parser(_: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+pub fn comment_regex_parser(_: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
+        for capture in re.captures_iter(data) {
             if tokinizer.add_token_from_match(&capture.get(0), None) {
                 tokinizer.add_uitoken_from_match(capture.get(0), UiTokenType::Comment);
             }
@@ -67,3 +66,20 @@ fn comment_test_3() {
     regex_tokinizer(&mut tokinizer_mut);
     assert_eq!(tokinizer_mut.ui_tokens.len(), 1);
 }
+
+#[cfg(test)]
+#[test]
+fn comment_test_4() {
+    use crate::alloc::string::ToString;
+    use crate::tokinizer::regex_tokinizer;
+    use crate::tokinizer::test::setup_tokinizer;
+    use crate::config::SmartCalcConfig;
+    use crate::session::Session;
+    let mut session = Session::new();
+    let config = SmartCalcConfig::default();
+    let mut tokinizer_mut = setup_tokinizer("1 /* 2 # */ + 3 // 4 /* 5".to_string(), &mut session, &config);
+
+    regex_tokinizer(&mut tokinizer_mut);
+    let comments = tokinizer_mut.ui_tokens.iter().filter(|token| token.ui_type == UiTokenType::Comment).map(|token| (token.start, token.end)).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(comments, [(2, 11), (16, 25)]);
+}
@@ -0,0 +1,124 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use alloc::format;
+use alloc::string::ToString;
+use chrono::{Duration, NaiveDate, NaiveTime};
+use regex::{Captures, Regex};
+use crate::config::SmartCalcConfig;
+use crate::tokinizer::Tokinizer;
+use crate::types::{TokenType, TimeOffset};
+use crate::token::ui_token::{UiTokenType};
+
+fn get_capture_number(capture: &Captures, name: &str) -> u32 {
+    match capture.name(name) {
+        Some(data) => data.as_str().parse::<u32>().unwrap_or(0),
+        None => 0
+    }
+}
+
+fn parse_offset(config: &SmartCalcConfig, capture: &Captures) -> Option<TimeOffset> {
+    let timezone = match capture.name("timezone") {
+        Some(timezone) => timezone.as_str(),
+        None => return Some(config.get_time_offset())
+    };
+
+    if timezone == "Z" {
+        return Some(TimeOffset {
+            name: "UTC".to_string(),
+            offset: 0
+        });
+    }
+
+    let hour = timezone.get(1..3)?.parse::<i32>().ok()?;
+    let minute = timezone.get(timezone.len() - 2..)?.parse::<i32>().ok()?;
+    let offset = match timezone.starts_with('-') {
+        true => -(hour * 60 + minute),
+        false => hour * 60 + minute
+    };
+
+    Some(TimeOffset {
+        name: format!("GMT{}{:02}:{:02}", &timezone[..1], hour, minute),
+        offset
+    })
+}
+
+pub fn date_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
+    for re in group_item.iter() {
+        for capture in re.captures_iter(data) {
+            let date = match NaiveDate::from_ymd_opt(get_capture_number(&capture, "year") as i32, get_capture_number(&capture, "month"), get_capture_number(&capture, "day")) {
+                Some(date) => date,
+                None => continue
+            };
+
+            let token = match capture.name("hour") {
+                Some(_) => {
+                    let time = match NaiveTime::from_hms_opt(get_capture_number(&capture, "hour"), get_capture_number(&capture, "minute"), get_capture_number(&capture, "second")) {
+                        Some(time) => time,
+                        None => continue
+                    };
+
+                    let time_offset = match parse_offset(config, &capture) {
+                        Some(time_offset) => time_offset,
+                        None => continue
+                    };
+
+                    /* Date time values are kept as UTC */
+                    let date_time = date.and_time(time) - Duration::minutes(time_offset.offset as i64);
+                    TokenType::DateTime(date_time, time_offset)
+                },
+                None => TokenType::Date(date, config.get_time_offset())
+            };
+
+            let ui_type = UiTokenType::from_token(&token);
+            if tokinizer.add_token_from_match(&capture.get(0), Some(token)) {
+                tokinizer.add_uitoken_from_match(capture.get(0), ui_type);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn date_test() {
+    use core::ops::Deref;
+    use alloc::string::ToString;
+    use crate::tokinizer::regex_tokinizer;
+    use crate::tokinizer::test::setup_tokinizer;
+    use crate::config::SmartCalcConfig;
+    use crate::session::Session;
+    let mut session = Session::new();
+    let config = SmartCalcConfig::default();
+    let mut tokinizer_mut = setup_tokinizer("2024-06-15 2024-06-15T13:45:00Z 2024-06-15 13:45 2024-06-15T13:45:10+03:00 2024-02-30".to_string(), &mut session, &config);
+
+    regex_tokinizer(&mut tokinizer_mut);
+    let tokens = &tokinizer_mut.token_infos;
+
+    assert_eq!(tokens[0].start, 0);
+    assert_eq!(tokens[0].end, 10);
+    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Date(NaiveDate::from_ymd_opt(2024, 6, 15).unwrap(), config.get_time_offset())));
+
+    assert_eq!(tokens[1].start, 11);
+    assert_eq!(tokens[1].end, 31);
+    assert_eq!(tokens[1].token_type.borrow().deref(), &Some(TokenType::DateTime(NaiveDate::from_ymd_opt(2024, 6, 15).unwrap().and_hms_opt(13, 45, 0).unwrap(), TimeOffset {
+        name: "UTC".to_string(),
+        offset: 0
+    })));
+
+    assert_eq!(tokens[2].start, 32);
+    assert_eq!(tokens[2].end, 48);
+    assert_eq!(tokens[2].token_type.borrow().deref(), &Some(TokenType::DateTime(NaiveDate::from_ymd_opt(2024, 6, 15).unwrap().and_hms_opt(13, 45, 0).unwrap(), config.get_time_offset())));
+
+    assert_eq!(tokens[3].start, 49);
+    assert_eq!(tokens[3].end, 74);
+    assert_eq!(tokens[3].token_type.borrow().deref(), &Some(TokenType::DateTime(NaiveDate::from_ymd_opt(2024, 6, 15).unwrap().and_hms_opt(10, 45, 10).unwrap(), TimeOffset {
+        name: "GMT+03:00".to_string(),
+        offset: 180
+    })));
+
+    /* Invalid dates are left to the other parsers */
+    assert!(tokens.iter().all(|token| token.start != 75 || token.end != 85));
+}
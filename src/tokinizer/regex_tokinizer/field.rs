@@ -4,12 +4,12 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::string::ToString;
-use alloc::borrow::ToOwned;
 use crate::config::SmartCalcConfig;
 use crate::types::*;
 use crate::tokinizer::Tokinizer;
+use crate::token::ui_token::UiTokenType;
 use regex::{Regex, Captures};
 
 fn get_field_type<'t>(config: &SmartCalcConfig, type_name: &str, value: &str, language: &str, capture: &Captures<'t>) -> Option<FieldType> {
@@ -23,12 +23,13 @@ fn get_field_type<'t>(config: &SmartCalcConfig, type_name: &str, value: &str, la
         "MONTH" => Some(FieldType::Month(value.to_string())),
         "TIMEZONE" => Some(FieldType::Timezone(value.to_string())),
         "DURATION" => Some(FieldType::Duration(value.to_string())),
+        "RANGE" => Some(FieldType::Range(value.to_string())),
         "DYNAMIC_TYPE" => {
             let expected  = capture.name("EXTRA").map(|data| data.as_str().to_string());
             Some(FieldType::DynamicType(value.to_string(), expected))
         },
         "TEXT" => {
-            let expected  = capture.name("EXTRA").map(|data| data.as_str().to_string());
+            let expected  = capture.name("EXTRA").map(|data| config.interner.symbol(data.as_str()));
             Some(FieldType::Text(value.to_string(), expected))
         },
         "GROUP" => {
@@ -37,7 +38,7 @@ fn get_field_type<'t>(config: &SmartCalcConfig, type_name: &str, value: &str, la
                 None => "".to_string()
             };
             
-            config.word_group.get(language).unwrap().get(&group).map(|group_items| FieldType::Group(value.to_string(), group_items.to_vec()))
+            config.word_group.get(language).unwrap().get(&group).map(|group_items| FieldType::Group(value.to_string(), group_items.iter().map(|item| config.interner.symbol(item)).collect()))
         },
         _ => match config.json_data.type_group.get(type_name) {
             Some(group) => Some(FieldType::TypeGroup(group.to_vec(), value.to_string())),
@@ -49,14 +50,16 @@ fn get_field_type<'t>(config: &SmartCalcConfig, type_name: &str, value: &str, la
     }
 }
 
-pub fn field_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+pub fn field_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
+        for capture in re.captures_iter(data) {
             let field_type = capture.name("FIELD").unwrap().as_str();
             let name  = capture.name("NAME").unwrap().as_str();
 
             if let Some(field) = get_field_type(config, field_type, name, &tokinizer.language, &capture) {
-                tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Field(Rc::new(field))));
+                if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Field(Arc::new(field)))) {
+                    tokinizer.add_uitoken_from_match(capture.get(0), UiTokenType::Symbol1);
+                }
             }
         }
     }
@@ -80,11 +83,11 @@ fn field_test() {
     assert_eq!(tokens.len(), 2);
     assert_eq!(tokens[0].start, 0);
     assert_eq!(tokens[0].end, 14);
-    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Field(Rc::new(FieldType::Text("merhaba".to_string(), None)))));
+    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Field(Arc::new(FieldType::Text("merhaba".to_string(), None)))));
 
-    assert_ne!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Field(Rc::new(FieldType::Text("test".to_string(), None)))));
+    assert_ne!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Field(Arc::new(FieldType::Text("test".to_string(), None)))));
 
     assert_eq!(tokens[1].start, 15);
     assert_eq!(tokens[1].end, 32);
-    assert_eq!(tokens[1].token_type.borrow().deref(), &Some(TokenType::Field(Rc::new(FieldType::Percent("percent".to_string())))));
+    assert_eq!(tokens[1].token_type.borrow().deref(), &Some(TokenType::Field(Arc::new(FieldType::Percent("percent".to_string())))));
 }
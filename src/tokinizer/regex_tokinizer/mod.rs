@@ -5,9 +5,11 @@
  */
 
 
-use alloc::string::ToString;
 use alloc::vec;
 use alloc::vec::Vec;
+use alloc::string::String;
+use alloc::collections::{BTreeMap, BTreeSet};
+use aho_corasick::AhoCorasick;
 use regex::Regex;
 use lazy_static::*;
 
@@ -19,14 +21,17 @@ mod field;
 mod percent;
 mod atom;
 mod time;
+mod date;
 mod money;
 mod comment;
 mod month;
 mod timezone;
+mod range;
 
 use crate::SmartCalcConfig;
 
 pub use self::time::time_regex_parser;
+pub use self::date::date_regex_parser;
 pub use self::number::number_regex_parser;
 pub use self::percent::percent_regex_parser;
 pub use self::money::money_regex_parser;
@@ -38,12 +43,14 @@ pub use self::comment::comment_regex_parser;
 pub use self::timezone::timezone_regex_parser;
 pub use self::month::month_parser;
 pub use self::operator::operator_regex_parser;
+pub use self::range::range_regex_parser;
 
 use super::Tokinizer;
 
 
-pub type RegexParser = fn(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]);
+pub type RegexParser = fn(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]);
 pub type Parser      = fn(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str);
+pub type TokenParser = fn(config: &SmartCalcConfig, tokinizer: &mut Tokinizer);
 
 
 lazy_static! {
@@ -51,8 +58,10 @@ lazy_static! {
         let m = vec![
         ("comment",    comment_regex_parser    as RegexParser),
         ("field",      field_regex_parser      as RegexParser),
+        ("range",      range_regex_parser      as RegexParser),
         ("money",      money_regex_parser      as RegexParser),
         ("atom",       atom_regex_parser       as RegexParser),
+        ("date",       date_regex_parser       as RegexParser),
         ("percent",    percent_regex_parser    as RegexParser),
         ("timezone",   timezone_regex_parser   as RegexParser),
         ("time",       time_regex_parser       as RegexParser),
@@ -71,11 +80,69 @@ lazy_static! {
     };
 }
 
+/* Keywords one of them has to be in the line for the group to match, groups without keywords are always matched */
+#[derive(Clone, Default)]
+pub struct KeywordFilter {
+    matcher: Option<AhoCorasick>,
+    keyword_groups: Vec<String>,
+    filtered_groups: BTreeSet<String>
+}
+
+impl KeywordFilter {
+    pub fn new(keywords: &BTreeMap<String, Vec<String>>) -> Self {
+        let mut patterns = Vec::new();
+        let mut keyword_groups = Vec::new();
+        for (group, group_keywords) in keywords.iter() {
+            for keyword in group_keywords.iter().filter(|keyword| !keyword.is_empty()) {
+                patterns.push(keyword.to_lowercase());
+                keyword_groups.push(group.clone());
+            }
+        }
+
+        let matcher = match AhoCorasick::new(&patterns) {
+            Ok(matcher) => Some(matcher),
+            Err(error) => {
+                log::error!("Keyword filter error {}", error);
+                None
+            }
+        };
+
+        KeywordFilter { matcher, keyword_groups, filtered_groups: keywords.keys().cloned().collect() }
+    }
+
+    /* Groups that can not match the line, the line is scanned once for the keywords of every group */
+    pub fn skipped_groups(&self, data: &str) -> BTreeSet<&str> {
+        let matcher = match &self.matcher {
+            Some(matcher) => matcher,
+            None => return BTreeSet::new()
+        };
+
+        let mut skipped = self.filtered_groups.iter().map(|group| &group[..]).collect::<BTreeSet<_>>();
+        for keyword in matcher.find_overlapping_iter(&data.to_lowercase()) {
+            skipped.remove(&self.keyword_groups[keyword.pattern().as_usize()][..]);
+            if skipped.is_empty() {
+                break;
+            }
+        }
+
+        skipped
+    }
+}
+
 pub fn regex_tokinizer(tokinizer: &mut Tokinizer) {
-    /* Token parser with regex */
+    /* Patterns are compiled with the config, the line buffer is shared by every parser */
+    let data = tokinizer.data.clone();
+    let skipped_groups = tokinizer.config.keyword_filter.skipped_groups(&data);
     for (key, func) in TOKEN_REGEX_PARSER.iter() {
-        if let Some(items) = tokinizer.config.token_parse_regex.get(&key.to_string()) { 
-            func(tokinizer.config, tokinizer, items) 
+        if let Some(items) = tokinizer.config.token_parse_regex.get(*key).filter(|_| !skipped_groups.contains(key)) {
+            func(tokinizer.config, tokinizer, &data, items)
+        }
+
+        /* Embedder parsers run before the builtin literals, only comments can hide them */
+        if *key == "comment" {
+            for (_, parser) in tokinizer.config.token_parsers.iter() {
+                parser(tokinizer.config, tokinizer);
+            }
         }
     }
     
@@ -90,3 +157,24 @@ pub fn language_tokinizer(tokinizer: &mut Tokinizer) {
 
     tokinizer.cleanup_token_infos();
 }
+
+#[cfg(test)]
+#[test]
+fn keyword_filter_test() {
+    use alloc::string::ToString;
+    use crate::config::SmartCalcConfig;
+
+    let config = SmartCalcConfig::default();
+    let skipped = config.keyword_filter.skipped_groups("12 + 34 * 2");
+    assert!(!skipped.contains("number"));
+    assert!(skipped.contains("money") && skipped.contains("date") && skipped.contains("time") && skipped.contains("comment"));
+    assert!(!skipped.contains("text") && !skipped.contains("operator"));
+
+    let skipped = config.keyword_filter.skipped_groups("$10 at 3PM EST # note");
+    assert!(!skipped.contains("money") && !skipped.contains("time") && !skipped.contains("timezone") && !skipped.contains("comment"));
+    assert!(skipped.contains("percent") && skipped.contains("range"));
+
+    let mut keywords = BTreeMap::new();
+    keywords.insert("percent".to_string(), vec!["%".to_string()]);
+    assert_eq!(KeywordFilter::new(&keywords).skipped_groups("5 + 5").into_iter().collect::<Vec<_>>(), ["percent"]);
+}
@@ -4,19 +4,17 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::string::ToString;
 use regex::Regex;
-use alloc::borrow::ToOwned;
 use crate::config::SmartCalcConfig;
 use crate::tokinizer::{Tokinizer, read_currency};
 use crate::types::{TokenType};
 use crate::token::ui_token::{UiTokenType};
 
-pub fn money_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+pub fn money_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
+        for capture in re.captures_iter(data) {
             /* Check price value */
-            let price = match capture.name("PRICE").unwrap().as_str().replace(&config.thousand_separator[..], "").replace(&config.decimal_seperator[..], ".").parse::<f64>() {
+            let price = match capture.name("PRICE").unwrap().as_str().replace(config.get_thousand_separator(&tokinizer.language), "").replace(config.get_decimal_seperator(&tokinizer.language), ".").parse::<f64>() {
                 Ok(price) => match capture.name("NOTATION") {
                     Some(notation) => price * match notation.as_str() {
                         "k" | "K" => 1_000.0,
@@ -49,7 +47,7 @@ pub fn money_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, g
                 _ => capture.name("CURRENCY").unwrap().end()
             };
 
-            if tokinizer.add_token_location(capture.get(0).unwrap().start(), end, Some(TokenType::Money(price, currency.clone())), capture.name("PRICE").unwrap().as_str().to_string()) {
+            if tokinizer.add_token_location(capture.get(0).unwrap().start(), end, Some(TokenType::Money(price, currency.clone()))) {
                 tokinizer.add_uitoken_from_match(capture.name("PRICE"), UiTokenType::Number);
                 tokinizer.add_uitoken_from_match(capture.name("CURRENCY"), UiTokenType::Symbol1);
                 tokinizer.add_uitoken_from_match(capture.name("NOTATION"), UiTokenType::Symbol2);
@@ -61,6 +59,7 @@ pub fn money_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, g
 #[cfg(test)]
 #[test]
 fn money_test_1() {
+    use alloc::string::ToString;
     use core::ops::Deref;
     use crate::tokinizer::regex_tokinizer;
     use crate::tokinizer::test::setup_tokinizer;
@@ -102,6 +101,7 @@ fn money_test_1() {
 #[cfg(test)]
 #[test]
 fn money_test_2() {
+    use alloc::string::ToString;
     use crate::tokinizer::regex_tokinizer;
     use crate::tokinizer::test::setup_tokinizer;
     use core::ops::Deref;
@@ -119,3 +119,29 @@ fn money_test_2() {
     assert_eq!(tokens[0].end, 3);
     assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Money(2000.0, config.get_currency("usd".to_string()).unwrap())));
 }
+
+#[cfg(test)]
+#[test]
+fn money_test_3() {
+    use alloc::string::ToString;
+    use crate::tokinizer::regex_tokinizer;
+    use crate::tokinizer::test::setup_tokinizer;
+    use core::ops::Deref;
+    use crate::config::SmartCalcConfig;
+    use crate::session::Session;
+    let mut session = Session::new();
+    let config = SmartCalcConfig::default();
+    let mut tokinizer_mut = setup_tokinizer("₿2 3Ξ 100₺ ₹50 ₩1000 2 xbt".to_string(), &mut session, &config);
+
+    regex_tokinizer(&mut tokinizer_mut);
+    let tokens = &tokinizer_mut.token_infos;
+    let currencies = ["xbt", "eth", "try", "inr", "krw", "xbt"];
+
+    assert_eq!(tokens.len(), currencies.len());
+    for (token, currency) in tokens.iter().zip(currencies.iter()) {
+        match token.token_type.borrow().deref() {
+            Some(TokenType::Money(_, token_currency)) => assert_eq!(token_currency, &config.get_currency(currency.to_string()).unwrap()),
+            _ => panic!("Money token expected")
+        };
+    }
+}
@@ -4,17 +4,15 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::string::ToString;
-use alloc::borrow::ToOwned;
 use crate::config::SmartCalcConfig;
 use crate::types::*;
 use crate::tokinizer::{Tokinizer};
 use regex::Regex;
 use crate::token::ui_token::{UiTokenType};
 
-pub fn number_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+pub fn number_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
+        for capture in re.captures_iter(data) {
             let mut parse_end = 0;
             let mut number_match = None;
             let mut notation_match = None;
@@ -43,7 +41,7 @@ pub fn number_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer,
             }
             else if let Some(decimal) = capture.name("DECIMAL") {
                 parse_end = decimal.end();
-                number = match decimal.as_str().replace(&config.thousand_separator[..], "").replace(&config.decimal_seperator[..], ".").parse::<f64>() {
+                number = match decimal.as_str().replace(config.get_thousand_separator(&tokinizer.language), "").replace(config.get_decimal_seperator(&tokinizer.language), ".").parse::<f64>() {
                     Ok(num) => {
                         number_match = Some(decimal);
                         match capture.name("NOTATION") {
@@ -67,7 +65,7 @@ pub fn number_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer,
                 };
             }
 
-            if tokinizer.add_token_location(capture.get(0).unwrap().start(), parse_end, Some(TokenType::Number(number, number_type)), capture.get(0).unwrap().as_str().to_string()) {
+            if tokinizer.add_token_location(capture.get(0).unwrap().start(), parse_end, Some(TokenType::Number(number, number_type))) {
                 tokinizer.add_uitoken_from_match(number_match, UiTokenType::Number);
                 tokinizer.add_uitoken_from_match(notation_match, UiTokenType::Symbol2);
             }
@@ -79,6 +77,7 @@ pub fn number_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer,
 #[cfg(test)]
 #[test]
 fn number_test_1() {
+    use alloc::string::ToString;
     use core::ops::Deref;
     use crate::tokinizer::regex_tokinizer;
     use crate::tokinizer::test::setup_tokinizer;
@@ -113,6 +112,7 @@ fn number_test_1() {
 #[cfg(test)]
 #[test]
 fn number_test_2() {
+    use alloc::string::ToString;
     use core::ops::Deref;
     use crate::tokinizer::regex_tokinizer;
     use crate::tokinizer::test::setup_tokinizer;
@@ -154,6 +154,7 @@ fn number_test_2() {
 #[cfg(test)]
 #[test]
 fn number_test_3() {
+    use alloc::string::ToString;
     use core::ops::Deref;
     use crate::tokinizer::regex_tokinizer;
     use crate::tokinizer::test::setup_tokinizer;
@@ -196,6 +197,7 @@ fn number_test_3() {
 #[cfg(test)]
 #[test]
 fn number_test_4() {
+    use alloc::string::ToString;
     use core::ops::Deref;
     use crate::tokinizer::regex_tokinizer;
     use crate::tokinizer::test::setup_tokinizer;
@@ -4,17 +4,26 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::borrow::ToOwned;
 use regex::Regex;
 use crate::config::SmartCalcConfig;
 use crate::{types::*};
 use crate::tokinizer::Tokinizer;
 use crate::token::ui_token::UiTokenType;
 
-pub fn operator_regex_parser(_: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+/* Math symbols from documents and web pages are used as their ASCII operators */
+fn operator_char(operator: char) -> char {
+    match operator {
+        '\u{2212}' => '-',
+        '\u{00D7}' | '\u{00B7}' => '*',
+        '\u{00F7}' => '/',
+        _ => operator
+    }
+}
+
+pub fn operator_regex_parser(_: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
-            if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Operator(capture.get(0).unwrap().as_str().chars().next().unwrap())))  {
+        for capture in re.captures_iter(data) {
+            if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Operator(operator_char(capture.get(0).unwrap().as_str().chars().next().unwrap()))))  {
                 tokinizer.add_uitoken_from_match(capture.get(0), UiTokenType::Operator);
             }
         }
@@ -48,7 +57,7 @@ mod tests {
         
         assert_eq!(tokinizer.token_infos[1].start, 3);
         assert_eq!(tokinizer.token_infos[1].end, 10);
-        assert_eq!(tokinizer.token_infos[1].token_type.borrow().deref(), &Some(TokenType::Text("merhaba".to_string())));
+        assert_eq!(tokinizer.token_infos[1].token_type.borrow().deref(), &Some(TokenType::Text("merhaba".into())));
     }
 
     #[cfg(test)]
@@ -91,4 +100,28 @@ mod tests {
         assert_eq!(tokinizer.token_infos[5].end, 10);
         assert_eq!(tokinizer.token_infos[5].token_type.borrow().deref(), &Some(TokenType::Operator(',')));
     }
-}
\ No newline at end of file
+
+    #[cfg(test)]
+    #[test]
+    fn operator_test_3() {
+        use core::ops::Deref;
+        use crate::tokinizer::regex_tokinizer;
+        use crate::tokinizer::test::setup_tokinizer;
+        use alloc::string::ToString;
+        use crate::config::SmartCalcConfig;
+        use crate::session::Session;
+        let mut session = Session::new();
+        let config = SmartCalcConfig::default();
+        let mut tokinizer = setup_tokinizer("− × ÷ ·".to_string(), &mut session, &config);
+
+        regex_tokinizer(&mut tokinizer);
+
+        assert_eq!(tokinizer.token_infos.len(), 4);
+        assert_eq!(tokinizer.token_infos[0].start, 0);
+        assert_eq!(tokinizer.token_infos[0].end, 3);
+        assert_eq!(tokinizer.token_infos[0].token_type.borrow().deref(), &Some(TokenType::Operator('-')));
+        assert_eq!(tokinizer.token_infos[1].token_type.borrow().deref(), &Some(TokenType::Operator('*')));
+        assert_eq!(tokinizer.token_infos[2].token_type.borrow().deref(), &Some(TokenType::Operator('/')));
+        assert_eq!(tokinizer.token_infos[3].token_type.borrow().deref(), &Some(TokenType::Operator('*')));
+    }
+}
@@ -4,17 +4,16 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::borrow::ToOwned;
 use crate::config::SmartCalcConfig;
 use crate::types::*;
 use crate::tokinizer::Tokinizer;
 use regex::Regex;
 use crate::token::ui_token::{UiTokenType};
 
-pub fn percent_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+pub fn percent_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
-            if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Percent(capture.name("NUMBER").unwrap().as_str().replace(&config.thousand_separator[..], "").replace(&config.decimal_seperator[..], ".").parse::<f64>().unwrap()))) {
+        for capture in re.captures_iter(data) {
+            if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Percent(capture.name("NUMBER").unwrap().as_str().replace(config.get_thousand_separator(&tokinizer.language), "").replace(config.get_decimal_seperator(&tokinizer.language), ".").parse::<f64>().unwrap()))) {
                 tokinizer.add_uitoken_from_match(capture.name("NUMBER"), UiTokenType::Number);
                 tokinizer.add_uitoken_from_match(capture.name("PERCENT"), UiTokenType::Symbol2);
             }
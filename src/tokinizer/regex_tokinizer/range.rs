@@ -0,0 +1,69 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use crate::config::SmartCalcConfig;
+use crate::types::*;
+use crate::tokinizer::Tokinizer;
+use regex::Regex;
+use crate::token::ui_token::{UiTokenType};
+
+/* Range limits should not be a part of a decimal number, like 1.5..3 or 1..2.5 */
+fn is_number_part(data: &str, start: usize, end: usize) -> bool {
+    let previous = data[..start].chars().next_back();
+    let mut next = data[end..].chars();
+
+    matches!(previous, Some('0'..='9' | '.' | ',')) || matches!((next.next(), next.next()), (Some('.' | ','), Some('0'..='9')))
+}
+
+pub fn range_regex_parser(_: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
+    for re in group_item.iter() {
+        for capture in re.captures_iter(data) {
+            let range = capture.get(0).unwrap();
+            if is_number_part(&tokinizer.data, range.start(), range.end()) {
+                continue;
+            }
+
+            let start = capture.name("START").unwrap().as_str().parse::<i64>();
+            let end   = capture.name("END").unwrap().as_str().parse::<i64>();
+
+            if let (Ok(start), Ok(end)) = (start, end) {
+                if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Range(start, end, RangeAggregate::Sum))) {
+                    tokinizer.add_uitoken_from_match(capture.name("START"), UiTokenType::Number);
+                    tokinizer.add_uitoken_from_match(capture.name("RANGE"), UiTokenType::Operator);
+                    tokinizer.add_uitoken_from_match(capture.name("END"), UiTokenType::Number);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn range_test() {
+    use core::ops::Deref;
+    use alloc::string::ToString;
+    use crate::tokinizer::regex_tokinizer;
+    use crate::tokinizer::test::setup_tokinizer;
+    use crate::config::SmartCalcConfig;
+    use crate::session::Session;
+    let mut session = Session::new();
+    let config = SmartCalcConfig::default();
+    let mut tokinizer_mut = setup_tokinizer("1..10 -5 .. 5 1.5..3".to_string(), &mut session, &config);
+
+    regex_tokinizer(&mut tokinizer_mut);
+    let tokens = &tokinizer_mut.token_infos;
+
+    assert_eq!(tokens.len(), 3);
+    assert_eq!(tokens[0].start, 0);
+    assert_eq!(tokens[0].end, 5);
+    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Range(1, 10, RangeAggregate::Sum)));
+
+    assert_eq!(tokens[1].start, 6);
+    assert_eq!(tokens[1].end, 13);
+    assert_eq!(tokens[1].token_type.borrow().deref(), &Some(TokenType::Range(-5, 5, RangeAggregate::Sum)));
+
+    assert_ne!(tokens[2].token_type.borrow().deref(), &Some(TokenType::Range(5, 3, RangeAggregate::Sum)));
+}
@@ -4,38 +4,51 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
+use core::f64::consts;
 use alloc::string::ToString;
-use alloc::borrow::ToOwned;
-use chrono::{Duration, Utc};
+use chrono::Duration;
 use crate::config::SmartCalcConfig;
-use crate::types::{TokenType};
+use crate::types::{NumberType, TokenType};
+use crate::variable::VariableInfo;
 use crate::tokinizer::{Tokinizer, read_currency};
 use crate::token::ui_token::{UiTokenType};
-use regex::{Regex};
+use regex::{Captures, Regex};
 use crate::constants::ConstantType;
 
-pub fn text_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+/* Letter e next to a digit is the exponent of a number like 1e3, not Euler's number */
+fn is_standalone(data: &str, capture: &Captures) -> bool {
+    let (start, end) = capture.get(0).map_or((0, 0), |item| (item.start(), item.end()));
+    !data[..start].ends_with(|ch: char| ch.is_ascii_digit()) && !data[end..].starts_with(|ch: char| ch.is_ascii_digit())
+}
+
+pub fn text_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
+        for capture in re.captures_iter(data) {
             let text = capture.name("TEXT").unwrap().as_str();
             if !text.trim().is_empty() {
 
-                if let Some(constant) = config.constant_pair.get(&tokinizer.language).unwrap().get(&text.to_string()) {
+                if let Some(constant) = config.constant_pair.get(&tokinizer.language).unwrap().get(&text.to_lowercase()) {
 
                     let token = match constant {
-                        ConstantType::Today     => Some(TokenType::Date(Utc::today().naive_utc(), config.get_time_offset())),
-                        ConstantType::Tomorrow  => Some(TokenType::Date(Utc::today().naive_utc() + Duration::days(1), config.get_time_offset())),
-                        ConstantType::Yesterday => Some(TokenType::Date(Utc::today().naive_utc() + Duration::days(-1), config.get_time_offset())),
-                        ConstantType::Now       => Some(TokenType::Time(Utc::now().naive_utc(), config.get_time_offset())),
+                        ConstantType::Today     => Some(TokenType::Date(config.get_today(), config.get_time_offset())),
+                        ConstantType::Tomorrow  => Some(TokenType::Date(config.get_today() + Duration::days(1), config.get_time_offset())),
+                        ConstantType::Yesterday => Some(TokenType::Date(config.get_today() + Duration::days(-1), config.get_time_offset())),
+                        ConstantType::Now       => Some(TokenType::Time(config.get_now(), config.get_time_offset())),
+                        ConstantType::Pi        => Some(TokenType::Number(consts::PI, NumberType::Decimal)),
+                        ConstantType::E if is_standalone(data, &capture) => Some(TokenType::Number(consts::E, NumberType::Decimal)),
+                        ConstantType::Tau       => Some(TokenType::Number(consts::TAU, NumberType::Decimal)),
+                        ConstantType::PreviousResult => tokinizer.session.last_result().map(|result| TokenType::Variable(VariableInfo::from_result(text.to_string(), result))),
                         _ => None
                     };
 
+                    let ui_type = token.as_ref().map_or(UiTokenType::Text, UiTokenType::from_token);
+
                     if token.is_some() && tokinizer.add_token_from_match(&capture.get(0), token) {
-                        tokinizer.add_uitoken_from_match(capture.get(0), UiTokenType::DateTime);
+                        tokinizer.add_uitoken_from_match(capture.get(0), ui_type);
                     }
                 }
 
-                if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Text(text.to_string()))) {
+                if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Text(config.interner.symbol(text)))) {
                     match read_currency(config, text) {
                         Some(_) => tokinizer.add_uitoken_from_match(capture.get(0), UiTokenType::Symbol1),
                         _ => tokinizer.add_uitoken_from_match(capture.get(0), UiTokenType::Text)
@@ -64,29 +77,30 @@ fn text_test_1() {
     assert_eq!(tokens.len(), 5);
     assert_eq!(tokens[0].start, 0);
     assert_eq!(tokens[0].end, 5);
-    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Text("erhan".to_string())));
+    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Text("erhan".into())));
 
     assert_eq!(tokens[1].start, 6);
     assert_eq!(tokens[1].end, 13);
-    assert_eq!(tokens[1].token_type.borrow().deref(), &Some(TokenType::Text("barış".to_string())));
+    assert_eq!(tokens[1].token_type.borrow().deref(), &Some(TokenType::Text("barış".into())));
 
     assert_eq!(tokens[2].start, 14);
     assert_eq!(tokens[2].end, 19);
-    assert_eq!(tokens[2].token_type.borrow().deref(), &Some(TokenType::Text("aysel".to_string())));
+    assert_eq!(tokens[2].token_type.borrow().deref(), &Some(TokenType::Text("aysel".into())));
 
     assert_eq!(tokens[3].start, 20);
     assert_eq!(tokens[3].end, 27);
-    assert_eq!(tokens[3].token_type.borrow().deref(), &Some(TokenType::Text("barış".to_string())));
+    assert_eq!(tokens[3].token_type.borrow().deref(), &Some(TokenType::Text("barış".into())));
 
     assert_eq!(tokens[4].start, 28);
     assert_eq!(tokens[4].end, 32);
-    assert_eq!(tokens[4].token_type.borrow().deref(), &Some(TokenType::Text("test".to_string())));
+    assert_eq!(tokens[4].token_type.borrow().deref(), &Some(TokenType::Text("test".into())));
 }
 
 #[cfg(test)]
 #[test]
 fn text_test_2() {
     use core::ops::Deref;
+    use chrono::Utc;
     use crate::tokinizer::regex_tokinizer;
     use crate::tokinizer::test::setup_tokinizer;
     use crate::config::SmartCalcConfig;
@@ -146,3 +160,33 @@ fn text_test_2() {
     } else { assert!(false); }
 }
 
+
+#[cfg(test)]
+#[test]
+fn text_test_3() {
+    use core::ops::Deref;
+    use crate::tokinizer::regex_tokinizer;
+    use crate::tokinizer::test::setup_tokinizer;
+    use crate::config::SmartCalcConfig;
+    use crate::session::Session;
+    let mut session = Session::new();
+    let config = SmartCalcConfig::default();
+    let mut tokinizer_mut = setup_tokinizer("pi π e tau".to_string(), &mut session, &config);
+
+    regex_tokinizer(&mut tokinizer_mut);
+    let tokens = &tokinizer_mut.token_infos;
+
+    assert_eq!(tokens.len(), 4);
+    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Number(consts::PI, NumberType::Decimal)));
+    assert_eq!(tokens[1].token_type.borrow().deref(), &Some(TokenType::Number(consts::PI, NumberType::Decimal)));
+    assert_eq!(tokens[2].token_type.borrow().deref(), &Some(TokenType::Number(consts::E, NumberType::Decimal)));
+    assert_eq!(tokens[3].token_type.borrow().deref(), &Some(TokenType::Number(consts::TAU, NumberType::Decimal)));
+
+    /* Exponent letter of a number and letters of a word are not the constant */
+    let mut session = Session::new();
+    let mut tokinizer_mut = setup_tokinizer("1e3 be E".to_string(), &mut session, &config);
+
+    regex_tokinizer(&mut tokinizer_mut);
+    let constants = tokinizer_mut.token_infos.iter().filter(|token| token.token_type.borrow().deref() == &Some(TokenType::Number(consts::E, NumberType::Decimal))).count();
+    assert_eq!(constants, 1);
+}
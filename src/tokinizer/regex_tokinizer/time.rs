@@ -5,7 +5,6 @@
  */
 
 use alloc::string::ToString;
-use alloc::borrow::ToOwned;
 use regex::Regex;
 use crate::config::SmartCalcConfig;
 use crate::tokinizer::Tokinizer;
@@ -15,9 +14,9 @@ use chrono::{Utc, FixedOffset, Datelike};
 
 use chrono::{TimeZone};
 
-pub fn time_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+pub fn time_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned()) {
+        for capture in re.captures_iter(data) {
             let mut end_position = 0;
             let mut hour = capture.name("hour").unwrap().as_str().parse::<i32>().unwrap();
             let minute   = match capture.name("minute") {
@@ -48,11 +47,11 @@ pub fn time_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, gr
                 offset: config.timezone_offset
             };
             
-            let date = Utc::today().naive_utc();
+            let date = config.get_today();
             let datetime = FixedOffset::east(time_offset.offset * 60).ymd(date.year(), date.month(), date.day()).and_hms(hour as u32, minute as u32, second as u32);
             let date_as_utc = Utc.from_utc_datetime(&datetime.naive_utc()).naive_utc();
             
-            if tokinizer.add_token_location(capture.get(0).unwrap().start(), end_position, Some(TokenType::Time(date_as_utc, time_offset)), capture.get(0).unwrap().as_str().to_string()) {
+            if tokinizer.add_token_location(capture.get(0).unwrap().start(), end_position, Some(TokenType::Time(date_as_utc, time_offset))) {
                 tokinizer.add_uitoken_from_match(capture.get(0), UiTokenType::DateTime);
             }
         }
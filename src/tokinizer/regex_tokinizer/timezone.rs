@@ -4,7 +4,6 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use alloc::borrow::ToOwned;
 use crate::config::SmartCalcConfig;
 use crate::types::*;
 use crate::tokinizer::Tokinizer;
@@ -12,9 +11,9 @@ use regex::Regex;
 use crate::token::ui_token::{UiTokenType};
 use crate::tools::parse_timezone;
 
-pub fn timezone_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, group_item: &[Regex]) {
+pub fn timezone_regex_parser(config: &SmartCalcConfig, tokinizer: &mut Tokinizer, data: &str, group_item: &[Regex]) {
     for re in group_item.iter() {
-        for capture in re.captures_iter(&tokinizer.data.to_owned().to_uppercase()) {
+        for capture in re.captures_iter(&data.to_uppercase()) {
             if let Some((timezone, offset)) = parse_timezone(config, &capture) {
                 if tokinizer.add_token_from_match(&capture.get(0), Some(TokenType::Timezone(timezone, offset))) {
                     tokinizer.add_uitoken_from_match(capture.name("timezone"), UiTokenType::Symbol1);
@@ -0,0 +1,177 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::ops::Deref;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::collections::{BTreeMap, BTreeSet};
+
+use crate::types::{FieldType, TokenType};
+use crate::tokinizer::{PatternToken, PatternType, TokenInfo, TokenInfoStatus};
+
+use super::RuleType;
+
+/* What a token is matched with, words are kept in lowercase */
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum TokenKey {
+    Word(String),
+    Operator(char),
+    Type(String)
+}
+
+/* Keys of every rule token, a token without keys may match any token */
+type PatternKeys = Vec<Option<Vec<TokenKey>>>;
+
+fn rule_token_keys(rule_token: &PatternToken) -> Option<Vec<TokenKey>> {
+    match rule_token.token_type.as_ref()? {
+        PatternType::Text(text) => Some(vec![TokenKey::Word(text.key().to_string())]),
+        PatternType::Operator(operator) => Some(vec![TokenKey::Operator(*operator)]),
+        PatternType::Field(field) => match field.deref() {
+            FieldType::Text(_, Some(expected)) => Some(vec![TokenKey::Word(expected.key().to_string())]),
+            FieldType::Group(_, items) => Some(items.iter().map(|item| TokenKey::Word(item.key().to_string())).collect()),
+            FieldType::TypeGroup(types, _) => Some(types.iter().map(|type_name| TokenKey::Type(type_name.to_string())).collect()),
+            field => Some(vec![TokenKey::Type(field.type_name())])
+        },
+        _ => None
+    }
+}
+
+/* Keys of the tokens of a line, variables are compared with their values so they may match any rule token */
+pub struct LineKeys {
+    keys: BTreeSet<TokenKey>,
+    has_variable: bool
+}
+
+impl LineKeys {
+    pub fn new(tokens: &[Rc<TokenInfo>]) -> Self {
+        let mut keys = BTreeSet::new();
+        let mut has_variable = false;
+
+        for token in tokens.iter().filter(|token| token.status.get() != TokenInfoStatus::Removed) {
+            match token.token_type.borrow().as_ref() {
+                Some(TokenType::Text(text)) => {
+                    keys.insert(TokenKey::Word(text.key().to_string()));
+                    keys.insert(TokenKey::Type("TEXT".to_string()));
+                },
+                Some(TokenType::Operator(operator)) => { keys.insert(TokenKey::Operator(*operator)); },
+                Some(TokenType::Variable(_)) => has_variable = true,
+                Some(token_type) => { keys.insert(TokenKey::Type(token_type.type_name())); },
+                None => ()
+            };
+        }
+
+        LineKeys { keys, has_variable }
+    }
+}
+
+/* Rule patterns compiled to the keys of their tokens. Patterns are indexed with the keys of their first token, so only the
+   patterns starting with a token of the line are consulted. A pattern matches only if every rule token matches a token of
+   the line, so the patterns having a token without a match in the line are not scanned. */
+#[derive(Clone, Debug, Default)]
+pub struct RuleMatcher {
+    rules: Vec<Vec<PatternKeys>>,
+    first_tokens: BTreeMap<TokenKey, Vec<(usize, usize)>>,
+    any_first_token: Vec<(usize, usize)>
+}
+
+impl RuleMatcher {
+    pub fn new(rules: &[RuleType]) -> Self {
+        let compile = |tokens_list: &Vec<Vec<PatternToken>>| tokens_list.iter()
+            .map(|rule_tokens| rule_tokens.iter().map(rule_token_keys).collect())
+            .collect();
+
+        let rules: Vec<Vec<PatternKeys>> = rules.iter().map(|rule| match rule {
+            RuleType::Internal { tokens_list, .. } | RuleType::API { tokens_list, .. } => compile(tokens_list)
+        }).collect();
+
+        let mut first_tokens: BTreeMap<TokenKey, Vec<(usize, usize)>> = BTreeMap::new();
+        let mut any_first_token = Vec::new();
+        for (rule_index, patterns) in rules.iter().enumerate() {
+            for (pattern_index, pattern_keys) in patterns.iter().enumerate() {
+                match pattern_keys.first() {
+                    Some(Some(keys)) => for key in keys.iter() {
+                        first_tokens.entry(key.clone()).or_default().push((rule_index, pattern_index));
+                    },
+                    _ => any_first_token.push((rule_index, pattern_index))
+                };
+            }
+        }
+
+        RuleMatcher { rules, first_tokens, any_first_token }
+    }
+
+    /* Rule and pattern indexes to try on the line, in the order of the rules */
+    pub fn candidates(&self, line: &LineKeys) -> Vec<(usize, usize)> {
+        let mut candidates = match line.has_variable {
+            true => self.rules.iter().enumerate().flat_map(|(rule_index, patterns)| (0..patterns.len()).map(move |pattern_index| (rule_index, pattern_index))).collect(),
+            false => line.keys.iter()
+                .filter_map(|key| self.first_tokens.get(key))
+                .flatten()
+                .chain(self.any_first_token.iter())
+                .filter(|(rule_index, pattern_index)| self.is_candidate(*rule_index, *pattern_index, line))
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+
+        candidates.sort_unstable();
+        candidates.dedup();
+        candidates
+    }
+
+    fn is_candidate(&self, rule: usize, pattern: usize, line: &LineKeys) -> bool {
+        match self.rules.get(rule).and_then(|patterns| patterns.get(pattern)) {
+            Some(pattern_keys) => pattern_keys.iter().all(|keys| keys.as_ref().map_or(true, |keys| keys.iter().any(|key| line.keys.contains(key)))),
+            None => true
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn rule_matcher_test() {
+    use crate::config::SmartCalcConfig;
+    use crate::session::Session;
+    use crate::tokinizer::{Tokinizer, language_tokinizer, regex_tokinizer};
+
+    let config = SmartCalcConfig::default();
+    let rules = config.rule.get("en").unwrap();
+    let matcher = RuleMatcher::new(rules);
+
+    let is_candidate = |name: &str, text: &str| {
+        let mut session = Session::new();
+        session.set_language("en".to_string());
+        session.set_text(text.to_string());
+        let mut tokinizer = Tokinizer::new(&config, &session);
+        language_tokinizer(&mut tokinizer);
+        regex_tokinizer(&mut tokinizer);
+        let candidates = matcher.candidates(&LineKeys::new(&tokinizer.token_infos));
+
+        /* Candidates are kept in the order of the rules */
+        assert!(candidates.windows(2).all(|pair| pair[0] < pair[1]));
+        candidates.iter().any(|(index, _)| matches!(&rules[*index], RuleType::Internal { function_name, .. } if function_name == name))
+    };
+
+    assert!(is_candidate("percent_calculator", "10% of 200"));
+    assert!(!is_candidate("convert_money", "10% of 200"));
+    assert!(is_candidate("convert_money", "$10 to eur"));
+    assert!(!is_candidate("percent_calculator", "$10 to eur"));
+
+    /* Patterns starting with an other token are not consulted */
+    let total = rules.iter().map(|rule| match rule {
+        RuleType::Internal { tokens_list, .. } | RuleType::API { tokens_list, .. } => tokens_list.len()
+    }).sum::<usize>();
+    let mut session = Session::new();
+    session.set_language("en".to_string());
+    session.set_text("12 + 34".to_string());
+    let mut tokinizer = Tokinizer::new(&config, &session);
+    language_tokinizer(&mut tokinizer);
+    regex_tokinizer(&mut tokinizer);
+    assert!(matcher.candidates(&LineKeys::new(&tokinizer.token_infos)).len() * 4 < total);
+}
+
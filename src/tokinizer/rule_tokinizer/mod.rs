@@ -5,22 +5,26 @@
  */
 
 pub mod rules;
+mod matcher;
 
-use core::cell::Cell;
 
+use core::ops::Deref;
+use alloc::format;
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::vec::Vec;
 use lazy_static::*;
 use alloc::string::ToString;
 use alloc::string::String;
 use alloc::collections::btree_map::BTreeMap;
-use core::cell::RefCell;
 
 use crate::RuleTrait;
 use crate::UiTokenType;
-use crate::types::TokenType;
+use crate::types::{FieldType, TokenType};
 use crate::types::{ExpressionFunc};
-use crate::tokinizer::{TokenInfo};
+use crate::tokinizer::{read_currency, PatternToken, TokenInfo};
+use crate::smartcalc::TraceStep;
+use crate::syntax::primative::PrimativeParser;
 
 use self::rules::date_time_rules::*;
 use self::rules::percent_rules::*;
@@ -30,19 +34,24 @@ use self::rules::cleanup_rules::*;
 use self::rules::date_rules::*;
 use self::rules::duration_rules::*;
 use self::rules::dynamic_type_rules::*;
+use self::rules::reference_rules::*;
+
+pub use self::matcher::RuleMatcher;
+use self::matcher::LineKeys;
 
 use super::TokenInfoStatus;
 use super::Tokinizer;
 
+#[derive(Clone)]
 pub enum RuleType {
     Internal { 
         function_name: String,
         function: ExpressionFunc,
-        tokens_list: Vec<Vec<Rc<TokenInfo>>>
+        tokens_list: Vec<Vec<PatternToken>>
     },
     API {
-        tokens_list: Vec<Vec<Rc<TokenInfo>>>, 
-        rule: Rc<dyn RuleTrait>
+        tokens_list: Vec<Vec<PatternToken>>, 
+        rule: Arc<dyn RuleTrait>
     }
 }
 
@@ -62,12 +71,19 @@ lazy_static! {
         m.insert("number_on".to_string(),          number_on as ExpressionFunc);
         m.insert("number_of".to_string(),          number_of as ExpressionFunc);
         m.insert("number_off".to_string(),         number_off as ExpressionFunc);
+        m.insert("aggregate_range".to_string(),    aggregate_range as ExpressionFunc);
 
         m.insert("division_cleanup".to_string(),   division_cleanup as ExpressionFunc);
         m.insert("duration_parse".to_string(),     duration_parse as ExpressionFunc);
         m.insert("as_duration".to_string(),        as_duration as ExpressionFunc);
         m.insert("to_duration".to_string(),        to_duration as ExpressionFunc);
         m.insert("at_date".to_string(),            at_date as ExpressionFunc);
+        m.insert("quarter_date".to_string(),       quarter_date as ExpressionFunc);
+        m.insert("period_date".to_string(),        period_date as ExpressionFunc);
+        m.insert("leap_year".to_string(),          leap_year as ExpressionFunc);
+        m.insert("fiscal_year_date".to_string(),   fiscal_year_date as ExpressionFunc);
+        m.insert("duration_date".to_string(),      duration_date as ExpressionFunc);
+        m.insert("days_until".to_string(),         days_until as ExpressionFunc);
         
         m.insert("combine_durations".to_string(),  combine_durations as ExpressionFunc);
 
@@ -77,113 +93,178 @@ lazy_static! {
         m.insert("number_type_convert".to_string(),     number_type_convert as ExpressionFunc);
         
         m.insert("dynamic_type_convert".to_string(),     dynamic_type_convert as ExpressionFunc);
+        m.insert("combine_dynamic_types".to_string(),    combine_dynamic_types as ExpressionFunc);
+
+        m.insert("line_reference".to_string(),     line_reference as ExpressionFunc);
+        m.insert("block_aggregate".to_string(),    block_aggregate as ExpressionFunc);
 
         m
     };
 }
 
-fn find_match(name: &String, rule_tokens: &Vec<Rc<TokenInfo>>, tokinizer: &Tokinizer) -> (usize, usize, usize, usize, BTreeMap<String, Rc<TokenInfo>>) {
+/* Field names ending with '+' match one or more tokens, every matched token is stored as "name.index" */
+fn repeated_field(rule_token: &PatternToken) -> Option<String> {
+    rule_token.field_name().and_then(|field_name| field_name.strip_suffix('+').map(|name| name.to_string()))
+}
+
+fn token_match(token: &Rc<TokenInfo>, token_type: &TokenType, rule_token: &PatternToken) -> bool {
+    match token_type {
+        TokenType::Variable(variable) => rule_token.matches_ast(&variable.data.borrow()),
+        _ => rule_token.matches(token)
+    }
+}
+
+fn add_field(fields: &mut BTreeMap<String, Rc<TokenInfo>>, rule_token: &PatternToken, token: &Rc<TokenInfo>) {
+    match (repeated_field(rule_token), rule_token.field_name()) {
+        (Some(name), _) => {
+            let prefix = format!("{}.", name);
+            let index  = fields.keys().filter(|key| key.starts_with(&prefix)).count();
+            fields.insert(format!("{}{}", prefix, index), token.clone());
+        },
+        (None, Some(field_name)) => { fields.insert(field_name.to_string(), token.clone()); },
+        (None, None) => ()
+    };
+}
+
+fn find_match(_: &String, rule_tokens: &[PatternToken], tokinizer: &Tokinizer) -> (usize, usize, usize, usize, BTreeMap<String, Rc<TokenInfo>>) {
     let total_rule_token       = rule_tokens.len();
     let mut rule_token_index   = 0;
     let mut target_token_index = 0;
     let mut start_token_index  = 0;
+    let mut end_token_index    = 0;
     let mut fields             = BTreeMap::new();
     
     while let Some(token) = tokinizer.token_infos.get(target_token_index) {
+        let repeated = rule_token_index > 0 && repeated_field(&rule_tokens[rule_token_index - 1]).is_some();
+        if total_rule_token == rule_token_index && !repeated {
+            break;
+        }
+
         target_token_index += 1;
         if token.status.get() == TokenInfoStatus::Removed {
             continue;
         }
 
         if let Some(token_type) = &token.token_type.borrow().deref() {
-
-            if let TokenType::Variable(variable) = &token_type {
-                let is_same = TokenType::variable_compare(&rule_tokens[rule_token_index], variable.data.borrow().clone());
-                if is_same {
-                    match TokenType::get_field_name(&rule_tokens[rule_token_index]) {
-                        Some(field_name) => fields.insert(field_name.to_string(), token.clone()),
-                        None => None
-                    };
-
-                    rule_token_index   += 1;
-                } else {
-                    rule_token_index    = 0;
-                    start_token_index   = target_token_index;
-                }
+            if rule_token_index < total_rule_token && token_match(token, token_type, &rule_tokens[rule_token_index]) {
+                add_field(&mut fields, &rule_tokens[rule_token_index], token);
+                rule_token_index += 1;
+                end_token_index   = target_token_index;
             }
-            else if token == &rule_tokens[rule_token_index] {
-                match TokenType::get_field_name(&rule_tokens[rule_token_index]) {
-                    Some(field_name) => fields.insert(field_name.to_string(), token.clone()),
-                    None => None
-                };
-
-                if cfg!(feature="debug-rules") {
-                }
-
-                rule_token_index   += 1;
+            else if repeated && token_match(token, token_type, &rule_tokens[rule_token_index - 1]) {
+                add_field(&mut fields, &rule_tokens[rule_token_index - 1], token);
+                end_token_index   = target_token_index;
+            }
+            else if total_rule_token == rule_token_index {
+                break;
             }
             else {
-                if cfg!(feature="debug-rules") {
-                }
                 rule_token_index    = 0;
                 start_token_index   = target_token_index;
-            }   
+            }
         }
+    }
+    
+    (total_rule_token, rule_token_index, start_token_index, end_token_index, fields)
+}
 
-        if total_rule_token == rule_token_index {
-            break;
+/* Calendar values and durations are highlighted as a whole, otherwise the rule words become keywords.
+   Free text fields (like a label) and currencies keep their own highlight. */
+fn update_ui_tokens(tokinizer: &mut Tokinizer, token: &TokenType, rule_tokens: &[PatternToken], fields: &BTreeMap<String, Rc<TokenInfo>>, start_token_index: usize, target_token_index: usize) {
+    let text_start_position = tokinizer.token_infos[start_token_index].start;
+    let text_end_position   = tokinizer.token_infos[target_token_index - 1].end;
+
+    match token {
+        TokenType::Date(_, _) | TokenType::Time(_, _) | TokenType::DateTime(_, _) | TokenType::Duration(_) => tokinizer.ui_tokens.update_tokens(text_start_position, text_end_position, UiTokenType::from_token(token)),
+        _ => {
+            let free_fields = rule_tokens.iter().filter_map(|rule_token| match rule_token.field() {
+                Some(FieldType::Text(name, None)) => Some(name.to_string()),
+                _ => None
+            }).collect::<Vec<_>>();
+
+            for token_info in tokinizer.token_infos[start_token_index..target_token_index].iter() {
+                let is_free_field = fields.iter().any(|(name, field)| Rc::ptr_eq(field, token_info) && free_fields.iter().any(|free_field| name == free_field || name.starts_with(&format!("{}.", free_field))));
+                let is_keyword = match token_info.token_type.borrow().as_ref() {
+                    Some(TokenType::Text(text)) => read_currency(tokinizer.config, text).is_none(),
+                    _ => false
+                };
+
+                if is_keyword && !is_free_field {
+                    tokinizer.ui_tokens.replace_type(token_info.start, token_info.end, UiTokenType::Text, UiTokenType::Keyword);
+                }
+            }
+
+            if let Some(data) = fields.get("type") {
+                tokinizer.ui_tokens.update_tokens(data.start, data.end, UiTokenType::Unit);
+            }
         }
+    };
+}
+
+/* Results are printed like the line results, tokens without a value are written as they are */
+fn trace_result(tokinizer: &Tokinizer, token: &TokenType) -> String {
+    match PrimativeParser::token_to_item(token) {
+        Some(item) => item.print(tokinizer.config, tokinizer.session),
+        None => token.to_string()
     }
-    
-    (total_rule_token, rule_token_index, start_token_index, target_token_index, fields)
 }
 
 pub fn rule_tokinizer(tokinizer: &mut Tokinizer) {    
     if let Some(language) = tokinizer.config.rule.get(&tokinizer.language) {
 
+        /* Matcher is compiled with the rules, it is only missing if the rules are changed without updating it */
+        let compiled_matcher;
+        let matcher = match tokinizer.config.rule_matcher.get(&tokinizer.language) {
+            Some(matcher) => matcher,
+            None => {
+                compiled_matcher = RuleMatcher::new(language);
+                &compiled_matcher
+            }
+        };
+
         let mut execute_rules = true;
-        while execute_rules {
+        while execute_rules && tokinizer.session.interruption().is_none() {
             execute_rules = false;
 
-            for rule in language.iter() {
-                
-                match rule {
+            /* Candidates are found again after a rule changed the line, the next rules are tried on the new tokens */
+            let mut candidates = matcher.candidates(&LineKeys::new(&tokinizer.token_infos));
+            let mut candidate_index = 0;
+
+            while let Some((rule_index, pattern_index)) = candidates.get(candidate_index).cloned() {
+                candidate_index += 1;
+
+                match &language[rule_index] {
                     RuleType::Internal { 
                         function_name,
                         function,
                         tokens_list
                     } => {
-                        for rule_tokens in tokens_list {
-                            let (total_rule_token, rule_token_index, start_token_index, target_token_index, fields) = find_match(&function_name, rule_tokens, tokinizer);
-                            if total_rule_token == rule_token_index {       
-                                match function(tokinizer.config, tokinizer, &fields) {
-                                    Ok(token) => {
-                                        if cfg!(feature="debug-rules") {
-                                        }
-        
-                                        let text_start_position = tokinizer.token_infos[start_token_index].start;
-                                        let text_end_position   = tokinizer.token_infos[target_token_index - 1].end;
-                                        execute_rules = true;
-        
-                                        for index in start_token_index..target_token_index {
-                                            tokinizer.token_infos[index].status.set(TokenInfoStatus::Removed);
-                                        }
-        
-                                        if let Some(data) = fields.get("type") {
-                                            tokinizer.ui_tokens.update_tokens(data.start, data.end, UiTokenType::Symbol2)
-                                        }
-        
-                                        tokinizer.token_infos.insert(start_token_index, Rc::new(TokenInfo {
-                                            start: text_start_position,
-                                            end: text_end_position,
-                                            token_type: RefCell::new(Some(token)),
-                                            original_text: "".to_string(),
-                                            status: Cell::new(TokenInfoStatus::Active)
-                                        }));
-                                        break;
-                                    },
-                                    Err(error) => log::info!("Rule execution error, {}", error)
-                                }
+                        let rule_tokens = &tokens_list[pattern_index];
+                        let (total_rule_token, rule_token_index, start_token_index, target_token_index, fields) = find_match(function_name, rule_tokens, tokinizer);
+                        if total_rule_token == rule_token_index {       
+                            match function(tokinizer.config, tokinizer, &fields) {
+                                Ok(token) => {
+                                    if cfg!(feature="debug-rules") {
+                                    }
+
+                                    tokinizer.session.add_trace(|| TraceStep::Rule { name: function_name.to_string(), result: trace_result(tokinizer, &token) });
+    
+                                    let text_start_position = tokinizer.token_infos[start_token_index].start;
+                                    let text_end_position   = tokinizer.token_infos[target_token_index - 1].end;
+                                    execute_rules = true;
+    
+                                    for index in start_token_index..target_token_index {
+                                        tokinizer.token_infos[index].status.set(TokenInfoStatus::Removed);
+                                    }
+    
+                                    update_ui_tokens(tokinizer, &token, rule_tokens, &fields, start_token_index, target_token_index);
+                                    tokinizer.add_rule_match(function_name.to_string(), text_start_position, text_end_position);
+    
+                                    tokinizer.token_infos.insert(start_token_index, Rc::new(TokenInfo::new(text_start_position, text_end_position, Some(token), tokinizer.data.clone())));
+                                    candidates = matcher.candidates(&LineKeys::new(&tokinizer.token_infos));
+                                    candidate_index = candidates.partition_point(|candidate| candidate.0 <= rule_index);
+                                },
+                                Err(error) => log::info!("Rule execution error, {}", error)
                             }
                         }
                     },
@@ -191,43 +272,32 @@ pub fn rule_tokinizer(tokinizer: &mut Tokinizer) {
                         tokens_list, 
                         rule
                     } => {
-                        for rule_tokens in tokens_list {
-                            let (total_rule_token, rule_token_index, start_token_index, target_token_index, fields) = find_match(&rule.name(), rule_tokens, tokinizer);
-                            if total_rule_token == rule_token_index {
-                                let simple_fields = fields.iter().map(|(key, value)| (key.to_string(), value.token_type.borrow().as_ref().unwrap().clone())).collect::<BTreeMap<_, _>>();
-                                if let Some(token) = rule.call(tokinizer.config, &simple_fields) {
-                                    
-                                    let text_start_position = tokinizer.token_infos[start_token_index].start;
-                                    let text_end_position   = tokinizer.token_infos[target_token_index - 1].end;
-                                    execute_rules = true;
-        
-                                    for index in start_token_index..target_token_index {
-                                        tokinizer.token_infos[index].status.set(TokenInfoStatus::Removed);
-                                    }
-        
-                                    for (_, token) in fields.iter() {
-                                        let ui_token = match token.token_type.borrow().as_ref() {
-                                            Some(TokenType::Number(_, _)) => UiTokenType::Number,
-                                            Some(TokenType::Money(_, _)) => UiTokenType::Number,
-                                            Some(TokenType::Date(_, _)) => UiTokenType::DateTime,
-                                            Some(TokenType::Time(_, _)) => UiTokenType::DateTime,
-                                            Some(TokenType::DateTime(_, _)) => UiTokenType::DateTime,
-                                            Some(TokenType::Month(_)) => UiTokenType::Month,
-                                            Some(TokenType::Percent(_)) => UiTokenType::Number,
-                                            _ => UiTokenType::Symbol2
-                                        };
-                                        tokinizer.ui_tokens.update_tokens(token.start, token.end, ui_token);
-                                    }
-        
-                                    tokinizer.token_infos.insert(start_token_index, Rc::new(TokenInfo {
-                                        start: text_start_position,
-                                        end: text_end_position,
-                                        token_type: RefCell::new(Some(token)),
-                                        original_text: "".to_string(),
-                                        status: Cell::new(TokenInfoStatus::Active)
-                                    }));
-                                    break;
+                        let rule_tokens = &tokens_list[pattern_index];
+                        let (total_rule_token, rule_token_index, start_token_index, target_token_index, fields) = find_match(&rule.name(), rule_tokens, tokinizer);
+                        if total_rule_token == rule_token_index {
+                            let simple_fields = fields.iter().map(|(key, value)| (key.to_string(), value.token_type.borrow().as_ref().unwrap().clone())).collect::<BTreeMap<_, _>>();
+                            if let Some(token) = rule.call(tokinizer.config, &simple_fields) {
+                                tokinizer.session.add_trace(|| TraceStep::Rule { name: rule.name(), result: trace_result(tokinizer, &token) });
+                                
+                                let text_start_position = tokinizer.token_infos[start_token_index].start;
+                                let text_end_position   = tokinizer.token_infos[target_token_index - 1].end;
+                                execute_rules = true;
+    
+                                for index in start_token_index..target_token_index {
+                                    tokinizer.token_infos[index].status.set(TokenInfoStatus::Removed);
                                 }
+    
+                                for (_, token) in fields.iter() {
+                                    let ui_token = token.token_type.borrow().as_ref().map_or(UiTokenType::Symbol2, UiTokenType::from_token);
+                                    tokinizer.ui_tokens.update_tokens(token.start, token.end, ui_token);
+                                }
+
+                                update_ui_tokens(tokinizer, &token, rule_tokens, &fields, start_token_index, target_token_index);
+                                tokinizer.add_rule_match(rule.name(), text_start_position, text_end_position);
+    
+                                tokinizer.token_infos.insert(start_token_index, Rc::new(TokenInfo::new(text_start_position, text_end_position, Some(token), tokinizer.data.clone())));
+                                candidates = matcher.candidates(&LineKeys::new(&tokinizer.token_infos));
+                                candidate_index = candidates.partition_point(|candidate| candidate.0 <= rule_index);
                             }
                         }
                     }
@@ -235,4 +305,4 @@ pub fn rule_tokinizer(tokinizer: &mut Tokinizer) {
             }
         }
     }
-}
\ No newline at end of file
+}
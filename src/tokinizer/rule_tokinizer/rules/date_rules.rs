@@ -7,18 +7,22 @@
 use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 use alloc::collections::btree_map::BTreeMap;
 
-use chrono::Utc;
+use chrono::{Duration, Months};
 use chrono::{NaiveDate, Datelike};
-use chrono::Timelike;
 
 use crate::config::SmartCalcConfig;
 use crate::tokinizer::get_date;
+use crate::tokinizer::get_duration;
 use crate::tokinizer::get_number;
 use crate::tokinizer::get_number_or_month;
 use crate::tokinizer::get_number_or_time;
-use crate::{tokinizer::Tokinizer, types::TokenType};
+use crate::tokinizer::get_month;
+use crate::tokinizer::get_text;
+use crate::constants::ConstantType;
+use crate::{tokinizer::Tokinizer, types::{NumberType, TokenType}};
 use crate::tokinizer::{TokenInfo};
 
 pub fn small_date(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
@@ -35,7 +39,7 @@ pub fn small_date(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<Str
 
         let year = match get_number("year", fields) {
             Some(number) => number as i32,
-            _ => Utc::now().date().year() as i32
+            _ => config.get_today().year()
         };
 
         return match NaiveDate::from_ymd_opt(year, month, day as u32) {
@@ -50,26 +54,172 @@ pub fn small_date(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<Str
 
 pub fn at_date(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
     if (fields.contains_key("source")) && fields.contains_key("time") {
-        let (date, date_tz) = match get_date("source", fields) {
+        let (date, _) = match get_date("source", fields) {
             Some(number) => number,
             _ => return Err("Date information not valid".to_string())
         };
         
-        //todo: convert timezone informations
-        let (time, _) = match get_number_or_time(config, "time", fields) {
+        let (time, time_tz) = match get_number_or_time(config, "time", fields) {
             Some(number) => number,
             _ => return Err("Date information not valid".to_string())
         };
-        return Ok(TokenType::DateTime(date.and_hms(time.hour(), time.minute(), time.second()), date_tz));
+
+        /* Time is kept as UTC, combine the date with the wall clock time of its own timezone */
+        let local_time = time + Duration::minutes(time_tz.offset as i64);
+        let date_time = date.and_time(local_time.time()) - Duration::minutes(time_tz.offset as i64);
+        return Ok(TokenType::DateTime(date_time, time_tz));
     }
     Err("Date type not valid".to_string())
 }
 
+fn period_edge(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>, start: NaiveDate, next_start: NaiveDate) -> core::result::Result<TokenType, String> {
+    match get_text("type", fields) {
+        Some(text) if config.is_group_word(&tokinizer.language, "period_end_group", &text) => Ok(TokenType::Date(next_start.pred_opt().unwrap_or(next_start), config.get_time_offset())),
+        _ => Ok(TokenType::Date(start, config.get_time_offset()))
+    }
+}
+
+fn period_range(today: NaiveDate, period: &str, shift: i32) -> Option<(NaiveDate, NaiveDate)> {
+    match period {
+        "day" => {
+            let start = today + Duration::days(shift as i64);
+            Some((start, start.succ_opt()?))
+        },
+        "week" => {
+            let start = today - Duration::days(today.weekday().num_days_from_monday() as i64) + Duration::weeks(shift as i64);
+            Some((start, start + Duration::weeks(1)))
+        },
+        "month" => {
+            let first_day = today.with_day(1)?;
+            let start = match shift.is_negative() {
+                true => first_day.checked_sub_months(Months::new(shift.unsigned_abs()))?,
+                false => first_day.checked_add_months(Months::new(shift as u32))?
+            };
+            Some((start, start.checked_add_months(Months::new(1))?))
+        },
+        "year" => Some((NaiveDate::from_ymd_opt(today.year() + shift, 1, 1)?, NaiveDate::from_ymd_opt(today.year() + shift + 1, 1, 1)?)),
+        _ => None
+    }
+}
+
+pub fn period_date(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    if let Some(month) = get_month("month", fields) {
+        let year = match get_number("year", fields) {
+            Some(number) => number as i32,
+            _ => config.get_today().year()
+        };
+
+        let start = NaiveDate::from_ymd_opt(year, month, 1).ok_or("Date is not valid")?;
+        let next_start = start.checked_add_months(Months::new(1)).ok_or("Date is not valid")?;
+        return period_edge(config, tokinizer, fields, start, next_start);
+    }
+
+    let period = match get_text("period", fields).and_then(|period| config.get_constant(&tokinizer.language, &period).cloned()) {
+        Some(ConstantType::Day) => "day",
+        Some(ConstantType::Week) => "week",
+        Some(ConstantType::Month) => "month",
+        Some(ConstantType::Year) => "year",
+        _ => return Err("Period information not valid".to_string())
+    };
+
+    let shift = match get_text("relative", fields) {
+        Some(text) if config.is_group_word(&tokinizer.language, "period_next_group", &text) => 1,
+        Some(text) if config.is_group_word(&tokinizer.language, "period_previous_group", &text) => -1,
+        _ => 0
+    };
+
+    match period_range(config.get_today(), period, shift) {
+        Some((start, next_start)) => period_edge(config, tokinizer, fields, start, next_start),
+        None => Err("Period information not valid".to_string())
+    }
+}
+
+pub fn quarter_date(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    let quarter = match get_number("quarter", fields) {
+        Some(number) if (1.0..=4.0).contains(&number) && number.fract() == 0.0 => number as u32,
+        _ => return Err("Quarter information not valid".to_string())
+    };
+
+    let year = match get_number("year", fields) {
+        Some(number) => number as i32,
+        _ => config.get_today().year()
+    };
+
+    let start = NaiveDate::from_ymd_opt(year, (quarter - 1) * 3 + 1, 1).ok_or("Date is not valid")?;
+    let next_start = start.checked_add_months(Months::new(3)).ok_or("Date is not valid")?;
+    period_edge(config, tokinizer, fields, start, next_start)
+}
+
+fn is_leap_year(year: i32) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+pub fn leap_year(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    if let Some(year) = get_number("year", fields) {
+        return Ok(TokenType::Bool(is_leap_year(year as i32)));
+    }
+
+    let (start, end) = match (get_number("start", fields), get_number("end", fields)) {
+        (Some(start), Some(end)) => (start.min(end) as i32, start.max(end) as i32),
+        _ => return Err("Year information not valid".to_string())
+    };
+
+    if end - start > 10_000 {
+        return Err("Year range not valid".to_string())
+    }
+
+    let years = (start..=end).filter(|year| is_leap_year(*year)).map(|year| year.to_string()).collect::<Vec<_>>();
+    Ok(TokenType::TextValue(years.join(", ")))
+}
+
+pub fn fiscal_year_date(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    let year = match get_number("year", fields) {
+        Some(number) => number as i32,
+        _ => return Err("Year information not valid".to_string())
+    };
+
+    let start_month = match get_month("month", fields) {
+        Some(month) => month,
+        None => config.fiscal_year_start_month
+    };
+
+    /* Fiscal years are named after the calendar year they end in */
+    let start_year = match start_month {
+        1 => year,
+        _ => year - 1
+    };
+
+    let start = NaiveDate::from_ymd_opt(start_year, start_month, 1).ok_or("Date is not valid")?;
+    let next_start = start.checked_add_months(Months::new(12)).ok_or("Date is not valid")?;
+    period_edge(config, tokinizer, fields, start, next_start)
+}
+
+pub fn duration_date(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    let duration = match (get_duration("later", fields), get_duration("ago", fields)) {
+        (Some(duration), _) => duration,
+        (_, Some(duration)) => -duration,
+        _ => return Err("Duration information not valid".to_string())
+    };
+
+    /* Whole days move the date, anything shorter moves the current time */
+    match duration.num_seconds() % (24 * 60 * 60) {
+        0 => Ok(TokenType::Date(config.get_today() + duration, config.get_time_offset())),
+        _ => Ok(TokenType::DateTime(config.get_now() + duration, config.get_time_offset()))
+    }
+}
+
+pub fn days_until(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    match get_date("target", fields) {
+        Some((target, _)) => Ok(TokenType::Number((target - config.get_today()).num_days().abs() as f64, NumberType::Decimal)),
+        None => Err("Date information not valid".to_string())
+    }
+}
 
 #[cfg(test)]
 #[test]
 fn small_date_test_1() {
     use core::ops::Deref;
+    use chrono::Utc;
     use crate::tokinizer::test::execute;
     
     let tokens = execute("12 january".to_string());
@@ -81,7 +231,7 @@ fn small_date_test_1() {
 #[cfg(test)]
 #[test]
 fn small_date_test_2() {
-    use crate::{tokinizer::test::get_executed_raw_tokens, types::NumberType};
+    use crate::tokinizer::test::get_executed_raw_tokens;
     
     let tokens = get_executed_raw_tokens("32 january".to_string());
     assert_eq!(tokens.len(), 3);
@@ -115,3 +265,18 @@ fn small_date_test_4() {
     assert_eq!(tokens.len(), 6);
     assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Date(NaiveDate::from_ymd(1985, 12, 22), config.get_time_offset())));
 }
+
+#[cfg(test)]
+#[test]
+fn period_range_test() {
+    let today = NaiveDate::from_ymd_opt(2024, 2, 14).unwrap();
+    let date = |year, month, day| NaiveDate::from_ymd_opt(year, month, day).unwrap();
+
+    assert_eq!(period_range(today, "day", 1), Some((date(2024, 2, 15), date(2024, 2, 16))));
+    assert_eq!(period_range(today, "week", 0), Some((date(2024, 2, 12), date(2024, 2, 19))));
+    assert_eq!(period_range(today, "week", 1), Some((date(2024, 2, 19), date(2024, 2, 26))));
+    assert_eq!(period_range(today, "month", 0), Some((date(2024, 2, 1), date(2024, 3, 1))));
+    assert_eq!(period_range(today, "month", -2), Some((date(2023, 12, 1), date(2024, 1, 1))));
+    assert_eq!(period_range(today, "year", 1), Some((date(2025, 1, 1), date(2026, 1, 1))));
+    assert_eq!(period_range(today, "decade", 0), None);
+}
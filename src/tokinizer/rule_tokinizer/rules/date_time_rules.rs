@@ -8,7 +8,8 @@ use alloc::rc::Rc;
 use alloc::string::String;
 use alloc::string::ToString;
 use chrono::FixedOffset;
-use chrono::NaiveDateTime;
+use chrono::DateTime;
+use chrono::NaiveTime;
 use chrono::TimeZone;
 
 use alloc::collections::btree_map::BTreeMap;
@@ -48,19 +49,27 @@ pub fn time_with_timezone(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<
     Err("Timezone or time informations not found".to_string())
 }
 
+/* Timestamps above this value are too far in the future to be seconds, so they are read as milliseconds */
+const MILLISECOND_TIMESTAMP_LIMIT: f64 = 100_000_000_000.0;
+
 pub fn to_unixtime(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
     if fields.contains_key("data") {
-        let timestamp = match get_time("data", fields) {
-            Some((time, _)) => time.timestamp(),
+        let date_time = match get_time("data", fields) {
+            Some((time, _)) => time,
             None => match get_date("data", fields) {
-                Some((date, _)) => date.and_hms(0,0,0).timestamp(),
+                Some((date, _)) => date.and_time(NaiveTime::MIN),
                 None => match get_date_time("data", fields) {
-                    Some((date_time, _)) => date_time.timestamp(),
-                    None => 0
+                    Some((date_time, _)) => date_time,
+                    None => return Err("Date with time/date/time information not found".to_string())
                 }
             }
         };
 
+        let timestamp = match fields.contains_key("unit") {
+            true => date_time.and_utc().timestamp_millis(),
+            false => date_time.and_utc().timestamp()
+        };
+
         return Ok(TokenType::Number(timestamp as f64, NumberType::Raw));
     }
     Err("Date with time/date/time information not found".to_string())
@@ -69,7 +78,15 @@ pub fn to_unixtime(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String,
 pub fn from_unixtime(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
     if fields.contains_key("number") {
         let timestamp = get_number("number", fields).unwrap();
-        let date = NaiveDateTime::from_timestamp(timestamp as i64, 0);
+        let milliseconds = match fields.contains_key("unit") || timestamp.abs() >= MILLISECOND_TIMESTAMP_LIMIT {
+            true => timestamp as i64,
+            false => timestamp as i64 * 1000
+        };
+
+        let date = match DateTime::from_timestamp_millis(milliseconds) {
+            Some(date) => date.naive_utc(),
+            None => return Err("Timestamp is not valid".to_string())
+        };
         
         return match get_timezone("timezone", fields) {
             Some((target_timezone, target_offset)) => Ok(TokenType::DateTime(date, TimeOffset { 
@@ -67,7 +67,7 @@ pub fn duration_parse(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &
 }
 
 pub fn combine_durations(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
-    if (fields.contains_key("1")) && fields.contains_key("2") {
+    if (fields.contains_key("first")) && fields.contains_key("items.0") {
         let mut sum_duration = Duration::zero();
 
         for key in fields.keys() {
@@ -99,7 +99,7 @@ pub fn as_duration(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTr
         match fields.get("source") {
             Some(token_info) => match token_info.token_type.borrow().deref()  {
                 Some(TokenType::Duration(duration)) => {
-                    let seconds = duration.num_seconds().abs() as i64;
+                    let seconds = duration.num_seconds();
                     
                     return match constant_type {
                         ConstantType::Day => Ok(TokenType::Duration(Duration::days(seconds / DAY))),
@@ -10,6 +10,7 @@ use alloc::string::ToString;
 use alloc::collections::btree_map::BTreeMap;
 
 use crate::config::SmartCalcConfig;
+use crate::compiler::{DataItem, OperationType};
 use crate::compiler::dynamic_type::DynamicTypeItem;
 use crate::tokinizer::get_dynamic_type;
 use crate::tokinizer::get_text;
@@ -34,3 +35,35 @@ pub fn dynamic_type_convert(config: &SmartCalcConfig, _: &Tokinizer, fields: &BT
 
     Err("Dynamic type not valid".to_string())
 }
+
+fn is_compatible(config: &SmartCalcConfig, left: &str, right: &str) -> bool {
+    left == right || config.type_conversion.iter().any(|conversion| (conversion.source.name == left && conversion.target.name == right) || (conversion.source.name == right && conversion.target.name == left))
+}
+
+pub fn combine_dynamic_types(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    if fields.contains_key("first") && fields.contains_key("items.0") {
+        let (number, first_type) = match get_dynamic_type("first", fields) {
+            Some(data) => data,
+            None => return Err("Dynamic type not valid".to_string())
+        };
+        let mut sum: Rc<dyn DataItem> = Rc::new(DynamicTypeItem(number, first_type.clone()));
+
+        /* Items are added into the unit of the first item, incompatible units are not combined */
+        for key in fields.keys().filter(|key| key.starts_with("items.")) {
+            let item = match get_dynamic_type(key, fields) {
+                Some((number, dynamic_type)) if is_compatible(config, &first_type.group_name, &dynamic_type.group_name) => DynamicTypeItem(number, dynamic_type),
+                Some(_) => return Err("Dynamic types can not be combined".to_string()),
+                None => return Err("Dynamic type not valid".to_string())
+            };
+
+            sum = match sum.calculate(config, true, &item, OperationType::Add) {
+                Some(sum) => sum,
+                None => return Err("Dynamic types can not be combined".to_string())
+            };
+        }
+
+        return Ok(sum.as_token_type());
+    }
+
+    Err("Dynamic type not valid".to_string())
+}
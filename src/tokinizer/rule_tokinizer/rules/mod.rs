@@ -12,3 +12,4 @@ pub mod cleanup_rules;
 pub mod date_rules;
 pub mod duration_rules;
 pub mod dynamic_type_rules;
+pub mod reference_rules;
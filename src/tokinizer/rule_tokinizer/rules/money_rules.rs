@@ -15,8 +15,9 @@ use crate::tokinizer::get_money;
 use crate::{tokinizer::Tokinizer, types::{TokenType}};
 use crate::tokinizer::TokenInfo;
 use crate::tools::do_divition;
+use crate::smartcalc::TraceStep;
 
-pub fn convert_money(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+pub fn convert_money(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
     if fields.contains_key("money") && fields.contains_key("currency") {
         let money = match get_money(config, "money", fields) {
             Some(money) => money,
@@ -28,16 +29,23 @@ pub fn convert_money(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<
             _ => return Err("Currency information not valid".to_string())
         };
 
-        let as_usd = match config.currency_rate.get(&money.get_currency()) {
-            Some(l_rate) => do_divition(money.get_price(), *l_rate),
+        let l_rate = match config.currency_rate.get(&money.get_currency()) {
+            Some(l_rate) => *l_rate,
             _ => return Err("Currency information not valid".to_string())
         };
 
-        let calculated_price = match config.currency_rate.get(&to_currency) {
-            Some(r_rate) => as_usd * r_rate,
+        let r_rate = match config.currency_rate.get(&to_currency) {
+            Some(r_rate) => *r_rate,
             _ => return Err("Currency information not valid".to_string())
         };
 
+        let calculated_price = do_divition(money.get_price(), l_rate) * r_rate;
+        tokinizer.session.add_trace(|| TraceStep::Conversion {
+            from: money.get_currency().code.to_string(),
+            to: to_currency.code.to_string(),
+            rate: do_divition(r_rate, l_rate)
+        });
+
         return Ok(TokenType::Money(calculated_price, to_currency));
     }
 
@@ -102,7 +110,7 @@ fn convert_money_4() {
     let tokens = execute("salary = 1000 dkk eur".to_string());
 
     assert_eq!(tokens.len(), 5);
-    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Text("salary".to_string())));
+    assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Text("salary".into())));
     assert_eq!(tokens[2].token_type.borrow().deref(), &Some(TokenType::Money(134.4772867837901, conf.get_currency("eur".to_string()).unwrap())));
 
 }
@@ -14,8 +14,9 @@ use crate::tokinizer::get_currency;
 use crate::tokinizer::get_number;
 use crate::tokinizer::get_number_or_price;
 use crate::tokinizer::get_percent;
+use crate::tokinizer::get_range;
 use crate::tokinizer::get_text;
-use crate::types::NumberType;
+use crate::types::{NumberType, RangeAggregate};
 use crate::{tokinizer::Tokinizer, types::{TokenType}};
 use crate::tokinizer::{TokenInfo};
 use crate::tools::do_divition;
@@ -217,3 +218,28 @@ fn number_type_convert_7() {
     let tokens = execute("100,0 to binary".to_string());
     assert_eq!(tokens[0].token_type.borrow().deref(), &Some(TokenType::Number(100.0, NumberType::Binary)));
 }
+
+pub fn aggregate_range(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    let aggregate = match fields.contains_key("average") {
+        true => RangeAggregate::Average,
+        false => RangeAggregate::Sum
+    };
+
+    if fields.contains_key("range") {
+        return match get_range("range", fields) {
+            Some((start, end)) => Ok(TokenType::Range(start, end, aggregate)),
+            None => Err("Range information not valid".to_string())
+        };
+    }
+
+    if fields.contains_key("start") && fields.contains_key("end") {
+        let (start, end) = match (get_number("start", fields), get_number("end", fields)) {
+            (Some(start), Some(end)) if start.fract() == 0.0 && end.fract() == 0.0 => (start as i64, end as i64),
+            _ => return Err("Range limits should be integer".to_string())
+        };
+
+        return Ok(TokenType::Range(start, end, aggregate));
+    }
+
+    Err("Range information not valid".to_string())
+}
@@ -18,6 +18,17 @@ use crate::{tokinizer::{TokenInfo, Tokinizer}, types::TokenType};
 
 use crate::tools::do_divition;
 
+/* Rule results are not calculated by the interpreter, so the line fails here instead of showing an infinite value */
+fn check_divisor(tokinizer: &Tokinizer, divisor: f64) -> core::result::Result<(), String> {
+    match divisor == 0.0 {
+        true => {
+            tokinizer.error.replace(Some("Division by zero".to_string()));
+            Err("Division by zero".to_string())
+        },
+        false => Ok(())
+    }
+}
+
 pub fn percent_calculator(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
     if fields.contains_key("p") && fields.contains_key("number") {
         let number = match get_number("number", fields) {
@@ -35,7 +46,7 @@ pub fn percent_calculator(_: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<
     Err("Percent not valid".to_string())
 }
 
-pub fn find_numbers_percent(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+pub fn find_numbers_percent(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
     if fields.contains_key("part") && fields.contains_key("total") {
         let total = match get_number_or_price(config, "total", fields) {
             Some(number) => number,
@@ -46,14 +57,15 @@ pub fn find_numbers_percent(config: &SmartCalcConfig, _: &Tokinizer, fields: &BT
             Some(number) => number,
             _ => return Err("Part number information not valid".to_string())
         };
-        
+
+        check_divisor(tokinizer, total)?;
         return Ok(TokenType::Percent(do_divition(part * 100.0, total)));
     }
 
     Err("Find percent not valid".to_string())
 }
 
-pub fn find_total_from_percent(config: &SmartCalcConfig, _: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+pub fn find_total_from_percent(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
     if fields.contains_key("number_part") && fields.contains_key("percent_part") {
         let number_part = match get_number_or_price(config, "number_part", fields) {
             Some(number) => number,
@@ -65,6 +77,7 @@ pub fn find_total_from_percent(config: &SmartCalcConfig, _: &Tokinizer, fields:
             _ => return Err("Percent part information not valid".to_string())
         };
 
+        check_divisor(tokinizer, percent_part)?;
         return Ok(match get_currency(config, "number_part", fields) {
             Some(currency) => TokenType::Money(do_divition(number_part * 100.0, percent_part), currency),
             None => TokenType::Number(do_divition(number_part * 100.0, percent_part), NumberType::Decimal)
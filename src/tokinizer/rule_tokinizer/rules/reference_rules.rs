@@ -0,0 +1,119 @@
+/*
+ * smartcalc v1.0.8
+ * Copyright (c) Erhan BARIS (Ruslan Ognyanov Asenov)
+ * Licensed under the GNU General Public License v2.0.
+ */
+
+use core::ops::Deref;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::collections::btree_map::BTreeMap;
+
+use crate::config::SmartCalcConfig;
+use crate::tokinizer::get_number;
+use crate::tokinizer::get_text;
+use crate::variable::VariableInfo;
+use crate::types::{NumberType, SmartCalcAstType};
+use crate::compiler::Interpreter;
+use crate::compiler::number::NumberItem;
+use crate::{tokinizer::Tokinizer, types::{TokenType}};
+use crate::tokinizer::TokenInfoStatus;
+use crate::tokinizer::{TokenInfo};
+
+pub fn line_reference(_: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    let line = match get_number("line", fields) {
+        Some(number) if number >= 1.0 && number.fract() == 0.0 => number as usize,
+        _ => return Err("Line information not valid".to_string())
+    };
+
+    /* Only lines above the current one are calculated, other references stay empty and fail instead of falling back to the number */
+    let result = tokinizer.session.line_result(line).unwrap_or_else(|| Rc::new(SmartCalcAstType::None));
+    Ok(TokenType::Variable(VariableInfo::from_result(format!("line{}", line), result)))
+}
+
+pub fn block_aggregate(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String> {
+    /* Only the lines with the label are aggregated, like total rent */
+    let label = get_text("label", fields);
+    let label = label.as_deref();
+
+    let (keyword, results) = if let Some(keyword) = fields.get("sum").or_else(|| fields.get("average")) {
+        (keyword, tokinizer.session.block_results(label))
+    } else if let Some(keyword) = fields.get("subtotal") {
+        (keyword, tokinizer.session.section_results(label))
+    } else if let Some(keyword) = fields.get("total") {
+        (keyword, tokinizer.session.document_results(label))
+    } else {
+        return Err("Aggregate information not valid".to_string());
+    };
+
+    /* Keyword on the left side of the assignment is a part of the variable name */
+    let is_variable_name = tokinizer.token_infos.iter().any(|token| token.start > keyword.start && matches!(token.token_type.borrow().deref(), Some(TokenType::Operator('='))));
+    if is_variable_name {
+        return Err("Aggregate keyword is a part of variable name".to_string());
+    }
+
+    /* Keyword is used as a function, like sum(1, 2) or [1, 2] sum */
+    let active_tokens = || tokinizer.token_infos.iter().filter(|token| token.status.get() == TokenInfoStatus::Active);
+    let previous = active_tokens().filter(|token| token.end <= keyword.start).max_by_key(|token| token.end);
+    let next = active_tokens().filter(|token| token.start >= keyword.end).min_by_key(|token| token.start);
+    if matches!(previous.map(|token| token.token_type.borrow().clone()), Some(Some(TokenType::Operator(']') | TokenType::Variable(_)))) || matches!(next.map(|token| token.token_type.borrow().clone()), Some(Some(TokenType::Operator('(')))) {
+        return Err("Aggregate keyword is a function call".to_string());
+    }
+
+    tokinizer.session.mark_aggregate_line();
+
+    /* Values of different kinds are not added together, numbers do not become money or durations */
+    let mut items = results.into_iter().filter_map(|result| match result.deref() {
+        SmartCalcAstType::Item(item) => Some(item.clone()),
+        _ => None
+    });
+
+    let first = match items.next() {
+        Some(item) => item,
+        None => return Err("No result found to aggregate".to_string())
+    };
+
+    let mut count = 1.0;
+    let mut total = Rc::new(SmartCalcAstType::Item(first.clone()));
+    for item in items {
+        let calculated = match item.type_name() == first.type_name() {
+            true => Interpreter::calculate_item(config, '+', total, Rc::new(SmartCalcAstType::Item(item.clone()))).ok(),
+            false => None
+        };
+
+        total = match calculated {
+            Some(calculated) => calculated,
+            None => {
+                let error = format!("Values can not be aggregated. ({}, {})", first.type_name().to_lowercase(), item.type_name().to_lowercase());
+                tokinizer.error.replace(Some(error.clone()));
+                return Err(error);
+            }
+        };
+        count += 1.0;
+    }
+
+    if fields.contains_key("average") {
+        total = Interpreter::calculate_item(config, '/', total, Rc::new(SmartCalcAstType::Item(Rc::new(NumberItem(count, NumberType::Decimal)))))?;
+    }
+
+    match total.deref() {
+        SmartCalcAstType::Item(item) => Ok(item.as_token_type()),
+        _ => Err("Aggregate result not valid".to_string())
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn line_reference_test() {
+    use crate::smartcalc::SmartCalc;
+    let calc = SmartCalc::default();
+    let results = calc.execute("en".to_string(), "10 * 3\n\n12\nline 1 + line 3\nline 4 * 2");
+    let outputs = results.lines.iter().map(|line| line.as_ref().and_then(|line| line.result.as_ref().ok()).map(|result| result.output.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Some("30".to_string()), None, Some("12".to_string()), Some("42".to_string()), Some("84".to_string())]);
+
+    let results = calc.execute("en".to_string(), "line 2 + 1\n5\nline 99\nline 2");
+    let outputs = results.lines.iter().map(|line| line.as_ref().unwrap().result.as_ref().map(|result| result.output.to_string()).map_err(|error| error.to_string())).collect::<alloc::vec::Vec<_>>();
+    assert_eq!(outputs, [Err("Line result not found".to_string()), Ok("5".to_string()), Err("Line result not found".to_string()), Ok("5".to_string())]);
+}
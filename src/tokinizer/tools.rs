@@ -5,15 +5,17 @@
  */
 
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec::Vec;
 use alloc::collections::btree_map::BTreeMap;
 use chrono::NaiveDateTime;
-use chrono::Utc;
+use lazy_static::*;
+use regex::Regex;
 use crate::compiler::date::DateItem;
 use crate::compiler::date_time::DateTimeItem;
 use crate::compiler::duration::DurationItem;
-use crate::compiler::number::NumberItem;
 use crate::compiler::percent::PercentItem;
 use crate::compiler::dynamic_type::DynamicTypeItem;
 use crate::compiler::DataItem;
@@ -30,10 +32,70 @@ use crate::types::{TokenType, SmartCalcAstType};
 use crate::tokinizer::TokenInfo;
 use crate::compiler::money::MoneyItem;
 
-pub fn read_currency(config: &SmartCalcConfig, currency: &'_ str) -> Option<Rc<CurrencyInfo>> {
+lazy_static! {
+    static ref UNICODE_DIGIT: Regex = Regex::new(r"^\p{Nd}$").unwrap();
+}
+
+fn is_unicode_digit(ch: char) -> bool {
+    let mut buffer = [0; 4];
+    UNICODE_DIGIT.is_match(ch.encode_utf8(&mut buffer))
+}
+
+/* Unicode decimal digits are defined in blocks of ten, starting from zero */
+fn unicode_digit_value(ch: char) -> Option<u32> {
+    if ch.is_ascii() || !is_unicode_digit(ch) {
+        return None;
+    }
+
+    let mut value = 0;
+    let mut code = ch as u32;
+    while let Some(previous) = code.checked_sub(1).and_then(char::from_u32) {
+        if !is_unicode_digit(previous) {
+            break;
+        }
+
+        value += 1;
+        code -= 1;
+    }
+
+    Some(value % 10)
+}
+
+/* Digits are replaced char by char, so char based positions are not changed.
+   Returned offsets map each byte of the normalized text to the original text, empty if nothing is replaced */
+pub fn normalize_digits(data: &str) -> (String, Vec<usize>) {
+    if data.is_ascii() {
+        return (data.to_string(), Vec::new());
+    }
+
+    let mut normalized = String::with_capacity(data.len());
+    let mut offsets = Vec::with_capacity(data.len() + 1);
+    let mut replaced = false;
+
+    for (position, ch) in data.char_indices() {
+        let ch = match unicode_digit_value(ch).and_then(|value| char::from_digit(value, 10)) {
+            Some(digit) => {
+                replaced = true;
+                digit
+            },
+            None => ch
+        };
+
+        offsets.extend(core::iter::repeat_n(position, ch.len_utf8()));
+        normalized.push(ch);
+    }
+
+    offsets.push(data.len());
+    match replaced {
+        true => (normalized, offsets),
+        false => (normalized, Vec::new())
+    }
+}
+
+pub fn read_currency(config: &SmartCalcConfig, currency: &'_ str) -> Option<Arc<CurrencyInfo>> {
     match config.currency_alias.get(&currency.to_lowercase()) {
         Some(symbol) => Some(symbol.clone()),
-        _ => config.currency.get(&currency.to_lowercase()).cloned()
+        _ => config.currency.get(&currency.to_lowercase()).filter(|currency| !currency.symbol_only).cloned()
     }
 }
 
@@ -44,7 +106,7 @@ pub fn get_number(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) ->
                 TokenType::Number(number, _) => Some(*number),
                 TokenType::Variable(variable) => {
                     match variable.data.borrow().deref().deref() {
-                        SmartCalcAstType::Item(item) => item.as_any().downcast_ref::<NumberItem>().map(|number| number.get_underlying_number()),
+                        SmartCalcAstType::Item(item) if item.type_name() == "NUMBER" => Some(item.get_underlying_number()),
                         _ => None
                     }
                 },
@@ -143,7 +205,7 @@ pub fn get_text(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> O
     }
 }
 
-pub fn get_dynamic_type(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> Option<(f64, Rc<DynamicType>)> {
+pub fn get_dynamic_type(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> Option<(f64, Arc<DynamicType>)> {
     return match &fields.get(field_name) {
         Some(data) =>match &data.token_type.borrow().deref() {
             Some(token) => match &token {
@@ -172,6 +234,16 @@ pub fn get_timezone(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>)
     }
 }
 
+pub fn get_range(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> Option<(i64, i64)> {
+    match fields.get(field_name) {
+        Some(data) => match &data.token_type.borrow().deref() {
+            Some(TokenType::Range(start, end, _)) => Some((*start, *end)),
+            _ => None
+        },
+        _ => None
+    }
+}
+
 pub fn get_month(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> Option<u32> {
     return match &fields.get(field_name) {
         Some(data) =>match &data.token_type.borrow().deref() {
@@ -194,9 +266,10 @@ pub fn get_month(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) ->
 pub fn get_number_or_time(config: &SmartCalcConfig, field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> Option<(NaiveDateTime, TimeOffset)> {
     match get_number(field_name, fields) {
         Some(number) => {
-            let date = Utc::now().naive_local().date();
-            let time = chrono::NaiveTime::from_hms(number as u32, 0, 0);
-            Some((NaiveDateTime::new(date, time), config.get_time_offset()))
+            let date = config.get_today();
+            let time = chrono::NaiveTime::from_hms_opt(number as u32, 0, 0)?;
+            let time_offset = config.get_time_offset();
+            Some((NaiveDateTime::new(date, time) - Duration::minutes(time_offset.offset as i64), time_offset))
         },
         None => get_time(field_name, fields)
     }
@@ -236,7 +309,7 @@ pub fn get_money(_: &SmartCalcConfig, field_name: &str, fields: &BTreeMap<String
     }
 }
 
-pub fn get_currency(config: &SmartCalcConfig, field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> Option<Rc<CurrencyInfo>> {
+pub fn get_currency(config: &SmartCalcConfig, field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -> Option<Arc<CurrencyInfo>> {
     match &fields.get(field_name) {
         Some(data) => match &data.token_type.borrow().deref() {
             Some(token) => match &token {
@@ -274,3 +347,13 @@ pub fn get_percent(field_name: &str, fields: &BTreeMap<String, Rc<TokenInfo>>) -
         _ => None
     }
 }
+
+#[cfg(test)]
+#[test]
+fn normalize_digits_test() {
+    assert_eq!(normalize_digits("12 + 3"), ("12 + 3".to_string(), Vec::new()));
+    assert_eq!(normalize_digits("ü 5"), ("ü 5".to_string(), Vec::new()));
+    assert_eq!(normalize_digits("１２"), ("12".to_string(), alloc::vec![0, 3, 6]));
+    assert_eq!(normalize_digits("١٠ x ۹").0, "10 x 9".to_string());
+    assert_eq!(normalize_digits("𝟘𝟗").0, "09".to_string());
+}
@@ -6,13 +6,10 @@
 
 use alloc::string::{ToString, String};
 use crate::config::SmartCalcConfig;
- 
+
 pub fn do_divition(left: f64, right: f64) -> f64 {
-    let mut calculation = left / right;
-    if calculation.is_infinite() || calculation.is_nan() {
-        calculation = 0.0;
-    }
-    calculation
+    /* Division by zero is not hidden, the interpreter reports the infinite and NaN results */
+    left / right
 }
 
 pub fn parse_timezone<'t>(config: &SmartCalcConfig, capture: &regex::Captures<'t>) -> Option<(String, i32)> {
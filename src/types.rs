@@ -7,6 +7,7 @@
 use alloc::vec::Vec;
 use core::result::Result;
 use alloc::rc::Rc;
+use alloc::sync::Arc;
 use alloc::string::ToString;
 use alloc::string::String;
 use alloc::format;
@@ -20,6 +21,7 @@ use crate::compiler::DataItem;
 use crate::compiler::dynamic_type::DynamicTypeItem;
 use crate::config::DynamicType;
 use crate::config::SmartCalcConfig;
+use crate::interner::Symbol;
 
 use crate::tokinizer::TokenInfoStatus;
 use crate::tokinizer::{TokenInfo, Tokinizer};
@@ -28,13 +30,13 @@ use crate::variable::VariableInfo;
 pub type ExpressionFunc     = fn(config: &SmartCalcConfig, tokinizer: &Tokinizer, fields: &BTreeMap<String, Rc<TokenInfo>>) -> core::result::Result<TokenType, String>;
 pub type AstResult          = Result<SmartCalcAstType, (&'static str, u16, u16)>;
 
-pub struct Money(pub f64, pub Rc<CurrencyInfo>);
+pub struct Money(pub f64, pub Arc<CurrencyInfo>);
 impl Money {
     pub fn get_price(&self) -> f64 {
         self.0
     }
     
-    pub fn get_currency(&self) -> Rc<CurrencyInfo> {
+    pub fn get_currency(&self) -> Arc<CurrencyInfo> {
         self.1.clone()
     }
 }
@@ -43,24 +45,22 @@ impl Money {
 #[derive(Clone)]
 #[derive(Debug)]
 pub enum FieldType {
-    Text(String, Option<String>),
+    Text(String, Option<Symbol>),
     DateTime(String),
     Date(String),
     Time(String),
     Money(String),
     Percent(String),
     Number(String),
-    Group(String, Vec<String>),
+    Group(String, Vec<Symbol>),
     TypeGroup(Vec<String>, String),
     Month(String),
     Duration(String),
     Timezone(String),
-    DynamicType(String, Option<String>)
+    DynamicType(String, Option<String>),
+    Range(String)
 }
 
-unsafe impl Send for FieldType {}
-unsafe impl Sync for FieldType {}
-
 impl FieldType {
     pub fn type_name(&self) -> String {
         match self {
@@ -76,7 +76,8 @@ impl FieldType {
             FieldType::Month(_) => "MONTH".to_string(),
             FieldType::Duration(_) => "DURATION".to_string(),
             FieldType::Timezone(_) => "TIMEZONE".to_string(),
-            FieldType::DynamicType(_, _) => "DYNAMIC_TYPE".to_string()
+            FieldType::DynamicType(_, _) => "DYNAMIC_TYPE".to_string(),
+            FieldType::Range(_) => "RANGE".to_string()
         }
     }
 }
@@ -97,6 +98,7 @@ impl PartialEq for FieldType {
             (FieldType::Duration(l),   FieldType::Duration(r)) => r == l,
             (FieldType::Group(_, l),   FieldType::Group(_, r)) => r == l,
             (FieldType::DynamicType(l, _),   FieldType::DynamicType(r, _)) => r == l,
+            (FieldType::Range(l),   FieldType::Range(r)) => r == l,
             (FieldType::TypeGroup(l1, l2),   FieldType::TypeGroup(r1, r2)) => r1 == l1 && r2 == l2,
             (_, _) => false,
         }
@@ -123,7 +125,11 @@ pub struct CurrencyInfo {
     pub space_between_amount_and_symbol: bool,
 
     #[serde(alias = "decimalDigits")]
-    pub decimal_digits: u8
+    pub decimal_digits: u8,
+
+    /* Code is not a known word, currency is only read from its symbol */
+    #[serde(default, alias = "symbolOnly")]
+    pub symbol_only: bool
 }
 
 
@@ -151,13 +157,13 @@ impl Ord for CurrencyInfo {
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TimeOffset {
     pub name: String,
     pub offset: i32
 }
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum NumberType {
     Decimal,
     Octal,
@@ -166,22 +172,43 @@ pub enum NumberType {
     Raw
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RangeAggregate {
+    Sum,
+    Average
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonType {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual
+}
+
 #[derive(Debug, Clone)]
 pub enum TokenType {
     Number(f64, NumberType),
-    Text(String),
+    Text(Symbol),
     Time(NaiveDateTime, TimeOffset),
     Date(NaiveDate, TimeOffset),
     DateTime(NaiveDateTime, TimeOffset),
     Operator(char),
-    Field(Rc<FieldType>),
+    Field(Arc<FieldType>),
     Percent(f64),
-    DynamicType(f64, Rc<DynamicType>),
-    Money(f64, Rc<CurrencyInfo>),
+    DynamicType(f64, Arc<DynamicType>),
+    Money(f64, Arc<CurrencyInfo>),
     Variable(Rc<VariableInfo>),
     Month(u32),
     Duration(Duration),
-    Timezone(String, i32)
+    Timezone(String, i32),
+    Bool(bool),
+    TextValue(String),
+    Range(i64, i64, RangeAggregate)
 }
 
 
@@ -189,7 +216,7 @@ impl PartialEq for TokenType {
     fn eq(&self, other: &Self) -> bool {
         match (&self, &other) {
             (TokenType::Timezone(l_value, l_type),     TokenType::Timezone(r_value, r_type)) => *l_value == *r_value && *l_type == *r_type,
-            (TokenType::Text(l_value),     TokenType::Text(r_value)) => l_value.to_lowercase() == r_value.to_lowercase(),
+            (TokenType::Text(l_value),     TokenType::Text(r_value)) => l_value == r_value,
             (TokenType::Number(l_value, _),   TokenType::Number(r_value, _)) => l_value == r_value,
             (TokenType::Percent(l_value),  TokenType::Percent(r_value)) => l_value == r_value,
             (TokenType::Operator(l_value), TokenType::Operator(r_value)) => l_value == r_value,
@@ -199,7 +226,11 @@ impl PartialEq for TokenType {
             (TokenType::Month(l_value),     TokenType::Month(r_value)) => l_value == r_value,
             (TokenType::Duration(l_value),     TokenType::Duration(r_value)) => l_value == r_value,
             (TokenType::Date(l_value, l_tz),     TokenType::Date(r_value, r_tz)) => l_value == r_value && l_tz == r_tz,
+            (TokenType::DateTime(l_value, l_tz),     TokenType::DateTime(r_value, r_tz)) => l_value == r_value && l_tz == r_tz,
             (TokenType::Field(l_value),    TokenType::Field(r_value)) => l_value.deref() == r_value.deref(),
+            (TokenType::Bool(l_value),     TokenType::Bool(r_value)) => l_value == r_value,
+            (TokenType::TextValue(l_value),     TokenType::TextValue(r_value)) => l_value == r_value,
+            (TokenType::Range(l_start, l_end, l_aggregate),     TokenType::Range(r_start, r_end, r_aggregate)) => l_start == r_start && l_end == r_end && l_aggregate == r_aggregate,
             (_, _)  => false
         }
     }
@@ -233,7 +264,10 @@ impl ToString for TokenType {
             TokenType::Variable(var) => var.to_string(),
             TokenType::Month(month) => month.to_string(),
             TokenType::Duration(duration) => duration.to_string(),
-            TokenType::Timezone(timezone, offset) => format!("{} {:?}", timezone, offset)
+            TokenType::Timezone(timezone, offset) => format!("{} {:?}", timezone, offset),
+            TokenType::Bool(value) => value.to_string(),
+            TokenType::TextValue(text) => text.to_string(),
+            TokenType::Range(start, end, _) => format!("{}..{}", start, end)
         }
     }
 }
@@ -255,7 +289,10 @@ impl TokenType {
             TokenType::Month(_) => "MONTH".to_string(),
             TokenType::Duration(_) => "DURATION".to_string(),
             TokenType::Timezone(_, _) => "TIMEZONE".to_string(),
-            TokenType::DynamicType(_, _) => "DYNAMIC_TYPE".to_string()
+            TokenType::DynamicType(_, _) => "DYNAMIC_TYPE".to_string(),
+            TokenType::Bool(_) => "BOOL".to_string(),
+            TokenType::TextValue(_) => "TEXT_VALUE".to_string(),
+            TokenType::Range(_, _, _) => "RANGE".to_string()
         }
     }
 
@@ -265,57 +302,19 @@ impl TokenType {
             (FieldType::Percent(_), TokenType::Percent(_)) => true,
             (FieldType::Timezone(_),  TokenType::Timezone(_, _)) => true,
             (FieldType::Number(_),  TokenType::Number(_, _)) => true,
-            (FieldType::Text(_, expected),    TokenType::Text(text) ) => expected.as_ref().map_or(true, |v| v.to_lowercase() == text.to_lowercase()),
+            (FieldType::Text(_, expected),    TokenType::Text(text) ) => expected.as_ref().map_or(true, |v| v == text),
             (FieldType::Time(_),    TokenType::Time(_, _)) => true,
             (FieldType::DateTime(_),    TokenType::DateTime(_, _)) => true,
             (FieldType::Date(_),    TokenType::Date(_, _)) => true,
             (FieldType::Money(_),   TokenType::Money(_, _)) => true,
             (FieldType::Month(_),   TokenType::Month(_)) => true,
             (FieldType::Duration(_),   TokenType::Duration(_)) => true,
-            (FieldType::Group(_, items),   TokenType::Text(text)) => items.iter().any(|item| item.to_lowercase() == text.to_lowercase()),
+            (FieldType::Range(_),   TokenType::Range(_, _, _)) => true,
+            (FieldType::Group(_, items),   TokenType::Text(text)) => items.contains(text),
             (FieldType::TypeGroup(types, _), right_ast) => types.contains(&right_ast.type_name()),
             (_, _) => false,
         }
     }
-
-    pub fn variable_compare(left: &TokenInfo, right: Rc<SmartCalcAstType>) -> bool {
-        match &left.token_type.borrow().deref() {
-            Some(token) => match (&token, right.deref()) {
-                (TokenType::Text(l_value), SmartCalcAstType::Symbol(r_value)) => l_value.deref().to_lowercase() == r_value.to_lowercase(),
-                (TokenType::Timezone(l_value, l_type), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(l_value.clone(), *l_type)),
-                (TokenType::Number(l_value, _), SmartCalcAstType::Item(r_value)) => r_value.is_same(l_value),
-                (TokenType::Percent(l_value), SmartCalcAstType::Item(r_value)) => r_value.is_same(l_value),
-                (TokenType::Duration(l_value), SmartCalcAstType::Item(r_value)) => r_value.is_same(l_value),
-                (TokenType::Time(l_value, l_tz), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(*l_value, l_tz.clone())),
-                (TokenType::Money(l_value, l_symbol), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(*l_value, l_symbol.clone())),
-                (TokenType::Date(l_value, l_tz), SmartCalcAstType::Item(r_value)) => r_value.is_same(&(*l_value, l_tz.clone())),
-                (TokenType::Field(l_value), _) => right.field_compare(l_value.deref()),
-                (_, _) => false
-            },
-            _ => false
-        }
-    }
-
-    pub fn get_field_name(token: &TokenInfo) -> Option<String> {
-        match &token.token_type.borrow().deref() {
-            Some(TokenType::Field(field)) =>  match field.deref() {
-                FieldType::Text(field_name, _)    => Some(field_name.to_string()),
-                FieldType::DateTime(field_name)    => Some(field_name.to_string()),
-                FieldType::Date(field_name)    => Some(field_name.to_string()),
-                FieldType::Time(field_name)    => Some(field_name.to_string()),
-                FieldType::Money(field_name)   => Some(field_name.to_string()),
-                FieldType::Percent(field_name) => Some(field_name.to_string()),
-                FieldType::Number(field_name)  => Some(field_name.to_string()),
-                FieldType::Month(field_name)  => Some(field_name.to_string()),
-                FieldType::Duration(field_name)  => Some(field_name.to_string()),
-                FieldType::Group(field_name, _)  => Some(field_name.to_string()),
-                FieldType::TypeGroup(_, field_name) => Some(field_name.to_string()),
-                FieldType::Timezone(field_name) => Some(field_name.to_string()),
-                FieldType::DynamicType(field_name, _) => Some(field_name.to_string())
-            },
-            _ => None
-        }
-    }
 }
 
 pub fn find_location<T: PartialEq<U>, U>(tokens: &[Rc<T>], rule_tokens: &[Rc<U>]) -> Option<usize> {
@@ -350,9 +349,13 @@ impl core::cmp::PartialEq<TokenType> for TokenInfo {
             return false
         }
 
+        if self.status.get() == TokenInfoStatus::Removed {
+            return false;
+        }
+
         match &self.token_type.borrow().deref() {
             Some(l_token) => match (&l_token, &other) {
-                (TokenType::Text(l_value), TokenType::Text(r_value)) => l_value.to_lowercase() == r_value.to_lowercase(),
+                (TokenType::Text(l_value), TokenType::Text(r_value)) => l_value == r_value,
                 (TokenType::Number(l_value, _),   TokenType::Number(r_value, _)) => l_value == r_value,
                 (TokenType::Percent(l_value),  TokenType::Percent(r_value)) => l_value == r_value,
                 (TokenType::Operator(l_value), TokenType::Operator(r_value)) => l_value == r_value,
@@ -383,7 +386,7 @@ impl PartialEq for TokenInfo {
 
         match (&self.token_type.borrow().deref(), &other.token_type.borrow().deref()) {
             (Some(l_token), Some(r_token)) => match (&l_token, &r_token) {
-                (TokenType::Text(l_value), TokenType::Text(r_value)) => l_value.to_lowercase() == r_value.to_lowercase(),
+                (TokenType::Text(l_value), TokenType::Text(r_value)) => l_value == r_value,
                 (TokenType::Number(l_value, _),   TokenType::Number(r_value, _)) => l_value == r_value,
                 (TokenType::Percent(l_value),  TokenType::Percent(r_value)) => l_value == r_value,
                 (TokenType::Operator(l_value), TokenType::Operator(r_value)) => l_value == r_value,
@@ -416,29 +419,93 @@ impl CharTraits for char {
     }
 }
 
+/// Syntax tree of a line, the interpreter replaces the nodes with `Item` results.
 #[repr(C)]
 #[derive(Clone)]
 #[derive(Debug)]
 pub enum SmartCalcAstType {
+    /// Empty line or statement.
     None,
-    Field(Rc<FieldType>),
+    /// Field of a rule pattern.
+    Field(Arc<FieldType>),
+    /// Calculated value.
     Item(Rc<dyn DataItem>),
+    /// Month number, 1 is January.
     Month(u32),
+    /// Calculation with one of `+ - * / ^`.
     Binary {
         left: Rc<SmartCalcAstType>,
         operator: char,
         right: Rc<SmartCalcAstType>
     },
+    /// `+` or `-` before an expression.
     PrefixUnary(char, Rc<SmartCalcAstType>),
+    /// Expression assigned to a variable.
     Assignment {
         variable: Rc<VariableInfo>,
         expression: Rc<SmartCalcAstType>
     },
     Symbol(String),
-    Variable(Rc<VariableInfo>)
+    /// Variable defined on a previous line, its value is in `VariableInfo::data`.
+    Variable(Rc<VariableInfo>),
+    /// Function call like `sqrt(16)`.
+    Call {
+        name: String,
+        arguments: Vec<Rc<SmartCalcAstType>>
+    },
+    /// Sum or average of the integers between two limits.
+    Range {
+        start: i64,
+        end: i64,
+        aggregate: RangeAggregate
+    },
+    /// List like `[1, 2, 3]`.
+    List(Vec<Rc<SmartCalcAstType>>),
+    /// Comparison evaluated to a bool item.
+    Comparison {
+        left: Rc<SmartCalcAstType>,
+        operator: ComparisonType,
+        right: Rc<SmartCalcAstType>
+    }
+}
+
+/// Visitor over a syntax tree, see `SmartCalcAstType::walk`.
+///
+/// Closures taking a node and returning a bool can be used as visitors.
+pub trait AstVisitor {
+    /// Called for every node, the children of the node are skipped when false is returned.
+    fn visit(&mut self, ast: &SmartCalcAstType) -> bool;
+}
+
+impl<F: FnMut(&SmartCalcAstType) -> bool> AstVisitor for F {
+    fn visit(&mut self, ast: &SmartCalcAstType) -> bool {
+        self(ast)
+    }
 }
 
 impl SmartCalcAstType {
+    /// Direct child nodes, values of the variables are not included.
+    pub fn children(&self) -> Vec<Rc<SmartCalcAstType>> {
+        match self {
+            SmartCalcAstType::Binary { left, operator: _, right } => alloc::vec![left.clone(), right.clone()],
+            SmartCalcAstType::Comparison { left, operator: _, right } => alloc::vec![left.clone(), right.clone()],
+            SmartCalcAstType::PrefixUnary(_, expression) => alloc::vec![expression.clone()],
+            SmartCalcAstType::Assignment { variable: _, expression } => alloc::vec![expression.clone()],
+            SmartCalcAstType::Call { name: _, arguments } => arguments.clone(),
+            SmartCalcAstType::List(items) => items.clone(),
+            _ => Vec::new()
+        }
+    }
+
+    /// Visit the node and its children in depth first order.
+    pub fn walk<V: AstVisitor + ?Sized>(&self, visitor: &mut V) {
+        if visitor.visit(self) {
+            for child in self.children().iter() {
+                child.walk(visitor);
+            }
+        }
+    }
+
     pub fn type_name(&self) -> String {
         match self {
             SmartCalcAstType::None => "NONE".to_string(),
@@ -456,7 +523,22 @@ impl SmartCalcAstType {
                 expression: _
             } => "ASSIGNMENT".to_string(),
             SmartCalcAstType::Symbol(_) => "SYMBOL".to_string(),
-            SmartCalcAstType::Variable(variable) => variable.data.borrow().type_name()
+            SmartCalcAstType::Variable(variable) => variable.data.borrow().type_name(),
+            SmartCalcAstType::Call {
+                name: _,
+                arguments: _
+            } => "CALL".to_string(),
+            SmartCalcAstType::Range {
+                start: _,
+                end: _,
+                aggregate: _
+            } => "RANGE".to_string(),
+            SmartCalcAstType::List(_) => "LIST".to_string(),
+            SmartCalcAstType::Comparison {
+                left: _,
+                operator: _,
+                right: _
+            } => "COMPARISON".to_string()
         }
     }
 
@@ -468,7 +550,7 @@ impl SmartCalcAstType {
             }),
             (FieldType::Percent(_), SmartCalcAstType::Item(item)) => item.type_name() == "PERCENT",
             (FieldType::Number(_), SmartCalcAstType::Item(item)) => item.type_name() == "NUMBER",
-            (FieldType::Text(_, expected), SmartCalcAstType::Symbol(symbol)) => expected.as_ref().map_or(true, |v| v.to_lowercase() == symbol.to_lowercase()),
+            (FieldType::Text(_, expected), SmartCalcAstType::Symbol(symbol)) => expected.as_ref().map_or(true, |v| v.matches(symbol)),
             (FieldType::Time(_), SmartCalcAstType::Item(item)) => item.type_name() == "TIME",
             (FieldType::Money(_),   SmartCalcAstType::Item(item)) => item.type_name() == "MONEY",
             (FieldType::Month(_),   SmartCalcAstType::Month(_)) => true,
@@ -4,16 +4,22 @@
  * Licensed under the GNU General Public License v2.0.
  */
 
-use core::cell::{RefCell, Cell};
+use core::cell::RefCell;
 use core::ops::Deref;
-use alloc::{string::{String, ToString}, vec::Vec, rc::Rc};
+use alloc::{string::{String, ToString}, vec, vec::Vec, rc::Rc, collections::BTreeMap};
 use crate::types::find_location;
+use crate::interner::Symbol;
 use crate::{types::TokenType, SmartCalcAstType, tokinizer::{Tokinizer, TokenInfoStatus, TokenInfo}, UiTokenType};
 
 #[derive(Debug)]
 pub struct VariableInfo {
     pub tokens: Vec<Rc<TokenType>>,
-    pub data: RefCell<Rc<SmartCalcAstType>>
+    pub data: RefCell<Rc<SmartCalcAstType>>,
+    pub name: String,
+    pub line: usize,
+    pub start: usize,
+    pub end: usize,
+    pub type_lock: RefCell<Option<String>>
 }
 
 impl PartialEq for VariableInfo {
@@ -22,8 +28,20 @@ impl PartialEq for VariableInfo {
     }
 }
 
-unsafe impl Send for VariableInfo {}
-unsafe impl Sync for VariableInfo {}
+impl VariableInfo {
+    /* Read only variable for already calculated values, like previous line results */
+    pub(crate) fn from_result(name: String, data: Rc<SmartCalcAstType>) -> Rc<VariableInfo> {
+        Rc::new(VariableInfo {
+            tokens: vec![Rc::new(TokenType::Text(Symbol::new(&name)))],
+            data: RefCell::new(data),
+            name,
+            line: 0,
+            start: 0,
+            end: 0,
+            type_lock: RefCell::new(None)
+        })
+    }
+}
 
 
 impl ToString for VariableInfo {
@@ -33,7 +51,6 @@ impl ToString for VariableInfo {
 }
 
 pub fn update_token_variables(tokenizer: &mut Tokinizer) {
-    let session = tokenizer.session;
     let mut token_start_index = 0;
     tokenizer.ui_tokens.sort();
 
@@ -41,12 +58,32 @@ pub fn update_token_variables(tokenizer: &mut Tokinizer) {
         if let Some(TokenType::Operator('=')) = &token.token_type.borrow().deref() {
             token_start_index = index as usize + 1;
 
-            tokenizer.ui_tokens.update_tokens(0, tokenizer.token_infos[index - 1].end, UiTokenType::VariableDefination);                        
+            tokenizer.ui_tokens.update_tokens(tokenizer.token_infos[0].start, tokenizer.token_infos[index - 1].end, UiTokenType::VariableDefination);
             break;
         }
     }
 
-   let mut update_tokens = true;
+    replace_variables(tokenizer, token_start_index, false);
+}
+
+/* Variables named like units, currencies or keywords are replaced after the rules, so the rules still see the original words */
+pub fn update_reserved_token_variables(tokenizer: &mut Tokinizer) -> bool {
+    let token_start_index = tokenizer.token_infos.iter()
+        .position(|token| token.status.get() == TokenInfoStatus::Active && matches!(token.token_type.borrow().deref(), Some(TokenType::Operator('='))))
+        .map_or(0, |index| index + 1);
+
+    replace_variables(tokenizer, token_start_index, true)
+}
+
+fn replace_variables(tokenizer: &mut Tokinizer, token_start_index: usize, reserved: bool) -> bool {
+    let config = tokenizer.config;
+    let language = tokenizer.language.to_string();
+    let variables = tokenizer.session.visible_variables().into_iter()
+        .filter(|(_, variable)| config.is_reserved_word(&language, &variable.name) == reserved)
+        .collect::<BTreeMap<_, _>>();
+
+    let mut replaced = false;
+    let mut update_tokens = true;
 
     while update_tokens {
         let mut found = false;
@@ -56,7 +93,7 @@ pub fn update_token_variables(tokenizer: &mut Tokinizer) {
 
         update_tokens            = false;
 
-        for (variable_name, variable) in session.variables.borrow().iter() {
+        for (variable_name, variable) in variables.iter() {
             if let Some(start_index) = find_location(&tokenizer.token_infos[token_start_index..].to_vec(), &variable.tokens) {
                 if (start_index == closest_variable && variable_size < variable.tokens.len()) || (start_index < closest_variable) {
                     closest_variable = start_index;
@@ -75,25 +112,15 @@ pub fn update_token_variables(tokenizer: &mut Tokinizer) {
 
             tokenizer.ui_tokens.update_tokens(text_start_position, text_end_position, UiTokenType::VariableUse);
 
-            let buffer_length: usize = tokenizer.token_infos[remove_start_index..remove_end_index].iter().map(|s| s.original_text.len()).sum();
-            let mut original_text = String::with_capacity(buffer_length);
-
-            for token in tokenizer.token_infos[remove_start_index..remove_end_index].iter() {
-                original_text.push_str(&token.original_text.to_string());
-            }
-
             tokenizer.token_infos.drain(remove_start_index..remove_end_index);
             
-            let token_type = RefCell::new(Some(TokenType::Variable(session.variables.borrow()[&name].clone())));
-            
-            tokenizer.token_infos.insert(remove_start_index, Rc::new(TokenInfo {
-                start: text_start_position as usize,
-                end: text_end_position as usize,
-                token_type,
-                original_text: original_text.to_string(),
-                status: Cell::new(TokenInfoStatus::Active)
-            }));
+            tokenizer.session.track_variable_use(&variables[&name]);
+            let token_type = Some(TokenType::Variable(variables[&name].clone()));
+            tokenizer.token_infos.insert(remove_start_index, Rc::new(TokenInfo::new(text_start_position, text_end_position, token_type, tokenizer.data.clone())));
             update_tokens = true;
+            replaced = true;
         }
     }
+
+    replaced
 }